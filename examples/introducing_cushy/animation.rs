@@ -13,7 +13,7 @@ fn random_color() -> Color {
     Color::new(rng.gen(), rng.gen(), rng.gen(), 255)
 }
 
-// ANCHOR_START
+// ANCHOR_START: animation
 pub fn animation() -> impl MakeWidget {
     let color = Dynamic::new(random_color());
     let hex = color.map_each(|color| format!("{color:?}"));
@@ -35,4 +35,4 @@ pub fn animation() -> impl MakeWidget {
         .and(hex)
         .into_rows()
 }
-// ANCHOR_END
+// ANCHOR_END: animation
@@ -7,7 +7,7 @@ use cushy::widgets::Space;
 use figures::units::Lp;
 use figures::Size;
 
-// ANCHOR_START
+// ANCHOR_START: color-pickers
 pub fn color_pickers() -> impl MakeWidget {
     let color = Dynamic::new(Color::RED);
     let color_as_string = color.map_each(|color| format!("{color:?}"));
@@ -29,4 +29,4 @@ pub fn color_pickers() -> impl MakeWidget {
         )
         .into_columns()
 }
-// ANCHOR_END
+// ANCHOR_END: color-pickers
@@ -1,10 +1,8 @@
-use std::borrow::Cow;
-
 use cushy::kludgine::{include_texture, wgpu};
 use cushy::styles::components::PrimaryColor;
 use cushy_show::{
-    code, expand_weighted, fit, h1, h3, h5, hr, hsplit, hstack, list, stack, vsplit, Element,
-    LazyWidget, Show, Slide, SlideCount, SlideIndex, SlideMeta,
+    code, expand_weighted, fit, h1, h3, h5, hr, hsplit, list, placeholder, rich, slide_number,
+    stack, vsplit, Code, LazyWidget, Show, Slide, SlideMeta,
 };
 
 mod animation;
@@ -12,9 +10,24 @@ mod color;
 mod counter;
 
 fn main() {
-    Show::default()
+    let show = Show::default()
+        .with_template(
+            "content",
+            vsplit((
+                fit(stack((placeholder("title"), hr()))),
+                placeholder("body"),
+                fit(stack((
+                    hr(),
+                    hsplit((
+                        fit(h5("Introducing Cushy")),
+                        "",
+                        fit(slide_number("{n} / {total}")),
+                    )),
+                ))),
+            )),
+        )
         .with(Slide::new(
-            SlideMeta::new("title").with_next_slide("01"),
+            SlideMeta::new("title").with_next_slide("01").unnumbered(),
             stack((
                 h1("Introducing Cushy").text_color(PrimaryColor),
                 h3("A reactive GUI framework for Rust"),
@@ -29,7 +42,7 @@ fn main() {
                 "Graphical User Interface for Rust",
                 "wgpu-based rendering",
                 "No existing widget toolchains",
-                "Reactive data model",
+                rich("**Reactive** data model"),
                 "Fun",
             )),
         ))
@@ -42,14 +55,14 @@ fn main() {
                 LazyWidget::new(counter::counter),
             )),
         ))
-        // ANCHOR_START
+        // ANCHOR_START: what-is-this
         .with(content_slide(
             "03",
             "What is this?",
             Some("04"),
             hsplit((
                 list((
-                    "cushy-show: Interactive presentations",
+                    rich("`cushy-show`: Interactive presentations"),
                     "Written in ~12 hours",
                     "Powered by Cushy",
                 )),
@@ -57,12 +70,12 @@ fn main() {
                     3,
                     stack((
                         include_texture!("./idea.png", wgpu::FilterMode::Linear).unwrap(),
-                        code("rs", anchored_range(include_str!("./main.rs"))),
+                        Code::new("rs", include_str!("./main.rs")).anchor("what-is-this"),
                     )),
                 ),
             )),
         ))
-        // ANCHOR_END
+        // ANCHOR_END: what-is-this
         .with(content_slide(
             "04",
             "Animations",
@@ -70,7 +83,7 @@ fn main() {
             hsplit((
                 expand_weighted(
                     2,
-                    code("rs", anchored_range(include_str!("./animation.rs"))),
+                    Code::new("rs", include_str!("./animation.rs")).anchor("animation"),
                 ),
                 LazyWidget::new(animation::animation),
             )),
@@ -80,7 +93,17 @@ fn main() {
             "Bidirectional Bindings",
             Some("06"),
             hsplit((
-                expand_weighted(3, code("rs", anchored_range(include_str!("./color.rs")))),
+                expand_weighted(
+                    3,
+                    Code::from_file(
+                        concat!(
+                            env!("CARGO_MANIFEST_DIR"),
+                            "/examples/introducing_cushy/color.rs"
+                        ),
+                        "rs",
+                    )
+                    .anchor("color-pickers"),
+                ),
                 LazyWidget::new(color::color_pickers),
             )),
         ))
@@ -100,10 +123,13 @@ fn main() {
             None,
             stack((
                 h1("Questions?"),
-                Element::from("https://cushy.rs/").text_color(PrimaryColor),
+                rich("[https://cushy.rs/](https://cushy.rs/)"),
             )),
-        ))
-        .present();
+        ));
+    for warning in show.validate() {
+        eprintln!("warning: {warning}");
+    }
+    show.present();
 }
 
 fn content_slide(
@@ -116,72 +142,7 @@ fn content_slide(
     if let Some(next_slide) = next_slide {
         meta = meta.with_next_slide(next_slide);
     }
-    Slide::new(
-        meta,
-        vsplit((
-            fit(stack((h3(title.to_string()).left_aligned(), hr()))),
-            contents,
-            fit(stack((
-                hr(),
-                hsplit((
-                    fit(h5("Introducing Cushy")),
-                    "",
-                    fit(hstack((SlideIndex, "/", SlideCount))),
-                )),
-            ))),
-        )),
-    )
-}
-
-fn leading_whitespace(source: &str) -> &str {
-    let mut common_whitepsace = "";
-    for (index, ch) in source.char_indices() {
-        if ch.is_ascii_whitespace() {
-            common_whitepsace = &source[..index + ch.len_utf8()];
-        } else {
-            break;
-        }
-    }
-    common_whitepsace
-}
-
-fn anchored_range(source: &str) -> Cow<'_, str> {
-    let (_, anchored) = source
-        .split_once("// ANCHOR_START")
-        .expect("missing anchor start");
-    let (anchored, _) = anchored
-        .split_once("// ANCHOR_END")
-        .expect("missing anchor end");
-    let anchored = anchored.trim_start_matches(['\r', '\n']).trim_end();
-    let mut common_whitespace = leading_whitespace(anchored);
-    let mut line_count = 1;
-    if !common_whitespace.is_empty() {
-        for line in anchored.lines().skip(1) {
-            line_count += 1;
-            let ws = leading_whitespace(&line[..line.len() - 1]);
-            if let Some(matching_until) = common_whitespace
-                .bytes()
-                .zip(ws.bytes())
-                .enumerate()
-                .find_map(|(index, (a, b))| (a != b).then_some(index))
-            {
-                common_whitespace = &common_whitespace[..matching_until];
-                if common_whitespace.is_empty() {
-                    break;
-                }
-            }
-        }
-    }
-
-    if common_whitespace.is_empty() {
-        Cow::Borrowed(anchored)
-    } else {
-        let mut trimmed =
-            String::with_capacity(source.len() - line_count * common_whitespace.len());
-        for line in anchored.lines() {
-            trimmed.push_str(&line[common_whitespace.len()..]);
-            trimmed.push('\n');
-        }
-        Cow::Owned(trimmed)
-    }
+    Slide::from_template(meta, "content")
+        .fill("title", h3(title.to_string()).left_aligned())
+        .fill("body", contents)
 }
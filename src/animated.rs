@@ -0,0 +1,239 @@
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use cushy::context::{EventContext, GraphicsContext, LayoutContext};
+use cushy::figures::units::{Px, UPx};
+use cushy::figures::{IntoSigned, Point, Rect, Size, Zero};
+use cushy::kludgine::app::winit::event::MouseButton;
+use cushy::kludgine::image::codecs::gif::GifDecoder;
+use cushy::kludgine::image::codecs::png::PngDecoder;
+use cushy::kludgine::image::codecs::webp::WebPDecoder;
+use cushy::kludgine::image::{self, AnimationDecoder, DynamicImage, ImageError};
+use cushy::kludgine::{wgpu, LazyTexture};
+use cushy::widget::{EventHandling, Widget, HANDLED, IGNORED};
+use cushy::window::DeviceId;
+use cushy::ConstraintLimit;
+
+use crate::image::aspect_fit;
+
+// Browsers treat very short GIF frame delays as a request for the default
+// frame rate, and many GIFs in the wild rely on that behavior.
+const MIN_DELAY: Duration = Duration::from_millis(20);
+const DEFAULT_DELAY: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum Playback {
+    #[default]
+    Loop,
+    Once,
+}
+
+#[derive(Debug)]
+pub(crate) struct Frame {
+    texture: LazyTexture,
+    delay: Duration,
+}
+
+#[derive(Debug)]
+pub(crate) struct Frames {
+    frames: Vec<Frame>,
+    size: Size<UPx>,
+    duration: Duration,
+}
+
+impl Frames {
+    fn new(images: impl IntoIterator<Item = (DynamicImage, Duration)>) -> Result<Self, String> {
+        let mut size = Size::ZERO;
+        let mut duration = Duration::ZERO;
+        let frames = images
+            .into_iter()
+            .map(|(image, delay)| {
+                let delay = if delay < MIN_DELAY {
+                    DEFAULT_DELAY
+                } else {
+                    delay
+                };
+                size = Size::new(UPx::new(image.width()), UPx::new(image.height()));
+                duration += delay;
+                Frame {
+                    texture: LazyTexture::from_image(image, wgpu::FilterMode::Linear),
+                    delay,
+                }
+            })
+            .collect::<Vec<_>>();
+        if frames.is_empty() {
+            return Err(String::from("no frames"));
+        }
+
+        Ok(Self {
+            frames,
+            size,
+            duration,
+        })
+    }
+}
+
+pub(crate) fn decode(path: &Path) -> Result<Arc<Frames>, String> {
+    let error = |err: ImageError| format!("{}: {err}", path.display());
+    let file =
+        BufReader::new(File::open(path).map_err(|err| format!("{}: {err}", path.display()))?);
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let frames = match extension.as_str() {
+        "gif" => GifDecoder::new(file).map_err(error)?.into_frames(),
+        "png" | "apng" => PngDecoder::new(file).map_err(error)?.apng().into_frames(),
+        "webp" => WebPDecoder::new(file).map_err(error)?.into_frames(),
+        _ => return Err(format!("{}: unsupported animation format", path.display())),
+    };
+    let frames = frames.collect_frames().map_err(error)?;
+
+    Frames::new(frames.into_iter().map(|frame| {
+        let (numerator, denominator) = frame.delay().numer_denom_ms();
+        let delay =
+            Duration::from_secs_f64(f64::from(numerator) / f64::from(denominator.max(1)) / 1000.);
+        (DynamicImage::ImageRgba8(frame.into_buffer()), delay)
+    }))
+    .map(Arc::new)
+}
+
+pub(crate) fn sequence(directory: &Path, frame_rate: f32) -> Result<Arc<Frames>, String> {
+    let mut paths = fs::read_dir(directory)
+        .map_err(|err| format!("{}: {err}", directory.display()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .map_or(false, |extension| extension.eq_ignore_ascii_case("png"))
+        })
+        .collect::<Vec<_>>();
+    paths.sort_by_cached_key(|path| frame_key(path));
+
+    let delay = Duration::from_secs_f32(1. / frame_rate.max(0.1));
+    let images = paths
+        .iter()
+        .map(|path| {
+            image::open(path)
+                .map(|image| (image, delay))
+                .map_err(|err| format!("{}: {err}", path.display()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Frames::new(images).map(Arc::new)
+}
+
+// Sequences are often numbered without zero padding, so the number at the end
+// of each name is compared numerically to play `frame2` before `frame10`.
+fn frame_key(path: &Path) -> (String, u128, String) {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let prefix = stem.trim_end_matches(|ch: char| ch.is_ascii_digit());
+    let number = stem[prefix.len()..].parse().unwrap_or(0);
+    (prefix.to_string(), number, stem)
+}
+
+#[derive(Debug)]
+pub struct AnimationView {
+    frames: Arc<Frames>,
+    playback: Playback,
+    started: Option<Instant>,
+    elapsed: Duration,
+    size: Size<UPx>,
+}
+
+impl AnimationView {
+    pub fn new(frames: Arc<Frames>, playback: Playback, autoplay: bool) -> Self {
+        Self {
+            frames,
+            playback,
+            started: autoplay.then(Instant::now),
+            elapsed: Duration::ZERO,
+            size: Size::ZERO,
+        }
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.elapsed
+            + self
+                .started
+                .map_or(Duration::ZERO, |started| started.elapsed())
+    }
+
+    fn frame_at(&self, elapsed: Duration) -> (&Frame, Option<Duration>) {
+        let elapsed = match self.playback {
+            Playback::Loop => {
+                Duration::from_secs_f64(elapsed.as_secs_f64() % self.frames.duration.as_secs_f64())
+            }
+            Playback::Once => elapsed,
+        };
+
+        let mut frame_end = Duration::ZERO;
+        for frame in &self.frames.frames {
+            frame_end += frame.delay;
+            if elapsed < frame_end {
+                return (frame, Some(frame_end - elapsed));
+            }
+        }
+
+        let last = self.frames.frames.last().expect("at least one frame");
+        (last, None)
+    }
+}
+
+impl Widget for AnimationView {
+    fn redraw(&mut self, context: &mut GraphicsContext<'_, '_, '_, '_>) {
+        let (frame, remaining) = self.frame_at(self.elapsed());
+        context.gfx.draw_texture(
+            &frame.texture,
+            Rect::new(Point::<Px>::ZERO, self.size.into_signed()),
+        );
+
+        match remaining {
+            Some(remaining) if self.started.is_some() => context.redraw_in(remaining),
+            _ => {}
+        }
+    }
+
+    fn layout(
+        &mut self,
+        available_space: Size<ConstraintLimit>,
+        _context: &mut LayoutContext<'_, '_, '_, '_>,
+    ) -> Size<UPx> {
+        self.size = aspect_fit(self.frames.size, available_space);
+        self.size
+    }
+
+    fn hit_test(&mut self, _location: Point<Px>, _context: &mut EventContext<'_>) -> bool {
+        true
+    }
+
+    fn mouse_down(
+        &mut self,
+        _location: Point<Px>,
+        _device_id: DeviceId,
+        button: MouseButton,
+        context: &mut EventContext<'_>,
+    ) -> EventHandling {
+        if button != MouseButton::Left {
+            return IGNORED;
+        }
+
+        // Clicking toggles playback. Clicking a finished animation that
+        // doesn't loop plays it again from the start.
+        if self.playback == Playback::Once && self.elapsed() >= self.frames.duration {
+            self.elapsed = Duration::ZERO;
+            self.started = Some(Instant::now());
+        } else if let Some(started) = self.started.take() {
+            self.elapsed += started.elapsed();
+        } else {
+            self.started = Some(Instant::now());
+        }
+        context.set_needs_redraw();
+        HANDLED
+    }
+}
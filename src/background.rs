@@ -0,0 +1,182 @@
+use cushy::context::{GraphicsContext, LayoutContext};
+use cushy::figures::units::{Px, UPx};
+use cushy::figures::{FloatConversion, IntoSigned, Point, Rect, Size, Zero};
+use cushy::kludgine::shapes::Shape;
+use cushy::kludgine::{DrawableExt, LazyTexture};
+use cushy::styles::Color;
+use cushy::widget::Widget;
+use cushy::ConstraintLimit;
+
+use crate::image::ImageFit;
+use crate::shapes::{ellipse, polygon};
+
+// Gradients are drawn as bands of solid color. This many bands is enough that
+// the steps aren't visible on a projector.
+const GRADIENT_STEPS: usize = 96;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Paint {
+    Solid(Color),
+    Linear { from: Color, to: Color, angle: f32 },
+    Radial { center: Color, edge: Color },
+}
+
+#[derive(Debug)]
+pub struct PaintView {
+    paint: Paint,
+    size: (f32, f32),
+}
+
+impl PaintView {
+    pub fn new(paint: Paint) -> Self {
+        Self {
+            paint,
+            size: (0., 0.),
+        }
+    }
+}
+
+fn mix(from: Color, to: Color, amount: f32) -> Color {
+    let channel = |from: u8, to: u8| {
+        (f32::from(from) + (f32::from(to) - f32::from(from)) * amount).round() as u8
+    };
+    Color::new(
+        channel(from.red(), to.red()),
+        channel(from.green(), to.green()),
+        channel(from.blue(), to.blue()),
+        channel(from.alpha(), to.alpha()),
+    )
+}
+
+impl Widget for PaintView {
+    fn redraw(&mut self, context: &mut GraphicsContext<'_, '_, '_, '_>) {
+        let (width, height) = self.size;
+        let center = (width / 2., height / 2.);
+        match self.paint {
+            Paint::Solid(color) => {
+                if let Some(path) = polygon(&[(0., 0.), (width, 0.), (width, height), (0., height)])
+                {
+                    context.gfx.draw_shape(&path.fill(color));
+                }
+            }
+            Paint::Linear { from, to, angle } => {
+                // Bands run perpendicular to the gradient's direction and are
+                // long enough to cover the slide at any angle. Anything
+                // outside of the slide is clipped.
+                let (dx, dy) = (angle.to_radians().cos(), angle.to_radians().sin());
+                let reach = (width.abs() * dx.abs() + height.abs() * dy.abs()) / 2.;
+                let across = (width * width + height * height).sqrt();
+                let step = reach * 2. / GRADIENT_STEPS as f32;
+                for index in 0..GRADIENT_STEPS {
+                    let start = -reach + step * index as f32;
+                    // Overlapping each band with the next hides seams.
+                    let end = start + step * 1.5;
+                    let at = |distance: f32, side: f32| {
+                        (
+                            center.0 + dx * distance - dy * side,
+                            center.1 + dy * distance + dx * side,
+                        )
+                    };
+                    let color = mix(from, to, (index as f32 + 0.5) / GRADIENT_STEPS as f32);
+                    if let Some(path) = polygon(&[
+                        at(start, -across),
+                        at(end, -across),
+                        at(end, across),
+                        at(start, across),
+                    ]) {
+                        context.gfx.draw_shape(&path.fill(color));
+                    }
+                }
+            }
+            Paint::Radial {
+                center: inner,
+                edge,
+            } => {
+                // Circles are drawn from the outside in, each covering the
+                // middle of the previous one.
+                let radius = (width * width + height * height).sqrt() / 2.;
+                for index in (0..GRADIENT_STEPS).rev() {
+                    let fraction = (index + 1) as f32 / GRADIENT_STEPS as f32;
+                    let color = mix(inner, edge, index as f32 / (GRADIENT_STEPS - 1) as f32);
+                    context.gfx.draw_shape(
+                        &ellipse(center, (radius * fraction, radius * fraction)).fill(color),
+                    );
+                }
+            }
+        }
+    }
+
+    fn layout(
+        &mut self,
+        available_space: Size<ConstraintLimit>,
+        _context: &mut LayoutContext<'_, '_, '_, '_>,
+    ) -> Size<UPx> {
+        let size = available_space.map(ConstraintLimit::max);
+        self.size = (size.width.into_float(), size.height.into_float());
+        size
+    }
+}
+
+#[derive(Debug)]
+pub struct ImageView {
+    texture: LazyTexture,
+    fit: ImageFit,
+    opacity: f32,
+    size: Size<UPx>,
+}
+
+impl ImageView {
+    pub fn new(texture: LazyTexture, fit: ImageFit, opacity: f32) -> Self {
+        Self {
+            texture,
+            fit,
+            opacity,
+            size: Size::ZERO,
+        }
+    }
+}
+
+impl Widget for ImageView {
+    fn redraw(&mut self, context: &mut GraphicsContext<'_, '_, '_, '_>) {
+        // Opacity is applied while drawing, so it works the same for image
+        // files and for textures supplied by the application.
+        let natural = self.texture.size();
+        let (natural_width, natural_height) =
+            (natural.width.into_float(), natural.height.into_float());
+        let (width, height) = (self.size.width.into_float(), self.size.height.into_float());
+        let (scale_x, scale_y) = match self.fit {
+            ImageFit::Stretch => (width / natural_width, height / natural_height),
+            ImageFit::Fit => {
+                let scale = (width / natural_width).min(height / natural_height);
+                (scale, scale)
+            }
+            ImageFit::Fill => {
+                let scale = (width / natural_width).max(height / natural_height);
+                (scale, scale)
+            }
+            ImageFit::Original => (1., 1.),
+        };
+        let drawn = Size::new(
+            Px::from(natural_width * scale_x),
+            Px::from(natural_height * scale_y),
+        );
+        let origin = Point::new(
+            (self.size.width.into_signed() - drawn.width) / 2,
+            (self.size.height.into_signed() - drawn.height) / 2,
+        );
+        context.gfx.draw_textured_shape(
+            Shape::textured_rect(Rect::new(origin, drawn), Rect::from(natural))
+                .opacity(self.opacity),
+            &self.texture,
+        );
+    }
+
+    fn layout(
+        &mut self,
+        available_space: Size<ConstraintLimit>,
+        _context: &mut LayoutContext<'_, '_, '_, '_>,
+    ) -> Size<UPx> {
+        self.size = available_space.map(ConstraintLimit::max);
+        self.size
+    }
+}
@@ -0,0 +1,107 @@
+use cushy::context::{EventContext, GraphicsContext, LayoutContext};
+use cushy::figures::units::{Px, UPx};
+use cushy::figures::{FloatConversion, IntoSigned, Point, Rect, Size};
+use cushy::widget::{MakeWidget, Widget, WidgetRef};
+use cushy::ConstraintLimit;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Placement {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Placement {
+    pub const FULL: Self = Self {
+        x: 0.,
+        y: 0.,
+        width: 1.,
+        height: 1.,
+    };
+}
+
+#[derive(Debug)]
+pub struct CanvasView {
+    layers: Vec<(WidgetRef, Placement)>,
+    design_frame: bool,
+}
+
+impl CanvasView {
+    // Layers are drawn in order, so later layers cover earlier ones.
+    pub fn new(layers: Vec<(impl MakeWidget, Placement)>) -> Self {
+        Self {
+            layers: layers
+                .into_iter()
+                .map(|(layer, placement)| (WidgetRef::new(layer), placement))
+                .collect(),
+            design_frame: true,
+        }
+    }
+
+    // Places the layers relative to all of the available space instead of
+    // the design frame, which lets backgrounds reach the window's edges.
+    pub fn full_bleed(layers: Vec<(impl MakeWidget, Placement)>) -> Self {
+        Self {
+            design_frame: false,
+            ..Self::new(layers)
+        }
+    }
+}
+
+impl Widget for CanvasView {
+    fn redraw(&mut self, context: &mut GraphicsContext<'_, '_, '_, '_>) {
+        for (layer, _) in &mut self.layers {
+            let layer = layer.mounted(&mut context.as_event_context());
+            context.for_other(&layer).redraw();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        available_space: Size<ConstraintLimit>,
+        context: &mut LayoutContext<'_, '_, '_, '_>,
+    ) -> Size<UPx> {
+        let size = available_space.map(ConstraintLimit::max);
+        let (available_width, available_height) =
+            (size.width.into_float(), size.height.into_float());
+        // Placements are relative to the largest 16:9 frame that fits, so
+        // layers keep their arrangement regardless of the window's shape.
+        let (width, height) = if self.design_frame {
+            let width = available_width.min(available_height * 16. / 9.);
+            (width, width * 9. / 16.)
+        } else {
+            (available_width, available_height)
+        };
+        let (left, top) = (
+            (available_width - width) / 2.,
+            (available_height - height) / 2.,
+        );
+        for (layer, placement) in &mut self.layers {
+            let layer = layer.mounted(&mut context.as_event_context());
+            let origin = Point::new(
+                Px::from((left + placement.x * width).round()),
+                Px::from((top + placement.y * height).round()),
+            );
+            let area = Size::new(
+                UPx::from((placement.width * width).max(0.).round()),
+                UPx::from((placement.height * height).max(0.).round()),
+            );
+            let measured = context
+                .for_other(&layer)
+                .layout(area.map(ConstraintLimit::Fill));
+            let layer_size = Size::new(
+                measured.width.min(area.width),
+                measured.height.min(area.height),
+            );
+            context.set_child_layout(&layer, Rect::new(origin, layer_size.into_signed()));
+        }
+        size
+    }
+
+    fn unmounted(&mut self, context: &mut EventContext<'_>) {
+        for (layer, _) in &mut self.layers {
+            layer.unmount_in(context);
+        }
+    }
+}
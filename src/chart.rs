@@ -0,0 +1,828 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use cushy::context::{GraphicsContext, LayoutContext};
+use cushy::figures::units::{Px, UPx};
+use cushy::figures::{FloatConversion, Rect, ScreenScale, Size};
+use cushy::kludgine::shapes::{Shape, StrokeOptions};
+use cushy::kludgine::text::{MeasuredText, Text, TextOrigin};
+use cushy::kludgine::DrawableExt;
+use cushy::styles::components::TextSize;
+use cushy::styles::Color;
+use cushy::widget::Widget;
+use cushy::ConstraintLimit;
+use serde_json::Value;
+
+use crate::shapes::{ellipse, point, polygon, polyline};
+
+const ANIMATION_DURATION: Duration = Duration::from_millis(800);
+
+#[derive(Debug, Clone)]
+struct Series {
+    name: String,
+    values: Vec<f32>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ChartData {
+    categories: Vec<String>,
+    series: Vec<Series>,
+}
+
+impl ChartData {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn categories(mut self, categories: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.categories = categories.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn series(
+        mut self,
+        name: impl Into<String>,
+        values: impl IntoIterator<Item = f32>,
+    ) -> Self {
+        self.series.push(Series {
+            name: name.into(),
+            values: values.into_iter().collect(),
+        });
+        self
+    }
+
+    pub(crate) fn check(&self) -> Result<(), String> {
+        if self.categories.is_empty() || self.series.is_empty() {
+            return Err(String::from("no data"));
+        }
+        for series in &self.series {
+            if series.values.len() != self.categories.len() {
+                return Err(format!(
+                    "series `{}` has {} values for {} categories",
+                    series.name,
+                    series.values.len(),
+                    self.categories.len()
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn series_count(&self) -> usize {
+        self.series.len()
+    }
+
+    pub(crate) fn has_negative_values(&self) -> bool {
+        self.series
+            .iter()
+            .flat_map(|series| &series.values)
+            .any(|value| *value < 0.)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ChartSource {
+    Inline(ChartData),
+    Csv(String),
+    Json(String),
+    File(PathBuf),
+}
+
+// Paths convert into file sources. Strings are ambiguous, so inline CSV or
+// JSON text and file names given as strings need to be marked explicitly.
+impl ChartSource {
+    pub fn csv(text: impl Into<String>) -> Self {
+        Self::Csv(text.into())
+    }
+
+    pub fn json(text: impl Into<String>) -> Self {
+        Self::Json(text.into())
+    }
+
+    pub fn file(path: impl Into<PathBuf>) -> Self {
+        Self::File(path.into())
+    }
+}
+
+impl From<ChartData> for ChartSource {
+    fn from(data: ChartData) -> Self {
+        Self::Inline(data)
+    }
+}
+
+impl From<PathBuf> for ChartSource {
+    fn from(path: PathBuf) -> Self {
+        Self::File(path)
+    }
+}
+
+impl<'a> From<&'a Path> for ChartSource {
+    fn from(path: &'a Path) -> Self {
+        Self::File(path.to_path_buf())
+    }
+}
+
+pub(crate) fn load(source: &ChartSource) -> Result<Arc<ChartData>, String> {
+    let data = match source {
+        ChartSource::Inline(data) => data.clone(),
+        ChartSource::Csv(text) => parse_csv(text)?,
+        ChartSource::Json(text) => parse_json(text)?,
+        ChartSource::File(path) => {
+            let contents =
+                fs::read_to_string(path).map_err(|err| format!("{}: {err}", path.display()))?;
+            let extension = path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .unwrap_or_default()
+                .to_ascii_lowercase();
+            match extension.as_str() {
+                "csv" => parse_csv(&contents),
+                "json" => parse_json(&contents),
+                _ => Err(String::from("unsupported data format")),
+            }
+            .map_err(|err| format!("{}: {err}", path.display()))?
+        }
+    };
+    data.check()?;
+    Ok(Arc::new(data))
+}
+
+fn csv_record(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '"' if quoted && chars.next_if_eq(&'"').is_some() => field.push('"'),
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field).trim().to_string()),
+            ch => field.push(ch),
+        }
+    }
+    fields.push(field.trim().to_string());
+    fields
+}
+
+// The first row names the series, and every following row starts with its
+// category followed by one value per series.
+fn parse_csv(contents: &str) -> Result<ChartData, String> {
+    let mut lines = contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+    let (_, header) = lines.next().ok_or_else(|| String::from("missing header"))?;
+    let header = csv_record(header);
+    let mut data = ChartData {
+        categories: Vec::new(),
+        series: header
+            .into_iter()
+            .skip(1)
+            .map(|name| Series {
+                name,
+                values: Vec::new(),
+            })
+            .collect(),
+    };
+    for (index, line) in lines {
+        let line_number = index + 1;
+        let record = csv_record(line);
+        if record.len() != data.series.len() + 1 {
+            return Err(format!(
+                "line {line_number}: expected {} values, found {}",
+                data.series.len(),
+                record.len() - 1
+            ));
+        }
+        let mut record = record.into_iter();
+        data.categories.extend(record.next());
+        for (series, value) in data.series.iter_mut().zip(record) {
+            let value = value
+                .parse()
+                .map_err(|_| format!("line {line_number}: `{value}` is not a number"))?;
+            series.values.push(value);
+        }
+    }
+    Ok(data)
+}
+
+fn json_label(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+fn json_values(name: &str, values: &Value) -> Result<Vec<f32>, String> {
+    values
+        .as_array()
+        .ok_or_else(|| format!("values of `{name}` must be an array"))?
+        .iter()
+        .map(|value| {
+            value
+                .as_f64()
+                .map(|value| value as f32)
+                .ok_or_else(|| format!("`{value}` in `{name}` is not a number"))
+        })
+        .collect()
+}
+
+// JSON data has a `categories` array and a `series` array, where each series
+// is an object with a `name` and its `values`.
+fn parse_json(contents: &str) -> Result<ChartData, String> {
+    let root = serde_json::from_str::<Value>(contents).map_err(|err| err.to_string())?;
+    let categories = root
+        .get("categories")
+        .and_then(Value::as_array)
+        .ok_or_else(|| String::from("missing `categories` array"))?
+        .iter()
+        .map(json_label)
+        .collect();
+    let series = root
+        .get("series")
+        .and_then(Value::as_array)
+        .ok_or_else(|| String::from("missing `series` array"))?
+        .iter()
+        .map(|series| {
+            let name = series.get("name").map(json_label).unwrap_or_default();
+            let values = series
+                .get("values")
+                .ok_or_else(|| format!("series `{name}` is missing `values`"))?;
+            Ok(Series {
+                values: json_values(&name, values)?,
+                name,
+            })
+        })
+        .collect::<Result<_, String>>()?;
+    Ok(ChartData { categories, series })
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum ChartKind {
+    Bar,
+    Line,
+    Pie,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChartColors {
+    pub text: Color,
+    pub axis: Color,
+    pub grid: Color,
+    pub palette: Vec<Color>,
+}
+
+impl ChartColors {
+    fn series(&self, index: usize) -> Color {
+        self.palette[index % self.palette.len()]
+    }
+}
+
+#[derive(Debug)]
+struct Label {
+    text: MeasuredText<Px>,
+    at: (f32, f32),
+}
+
+#[derive(Debug, Default)]
+struct Arranged {
+    labels: Vec<Label>,
+    swatches: Vec<((f32, f32), f32, Color)>,
+    left: f32,
+    top: f32,
+    right: f32,
+    bottom: f32,
+    minimum: f32,
+    maximum: f32,
+    grid: Vec<f32>,
+    line_width: f32,
+}
+
+impl Arranged {
+    fn y(&self, value: f32) -> f32 {
+        let range = (self.maximum - self.minimum).max(f32::EPSILON);
+        self.bottom - (value - self.minimum) / range * (self.bottom - self.top)
+    }
+
+    fn group_width(&self, categories: usize) -> f32 {
+        (self.right - self.left) / categories.max(1) as f32
+    }
+}
+
+fn ticks(minimum: f32, maximum: f32) -> (f32, f32, f32) {
+    let (minimum, maximum) = (minimum.min(0.), maximum.max(0.));
+    let range = if maximum > minimum {
+        maximum - minimum
+    } else {
+        1.
+    };
+    let rough = range / 5.;
+    let magnitude = 10_f32.powf(rough.log10().floor());
+    let step = [1., 2., 5., 10.]
+        .into_iter()
+        .map(|multiple| multiple * magnitude)
+        .find(|step| *step >= rough)
+        .unwrap_or(magnitude * 10.);
+    let low = (minimum / step).floor() * step;
+    let high = (maximum / step).ceil() * step;
+    // All-zero data would otherwise collapse the axis to a single tick.
+    (low, if high > low { high } else { low + step }, step)
+}
+
+#[derive(Debug)]
+pub struct ChartView {
+    kind: ChartKind,
+    data: Arc<ChartData>,
+    colors: ChartColors,
+    x_label: Option<String>,
+    y_label: Option<String>,
+    animated: bool,
+    started: Option<Instant>,
+    cached: Option<(f32, Size<UPx>)>,
+    arranged: Arranged,
+}
+
+impl ChartView {
+    pub fn new(
+        kind: ChartKind,
+        data: Arc<ChartData>,
+        colors: ChartColors,
+        x_label: Option<String>,
+        y_label: Option<String>,
+        animated: bool,
+    ) -> Self {
+        Self {
+            kind,
+            data,
+            colors,
+            x_label,
+            y_label,
+            animated,
+            started: None,
+            cached: None,
+            arranged: Arranged::default(),
+        }
+    }
+
+    fn measure(&self, context: &mut LayoutContext<'_, '_, '_, '_>, text: &str) -> MeasuredText<Px> {
+        context.gfx.measure_text(Text::new(text, self.colors.text))
+    }
+
+    fn arrange(
+        &mut self,
+        context: &mut LayoutContext<'_, '_, '_, '_>,
+        text_size: f32,
+        size: (f32, f32),
+    ) {
+        let (width, height) = size;
+        let gap = text_size * 0.4;
+        context.apply_current_font_settings();
+        context.gfx.set_font_size(Px::from(text_size * 0.7));
+        let mut arranged = Arranged {
+            line_width: (text_size / 8.).max(1.),
+            ..Arranged::default()
+        };
+
+        if self.kind == ChartKind::Pie {
+            self.arrange_pie(context, &mut arranged, gap, size);
+            context.apply_current_font_settings();
+            self.arranged = arranged;
+            return;
+        }
+
+        let mut top = 0.;
+        if self.data.series.len() > 1 {
+            // The legend flows across the top, wrapping when it runs out of
+            // room.
+            let mut x = 0.;
+            let mut row_height: f32 = 0.;
+            for (index, series) in self.data.series.iter().enumerate() {
+                let text = self.measure(context, &series.name);
+                let (text_width, text_height) =
+                    (text.size.width.into_float(), text.size.height.into_float());
+                let swatch = text_height * 0.6;
+                let entry = swatch + gap / 2. + text_width;
+                if x > 0. && x + entry > width {
+                    x = 0.;
+                    top += row_height + gap / 2.;
+                }
+                arranged.swatches.push((
+                    (x, top + (text_height - swatch) / 2.),
+                    swatch,
+                    self.colors.series(index),
+                ));
+                arranged.labels.push(Label {
+                    text,
+                    at: (x + swatch + gap / 2., top),
+                });
+                x += entry + gap * 2.;
+                row_height = row_height.max(text_height);
+            }
+            top += row_height + gap;
+        }
+        if let Some(y_label) = &self.y_label {
+            let text = self.measure(context, y_label);
+            let height = text.size.height.into_float();
+            arranged.labels.push(Label {
+                text,
+                at: (0., top),
+            });
+            top += height + gap / 2.;
+        }
+
+        let (minimum, maximum) = self
+            .data
+            .series
+            .iter()
+            .flat_map(|series| &series.values)
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), value| {
+                (min.min(*value), max.max(*value))
+            });
+        let (minimum, maximum, step) = ticks(minimum, maximum);
+        arranged.minimum = minimum;
+        arranged.maximum = maximum;
+        let decimals = (-step.log10().floor()).max(0.) as usize;
+        let tick_labels = (0..)
+            .map(|index| minimum + step * index as f32)
+            .take_while(|value| *value <= maximum + step / 2.)
+            .map(|value| (value, self.measure(context, &format!("{value:.decimals$}"))))
+            .collect::<Vec<_>>();
+        let tick_width = tick_labels
+            .iter()
+            .map(|(_, text)| text.size.width.into_float())
+            .fold(0., f32::max);
+        let tick_height = tick_labels
+            .first()
+            .map_or(0., |(_, text)| text.size.height.into_float());
+
+        let category_labels = self
+            .data
+            .categories
+            .iter()
+            .map(|category| self.measure(context, category))
+            .collect::<Vec<_>>();
+        let category_height = category_labels
+            .iter()
+            .map(|text| text.size.height.into_float())
+            .fold(0., f32::max);
+        let x_label = self
+            .x_label
+            .as_ref()
+            .map(|label| self.measure(context, label));
+        let x_label_height = x_label
+            .as_ref()
+            .map_or(0., |text| text.size.height.into_float() + gap / 2.);
+
+        arranged.left = tick_width + gap;
+        arranged.right = width - gap;
+        arranged.top = top + tick_height / 2.;
+        arranged.bottom = height - category_height - gap - x_label_height;
+
+        for (value, text) in tick_labels {
+            let y = arranged.y(value);
+            let text_width = text.size.width.into_float();
+            arranged.grid.push(y);
+            arranged.labels.push(Label {
+                text,
+                at: (tick_width - text_width, y - tick_height / 2.),
+            });
+        }
+
+        // Category labels that are wider than their group would overlap, so
+        // only every few labels are shown instead.
+        let group = arranged.group_width(self.data.categories.len());
+        let widest = category_labels
+            .iter()
+            .map(|text| text.size.width.into_float())
+            .fold(0., f32::max);
+        let every = ((widest + gap) / group).ceil().max(1.) as usize;
+        for (index, text) in category_labels.into_iter().enumerate() {
+            if index % every == 0 {
+                let center = arranged.left + group * (index as f32 + 0.5);
+                let text_width = text.size.width.into_float();
+                arranged.labels.push(Label {
+                    text,
+                    at: (center - text_width / 2., arranged.bottom + gap / 2.),
+                });
+            }
+        }
+        if let Some(text) = x_label {
+            let text_width = text.size.width.into_float();
+            let center = (arranged.left + arranged.right) / 2.;
+            arranged.labels.push(Label {
+                text,
+                at: (
+                    center - text_width / 2.,
+                    arranged.bottom + gap + category_height,
+                ),
+            });
+        }
+
+        context.apply_current_font_settings();
+        self.arranged = arranged;
+    }
+
+    fn arrange_pie(
+        &self,
+        context: &mut LayoutContext<'_, '_, '_, '_>,
+        arranged: &mut Arranged,
+        gap: f32,
+        (width, height): (f32, f32),
+    ) {
+        let values = &self.data.series[0].values;
+        let total = values.iter().map(|value| value.max(0.)).sum::<f32>();
+        let entries = self
+            .data
+            .categories
+            .iter()
+            .zip(values)
+            .map(|(category, value)| {
+                let percent = if total > 0. {
+                    value.max(0.) / total * 100.
+                } else {
+                    0.
+                };
+                self.measure(context, &format!("{category} ({percent:.0}%)"))
+            })
+            .collect::<Vec<_>>();
+        let legend_width = entries
+            .iter()
+            .map(|text| text.size.width.into_float())
+            .fold(0., f32::max);
+        let line_height = entries
+            .first()
+            .map_or(0., |text| text.size.height.into_float());
+        let legend_height = (line_height + gap / 2.) * entries.len() as f32;
+        let swatch = line_height * 0.6;
+
+        let diameter = (width - legend_width - swatch - gap * 3.)
+            .min(height)
+            .max(0.);
+        arranged.left = 0.;
+        arranged.top = (height - diameter) / 2.;
+        arranged.right = diameter;
+        arranged.bottom = arranged.top + diameter;
+
+        let x = diameter + gap * 2.;
+        let mut y = (height - legend_height) / 2.;
+        for (index, text) in entries.into_iter().enumerate() {
+            arranged.swatches.push((
+                (x, y + (line_height - swatch) / 2.),
+                swatch,
+                self.colors.series(index),
+            ));
+            arranged.labels.push(Label {
+                text,
+                at: (x + swatch + gap / 2., y),
+            });
+            y += line_height + gap / 2.;
+        }
+    }
+
+    fn progress(&mut self, context: &mut GraphicsContext<'_, '_, '_, '_>) -> f32 {
+        if !self.animated {
+            return 1.;
+        }
+        let started = *self.started.get_or_insert_with(Instant::now);
+        let elapsed = started.elapsed().as_secs_f32() / ANIMATION_DURATION.as_secs_f32();
+        if elapsed < 1. {
+            context.redraw_in(Duration::from_millis(16));
+        }
+        // Ease out so the data settles into place.
+        1. - (1. - elapsed.min(1.)).powi(3)
+    }
+
+    fn draw_axes(&self, context: &mut GraphicsContext<'_, '_, '_, '_>) {
+        let arranged = &self.arranged;
+        let width = arranged.right - arranged.left;
+        for &y in &arranged.grid {
+            context.gfx.draw_shape(&Shape::filled_rect(
+                Rect::new(
+                    point((arranged.left, y)),
+                    Size::new(Px::from(width), Px::new(1)),
+                ),
+                self.colors.grid,
+            ));
+        }
+        let thickness = Px::from(arranged.line_width / 2.).max(Px::new(1));
+        let zero = arranged.y(0.);
+        context.gfx.draw_shape(&Shape::filled_rect(
+            Rect::new(
+                point((arranged.left, zero)),
+                Size::new(Px::from(width), thickness),
+            ),
+            self.colors.axis,
+        ));
+        context.gfx.draw_shape(&Shape::filled_rect(
+            Rect::new(
+                point((arranged.left, arranged.top)),
+                Size::new(thickness, Px::from(arranged.bottom - arranged.top)),
+            ),
+            self.colors.axis,
+        ));
+    }
+
+    fn draw_bars(&self, context: &mut GraphicsContext<'_, '_, '_, '_>, progress: f32) {
+        let arranged = &self.arranged;
+        let group = arranged.group_width(self.data.categories.len());
+        let bar = group * 0.8 / self.data.series.len() as f32;
+        let zero = arranged.y(0.);
+        for (index, series) in self.data.series.iter().enumerate() {
+            for (category, value) in series.values.iter().enumerate() {
+                let x = arranged.left + group * (category as f32 + 0.1) + bar * index as f32;
+                let y = arranged.y(value * progress);
+                context.gfx.draw_shape(&Shape::filled_rect(
+                    Rect::new(
+                        point((x, y.min(zero))),
+                        Size::new(Px::from(bar), Px::from((y - zero).abs())),
+                    ),
+                    self.colors.series(index),
+                ));
+            }
+        }
+    }
+
+    fn draw_lines(&self, context: &mut GraphicsContext<'_, '_, '_, '_>, progress: f32) {
+        let arranged = &self.arranged;
+        let group = arranged.group_width(self.data.categories.len());
+        for (index, series) in self.data.series.iter().enumerate() {
+            let color = self.colors.series(index);
+            let points = series
+                .values
+                .iter()
+                .enumerate()
+                .map(|(category, value)| {
+                    (
+                        arranged.left + group * (category as f32 + 0.5),
+                        arranged.y(*value),
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            // Lines are drawn from left to right as the animation progresses.
+            let reach = progress * points.len().saturating_sub(1) as f32;
+            let whole = reach.floor() as usize;
+            let mut visible = points[..=whole.min(points.len() - 1)].to_vec();
+            if let (Some(from), Some(to)) = (points.get(whole), points.get(whole + 1)) {
+                let t = reach.fract();
+                visible.push((from.0 + (to.0 - from.0) * t, from.1 + (to.1 - from.1) * t));
+            }
+            if let Some(path) = polyline(&visible) {
+                context.gfx.draw_shape(
+                    &path.stroke(
+                        StrokeOptions::px_wide(Px::from(arranged.line_width)).colored(color),
+                    ),
+                );
+            }
+            let radius = arranged.line_width * 1.5;
+            for &center in &points[..=whole.min(points.len() - 1)] {
+                context
+                    .gfx
+                    .draw_shape(&ellipse(center, (radius, radius)).fill(color));
+            }
+        }
+    }
+
+    fn draw_pie(&self, context: &mut GraphicsContext<'_, '_, '_, '_>, progress: f32) {
+        let arranged = &self.arranged;
+        let radius = (arranged.right - arranged.left) / 2.;
+        let center = (arranged.left + radius, arranged.top + radius);
+        let values = &self.data.series[0].values;
+        let total = values.iter().map(|value| value.max(0.)).sum::<f32>();
+        if total <= 0. || radius <= 0. {
+            return;
+        }
+
+        let mut angle = -std::f32::consts::FRAC_PI_2;
+        for (index, value) in values.iter().enumerate() {
+            let sweep = value.max(0.) / total * std::f32::consts::TAU * progress;
+            let segments = ((sweep / 0.05).ceil() as usize).max(1);
+            let mut points = vec![center];
+            points.extend((0..=segments).map(|segment| {
+                let angle = angle + sweep * segment as f32 / segments as f32;
+                (
+                    center.0 + radius * angle.cos(),
+                    center.1 + radius * angle.sin(),
+                )
+            }));
+            if let Some(path) = polygon(&points) {
+                context
+                    .gfx
+                    .draw_shape(&path.fill(self.colors.series(index)));
+            }
+            angle += sweep;
+        }
+    }
+}
+
+impl Widget for ChartView {
+    fn redraw(&mut self, context: &mut GraphicsContext<'_, '_, '_, '_>) {
+        let progress = self.progress(context);
+        match self.kind {
+            ChartKind::Bar => {
+                self.draw_axes(context);
+                self.draw_bars(context, progress);
+            }
+            ChartKind::Line => {
+                self.draw_axes(context);
+                self.draw_lines(context, progress);
+            }
+            ChartKind::Pie => self.draw_pie(context, progress),
+        }
+
+        for &(origin, size, color) in &self.arranged.swatches {
+            context.gfx.draw_shape(&Shape::filled_rect(
+                Rect::new(point(origin), Size::squared(Px::from(size))),
+                color,
+            ));
+        }
+        for label in &self.arranged.labels {
+            context.gfx.draw_measured_text(
+                label.text.translate_by(point(label.at)),
+                TextOrigin::TopLeft,
+            );
+        }
+    }
+
+    fn layout(
+        &mut self,
+        available_space: Size<ConstraintLimit>,
+        context: &mut LayoutContext<'_, '_, '_, '_>,
+    ) -> Size<UPx> {
+        let text_size = context
+            .get(&TextSize)
+            .into_px(context.gfx.scale())
+            .into_float();
+        let mut size = available_space.map(ConstraintLimit::max);
+        // Charts grow to fill their space, but keep a sensible shape when the
+        // space is unbounded in one direction.
+        size.height = size.height.min(UPx::from(size.width.into_float() * 0.75));
+        if self.cached != Some((text_size, size)) {
+            self.arrange(
+                context,
+                text_size,
+                (size.width.into_float(), size.height.into_float()),
+            );
+            self.cached = Some((text_size, size));
+        }
+        size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quoted_fields() {
+        assert_eq!(
+            csv_record(r#""a, b", 1 ,"say ""hi""""#),
+            vec!["a, b", "1", r#"say "hi""#]
+        );
+
+        let data = parse_csv("name,\"x, y\"\n\"north, east\",2\n").unwrap();
+        assert_eq!(data.categories, vec!["north, east"]);
+        assert_eq!(data.series[0].name, "x, y");
+        assert_eq!(data.series[0].values, vec![2.]);
+    }
+
+    #[test]
+    fn ragged_rows() {
+        assert_eq!(
+            parse_csv("name,x,y\na,1,2\nb,3\n").unwrap_err(),
+            "line 3: expected 2 values, found 1"
+        );
+        assert_eq!(
+            parse_csv("name,x\na,1,2\n").unwrap_err(),
+            "line 2: expected 1 values, found 2"
+        );
+    }
+
+    #[test]
+    fn non_numeric_values() {
+        assert_eq!(
+            parse_csv("name,x\na,one\n").unwrap_err(),
+            "line 2: `one` is not a number"
+        );
+        assert_eq!(
+            parse_json(r#"{"categories": ["a"], "series": [{"name": "x", "values": ["1"]}]}"#)
+                .unwrap_err(),
+            "`\"1\"` in `x` is not a number"
+        );
+    }
+
+    #[test]
+    fn all_equal_ticks() {
+        for value in [0., 5., -5., 120.] {
+            let (minimum, maximum, step) = ticks(value, value);
+            assert!(step > 0., "{value}: {step}");
+            assert!(maximum > minimum, "{value}: {minimum}..{maximum}");
+            assert!(
+                minimum <= value && value <= maximum,
+                "{value}: {minimum}..{maximum}"
+            );
+        }
+    }
+}
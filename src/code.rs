@@ -1,27 +1,78 @@
 use std::borrow::Cow;
 
+use cushy::context::{EventContext, GraphicsContext, LayoutContext};
 use cushy::figures::units::{Px, UPx};
-use cushy::figures::{IntoUnsigned, Point, ScreenScale, Size, Zero};
+use cushy::figures::{
+    FloatConversion, IntoSigned, IntoUnsigned, Point, Rect, Round, ScreenScale, Size, Zero,
+};
+use cushy::kludgine::app::winit::event::{MouseButton, MouseScrollDelta, TouchPhase};
+use cushy::kludgine::app::winit::keyboard::{Key, NamedKey};
+use cushy::kludgine::shapes::Shape;
 use cushy::kludgine::text::{MeasuredText, Text, TextOrigin};
 use cushy::kludgine::DrawableExt;
-use cushy::styles::components::TextSize;
+use cushy::styles::components::{HighlightColor, TextSize};
 use cushy::styles::{Color, FamilyOwned, Style, Weight};
-use cushy::widget::Widget;
+use cushy::widget::{EventHandling, Widget, HANDLED, IGNORED};
+use cushy::window::{DeviceId, KeyEvent};
+use cushy::ConstraintLimit;
 use syntect::easy::HighlightLines;
 use syntect::highlighting::{FontStyle, Theme};
 use syntect::parsing::SyntaxSet;
 use syntect::util::LinesWithEndings;
 
+use crate::morph::Morph;
+use crate::snippet::SnippetWatcher;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum CodeOverflow {
+    #[default]
+    Clip,
+    Fit,
+    Scroll,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+struct TextPosition {
+    line: usize,
+    column: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Selection {
+    anchor: TextPosition,
+    cursor: TextPosition,
+}
+
+impl Selection {
+    fn ordered(self) -> Option<(TextPosition, TextPosition)> {
+        match self.anchor.cmp(&self.cursor) {
+            std::cmp::Ordering::Less => Some((self.anchor, self.cursor)),
+            std::cmp::Ordering::Equal => None,
+            std::cmp::Ordering::Greater => Some((self.cursor, self.anchor)),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct CodeView {
     extension: Cow<'static, str>,
     syntax_set: SyntaxSet,
     theme: Theme,
     source: Cow<'static, str>,
-    measured_lines: Vec<Vec<MeasuredText<Px>>>,
+    watcher: Option<SnippetWatcher>,
+    error: Option<String>,
+    copy_error: Option<String>,
+    error_color: Color,
+    morph: Option<Morph>,
+    overflow: CodeOverflow,
+    measured_lines: Vec<Vec<Span>>,
     size: Size<UPx>,
     line_height: Px,
     cached_text_size: Px,
+    cached_fit_to: Option<Size<UPx>>,
+    visible_height: Px,
+    scroll: Px,
+    selection: Option<Selection>,
 }
 
 impl CodeView {
@@ -36,62 +87,392 @@ impl CodeView {
             syntax_set,
             theme,
             source: source.into(),
+            watcher: None,
+            error: None,
+            copy_error: None,
+            error_color: Color::RED,
+            morph: None,
+            overflow: CodeOverflow::default(),
             measured_lines: Vec::new(),
             size: Size::ZERO,
             line_height: Px::ZERO,
             cached_text_size: Px::ZERO,
+            cached_fit_to: None,
+            visible_height: Px::ZERO,
+            scroll: Px::ZERO,
+            selection: None,
         }
     }
 
-    fn highlight(&mut self, context: &mut cushy::context::LayoutContext<'_, '_, '_, '_>) {
+    pub fn overflow(mut self, overflow: CodeOverflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    pub fn error_color(mut self, color: Color) -> Self {
+        self.error_color = color;
+        self
+    }
+
+    pub fn reload_from(mut self, watcher: SnippetWatcher) -> Self {
+        self.watcher = Some(watcher);
+        self
+    }
+
+    pub fn morph_with(mut self, morph: Morph) -> Self {
+        self.morph = Some(morph);
+        self
+    }
+
+    fn reload(&mut self, context: &mut LayoutContext<'_, '_, '_, '_>) {
+        let Some(watcher) = &mut self.watcher else {
+            return;
+        };
+        context.invalidate_when_changed(watcher.changed());
+
+        // A failed reload usually means the file was caught mid-save, so the
+        // last successfully loaded source stays on screen with the error
+        // shown beneath it until a reload succeeds.
+        match watcher.reload() {
+            Some(Ok(source)) => {
+                self.error = None;
+                self.source = Cow::Owned(source);
+                self.measured_lines.clear();
+                self.size = Size::ZERO;
+            }
+            Some(Err(err)) => {
+                self.error = Some(format!("error reloading code: {err}"));
+            }
+            None => {}
+        }
+    }
+
+    fn highlight(
+        &mut self,
+        available_space: Size<ConstraintLimit>,
+        context: &mut LayoutContext<'_, '_, '_, '_>,
+    ) {
+        self.reload(context);
         let text_size = context.get(&TextSize).into_px(context.gfx.scale());
-        if (text_size != self.cached_text_size || self.measured_lines.is_empty())
+        let fit_to =
+            (self.overflow == CodeOverflow::Fit).then(|| available_space.map(ConstraintLimit::max));
+        if (text_size != self.cached_text_size
+            || fit_to != self.cached_fit_to
+            || self.measured_lines.is_empty())
             && !self.source.is_empty()
         {
-            self.measured_lines.clear();
             self.cached_text_size = text_size;
-            let mut max_x = Px::ZERO;
-            let mut y = Px::ZERO;
-            let syntax = self
-                .syntax_set
-                .find_syntax_by_extension(&self.extension)
-                .expect("missing syntax definition");
-            let mut highlighter = HighlightLines::new(syntax, &self.theme);
+            self.cached_fit_to = fit_to;
             context.gfx.set_font_family(FamilyOwned::Monospace);
-            self.line_height = context.gfx.line_height().into_px(context.gfx.scale());
-            for line in LinesWithEndings::from(self.source.as_ref()) {
-                y += self.line_height;
-                let mut spans = Vec::new();
-                let mut x = Px::ZERO;
-                for (style, text) in highlighter
-                    .highlight_line(line, &self.syntax_set)
-                    .expect("invalid syntax")
+            self.measure(context);
+
+            if let Some(fit_to) = fit_to {
+                let line_height = self.line_height;
+                let mut fitted_size = text_size;
+                while (self.size.width > fit_to.width || self.size.height > fit_to.height)
+                    && fitted_size > Px::new(1)
                 {
-                    if style.font_style.contains(FontStyle::BOLD) {
-                        context.gfx.set_font_weight(Weight::BOLD);
-                    } else {
-                        context.gfx.set_font_weight(Weight::NORMAL);
-                    }
+                    // Monospace text scales roughly linearly, so jump straight
+                    // to the estimated size, but always make progress in case
+                    // rounding keeps the measurement slightly too large.
+                    let ratio = (fit_to.width.into_float() / self.size.width.into_float())
+                        .min(fit_to.height.into_float() / self.size.height.into_float());
+                    fitted_size = Px::from(fitted_size.into_float() * ratio)
+                        .floor()
+                        .min(fitted_size - Px::new(1))
+                        .max(Px::new(1));
+                    context.gfx.set_font_size(fitted_size);
+                    context.gfx.set_line_height(Px::from(
+                        line_height.into_float() * fitted_size.into_float()
+                            / text_size.into_float(),
+                    ));
+                    self.measure(context);
+                }
+            }
 
-                    if style.font_style.contains(FontStyle::ITALIC) {
-                        context.gfx.set_font_style(Style::Italic);
-                    } else {
-                        context.gfx.set_font_style(Style::Normal);
-                    }
+            if let Some(morph) = &mut self.morph {
+                morph.publish(&self.measured_lines, self.line_height);
+            }
+        }
+    }
 
-                    let span = context
-                        .gfx
-                        .measure_text(Text::new(text, color(style.foreground)));
-                    x += span.size.width;
-                    spans.push(span);
+    fn measure(&mut self, context: &mut LayoutContext<'_, '_, '_, '_>) {
+        self.line_height = context.gfx.line_height().into_px(context.gfx.scale());
+        self.measured_lines = measure_highlighted(
+            &self.source,
+            &self.extension,
+            &self.syntax_set,
+            &self.theme,
+            context,
+        );
+        let mut max_x = Px::ZERO;
+        let mut y = Px::ZERO;
+        for line in &self.measured_lines {
+            y += self.line_height;
+            max_x = max_x.max(line_width(line));
+        }
+
+        self.size = Size::new(max_x, y).into_unsigned();
+    }
+
+    fn position_at(&self, location: Point<Px>) -> TextPosition {
+        let Some(last_line) = self.measured_lines.len().checked_sub(1) else {
+            return TextPosition { line: 0, column: 0 };
+        };
+        let line = ((location.y + self.scroll).into_float() / self.line_height.into_float())
+            .floor()
+            .max(0.) as usize;
+        let line = line.min(last_line);
+
+        let mut x = Px::ZERO;
+        let mut column = 0;
+        for span in &self.measured_lines[line] {
+            let chars = span_columns(span);
+            let width = span.measured.size.width;
+            if location.x < x + width && chars > 0 {
+                // Code is always monospace, so each character within a span
+                // covers an equal share of its width.
+                let offset = ((location.x - x).into_float() / width.into_float() * chars as f32)
+                    .round() as usize;
+                return TextPosition {
+                    line,
+                    column: column + offset.min(chars),
+                };
+            }
+            x += width;
+            column += chars;
+        }
+        TextPosition { line, column }
+    }
+
+    fn column_x(&self, line: &[Span], column: usize) -> Px {
+        let mut x = Px::ZERO;
+        let mut remaining = column;
+        for span in line {
+            let chars = span_columns(span);
+            if remaining <= chars {
+                if chars > 0 {
+                    x += Px::from(
+                        span.measured.size.width.into_float() * remaining as f32 / chars as f32,
+                    );
                 }
-                self.measured_lines.push(spans);
-                max_x = max_x.max(x);
+                return x;
             }
+            remaining -= chars;
+            x += span.measured.size.width;
+        }
+        x
+    }
+
+    fn selected_text(&self) -> String {
+        let Some((start, end)) = self.selection.and_then(Selection::ordered) else {
+            return self.source.to_string();
+        };
+
+        let mut text = String::new();
+        for (index, line) in self
+            .source
+            .lines()
+            .enumerate()
+            .take(end.line + 1)
+            .skip(start.line)
+        {
+            let from = if index == start.line { start.column } else { 0 };
+            let to = if index == end.line {
+                end.column
+            } else {
+                usize::MAX
+            };
+            text.extend(line.chars().skip(from).take(to.saturating_sub(from)));
+            if index != end.line {
+                text.push('\n');
+            }
+        }
+        text
+    }
 
-            self.size = Size::new(max_x, y).into_unsigned();
+    fn copy(&mut self, context: &mut EventContext<'_>) {
+        if let Some(mut clipboard) = context.clipboard_guard() {
+            self.copy_error = clipboard
+                .set_text(self.selected_text())
+                .err()
+                .map(|err| format!("error copying code: {err}"));
+            context.set_needs_redraw();
         }
     }
+
+    fn draw_error(&self, context: &mut GraphicsContext<'_, '_, '_, '_>) {
+        let Some(error) = self.error.as_ref().or(self.copy_error.as_ref()) else {
+            return;
+        };
+        let text = context.gfx.measure_text(Text::new(error, self.error_color));
+        let size = context.gfx.size().into_signed();
+        let origin = Point::new(Px::ZERO, size.height - text.size.height);
+        let background = self.theme.settings.background.map_or(Color::BLACK, color);
+        context.gfx.draw_shape(&Shape::filled_rect(
+            Rect::new(origin, Size::new(size.width, text.size.height)),
+            background,
+        ));
+        context
+            .gfx
+            .draw_measured_text(text.translate_by(origin), TextOrigin::TopLeft);
+    }
+
+    fn draw_selection(&self, context: &mut GraphicsContext<'_, '_, '_, '_>) {
+        let Some((start, end)) = self.selection.and_then(Selection::ordered) else {
+            return;
+        };
+
+        let color = context.get(&HighlightColor).with_alpha(0x60);
+        for (index, line) in self
+            .measured_lines
+            .iter()
+            .enumerate()
+            .take(end.line + 1)
+            .skip(start.line)
+        {
+            let from = if index == start.line {
+                self.column_x(line, start.column)
+            } else {
+                Px::ZERO
+            };
+            let to = if index == end.line {
+                self.column_x(line, end.column)
+            } else {
+                line_width(line)
+            };
+            let y = Px::from(index as f32 * self.line_height.into_float()) - self.scroll;
+            context.gfx.draw_shape(&Shape::filled_rect(
+                Rect::new(Point::new(from, y), Size::new(to - from, self.line_height)),
+                color,
+            ));
+        }
+    }
+
+    fn max_scroll(&self) -> Px {
+        (self.size.height.into_signed() - self.visible_height).max(Px::ZERO)
+    }
+
+    fn scroll_to(&mut self, scroll: Px, context: &mut EventContext<'_>) {
+        let scroll = scroll.min(self.max_scroll()).max(Px::ZERO);
+        if scroll != self.scroll {
+            self.scroll = scroll;
+            context.set_needs_redraw();
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Span {
+    pub text: String,
+    pub measured: MeasuredText<Px>,
+}
+
+pub(crate) fn measure_highlighted(
+    source: &str,
+    extension: &str,
+    syntax_set: &SyntaxSet,
+    theme: &Theme,
+    context: &mut LayoutContext<'_, '_, '_, '_>,
+) -> Vec<Vec<Span>> {
+    let syntax = syntax_set
+        .find_syntax_by_extension(extension)
+        .expect("missing syntax definition");
+    let mut highlighter = HighlightLines::new(syntax, theme);
+    let mut lines = Vec::new();
+    for line in LinesWithEndings::from(source) {
+        let mut spans = Vec::new();
+        for (style, text) in highlighter
+            .highlight_line(line, syntax_set)
+            .expect("invalid syntax")
+        {
+            if style.font_style.contains(FontStyle::BOLD) {
+                context.gfx.set_font_weight(Weight::BOLD);
+            } else {
+                context.gfx.set_font_weight(Weight::NORMAL);
+            }
+
+            if style.font_style.contains(FontStyle::ITALIC) {
+                context.gfx.set_font_style(Style::Italic);
+            } else {
+                context.gfx.set_font_style(Style::Normal);
+            }
+
+            for token in tokens(text) {
+                spans.push(Span {
+                    text: token.to_string(),
+                    measured: context
+                        .gfx
+                        .measure_text(Text::new(token, color(style.foreground))),
+                });
+            }
+        }
+        lines.push(spans);
+    }
+    lines
+}
+
+pub(crate) fn monospace_cell(context: &mut LayoutContext<'_, '_, '_, '_>) -> Size<Px> {
+    context.gfx.set_font_family(FamilyOwned::Monospace);
+    let measured = context.gfx.measure_text(Text::new("0", Color::WHITE));
+    Size::new(
+        measured.size.width,
+        context.gfx.line_height().into_px(context.gfx.scale()),
+    )
+}
+
+// Syntax highlighting groups text such as `foo.bar(baz)` into a single span,
+// so spans are split into words, runs of whitespace and punctuation
+// characters. This lets code morphs match the individual pieces.
+fn tokens(text: &str) -> impl Iterator<Item = &str> {
+    let class = |ch: char| {
+        if ch.is_alphanumeric() || ch == '_' {
+            0
+        } else if ch.is_whitespace() {
+            1
+        } else {
+            2
+        }
+    };
+    let mut remaining = text;
+    std::iter::from_fn(move || {
+        let first = remaining.chars().next()?;
+        let length = if class(first) == 2 {
+            first.len_utf8()
+        } else {
+            remaining
+                .find(|ch| class(ch) != class(first))
+                .unwrap_or(remaining.len())
+        };
+        let (token, rest) = remaining.split_at(length);
+        remaining = rest;
+        Some(token)
+    })
+}
+
+pub(crate) fn line_width(spans: &[Span]) -> Px {
+    spans
+        .iter()
+        .fold(Px::ZERO, |width, span| width + span.measured.size.width)
+}
+
+pub(crate) fn draw_spans(
+    spans: &[Span],
+    origin: Point<Px>,
+    context: &mut GraphicsContext<'_, '_, '_, '_>,
+) {
+    let mut x = origin.x;
+    for span in spans {
+        context.gfx.draw_measured_text(
+            span.measured.translate_by(Point::new(x, origin.y)),
+            TextOrigin::TopLeft,
+        );
+        x += span.measured.size.width;
+    }
+}
+
+fn span_columns(span: &Span) -> usize {
+    span.text.trim_end_matches(['\r', '\n']).chars().count()
 }
 
 fn color(color: syntect::highlighting::Color) -> Color {
@@ -99,26 +480,225 @@ fn color(color: syntect::highlighting::Color) -> Color {
 }
 
 impl Widget for CodeView {
-    fn redraw(&mut self, context: &mut cushy::context::GraphicsContext<'_, '_, '_, '_>) {
-        let mut y = Px::ZERO;
+    fn redraw(&mut self, context: &mut GraphicsContext<'_, '_, '_, '_>) {
+        if let Some(morph) = &self.morph {
+            let origin = context.gfx.region().origin;
+            let mut y = origin.y - self.scroll;
+            morph.place_lines(
+                self.measured_lines
+                    .iter()
+                    .map(|line| {
+                        let area = Rect::new(
+                            Point::new(origin.x, y),
+                            Size::new(line_width(line), self.line_height),
+                        );
+                        y += self.line_height;
+                        area
+                    })
+                    .collect(),
+            );
+            if morph.draw(Point::new(Px::ZERO, -self.scroll), context) {
+                self.draw_error(context);
+                return;
+            }
+        }
+
+        self.draw_selection(context);
+
+        let mut y = -self.scroll;
         for line in &self.measured_lines {
-            let mut x = Px::ZERO;
-            for span in line {
-                context
-                    .gfx
-                    .draw_measured_text(span.translate_by(Point::new(x, y)), TextOrigin::TopLeft);
-                x += span.size.width;
+            if y + self.line_height > Px::ZERO {
+                draw_spans(line, Point::new(Px::ZERO, y), context);
             }
             y += self.line_height;
+            if self.overflow == CodeOverflow::Scroll && y > self.visible_height {
+                break;
+            }
         }
+        self.draw_error(context);
     }
 
     fn layout(
         &mut self,
-        _available_space: cushy::figures::Size<cushy::ConstraintLimit>,
-        context: &mut cushy::context::LayoutContext<'_, '_, '_, '_>,
-    ) -> cushy::figures::Size<cushy::figures::units::UPx> {
-        self.highlight(context);
-        self.size
+        available_space: Size<ConstraintLimit>,
+        context: &mut LayoutContext<'_, '_, '_, '_>,
+    ) -> Size<UPx> {
+        self.highlight(available_space, context);
+        match self.overflow {
+            CodeOverflow::Clip | CodeOverflow::Fit => self.size,
+            CodeOverflow::Scroll => {
+                let size = Size::new(
+                    self.size.width,
+                    self.size.height.min(available_space.height.max()),
+                );
+                self.visible_height = size.height.into_signed();
+                self.scroll = self.scroll.min(self.max_scroll());
+                size
+            }
+        }
+    }
+
+    fn hit_test(&mut self, _location: Point<Px>, _context: &mut EventContext<'_>) -> bool {
+        true
+    }
+
+    // Code blocks only hold the keyboard focus while text is selected, so
+    // clicking on one doesn't take slide navigation away from the deck.
+    fn accept_focus(&mut self, _context: &mut EventContext<'_>) -> bool {
+        self.selection.and_then(Selection::ordered).is_some()
+    }
+
+    fn mouse_down(
+        &mut self,
+        location: Point<Px>,
+        _device_id: DeviceId,
+        button: MouseButton,
+        context: &mut EventContext<'_>,
+    ) -> EventHandling {
+        if button != MouseButton::Left {
+            return IGNORED;
+        }
+
+        let position = self.position_at(location);
+        self.selection = Some(Selection {
+            anchor: position,
+            cursor: position,
+        });
+        context.set_needs_redraw();
+        HANDLED
+    }
+
+    fn mouse_drag(
+        &mut self,
+        location: Point<Px>,
+        _device_id: DeviceId,
+        button: MouseButton,
+        context: &mut EventContext<'_>,
+    ) {
+        if button != MouseButton::Left {
+            return;
+        }
+
+        let position = self.position_at(location);
+        if let Some(selection) = &mut self.selection {
+            if selection.cursor != position {
+                selection.cursor = position;
+                context.set_needs_redraw();
+                if selection.ordered().is_some() {
+                    context.focus();
+                }
+            }
+        }
+    }
+
+    fn mouse_up(
+        &mut self,
+        _location: Option<Point<Px>>,
+        _device_id: DeviceId,
+        button: MouseButton,
+        context: &mut EventContext<'_>,
+    ) {
+        // A click without a drag clears the selection and hands the keyboard
+        // back to the slides.
+        if button == MouseButton::Left && self.selection.and_then(Selection::ordered).is_none() {
+            self.selection = None;
+            context.blur();
+            context.set_needs_redraw();
+        }
+    }
+
+    fn mouse_wheel(
+        &mut self,
+        _device_id: DeviceId,
+        delta: MouseScrollDelta,
+        _phase: TouchPhase,
+        context: &mut EventContext<'_>,
+    ) -> EventHandling {
+        if self.overflow != CodeOverflow::Scroll {
+            return IGNORED;
+        }
+
+        let delta = match delta {
+            MouseScrollDelta::LineDelta(_, y) => Px::from(y * self.line_height.into_float()),
+            MouseScrollDelta::PixelDelta(delta) => Px::from(delta.y as f32),
+        };
+        self.scroll_to(self.scroll - delta, context);
+        HANDLED
+    }
+
+    fn keyboard_input(
+        &mut self,
+        _device_id: DeviceId,
+        input: KeyEvent,
+        _is_synthetic: bool,
+        context: &mut EventContext<'_>,
+    ) -> EventHandling {
+        let modifiers = context.modifiers().state();
+        if modifiers.control_key() || modifiers.super_key() {
+            return match input.logical_key {
+                Key::Character(ch) if ch.eq_ignore_ascii_case("c") => {
+                    if input.state.is_pressed() {
+                        self.copy(context);
+                    }
+                    HANDLED
+                }
+                Key::Character(ch) if ch.eq_ignore_ascii_case("a") => {
+                    if input.state.is_pressed() {
+                        let line = self.measured_lines.len().saturating_sub(1);
+                        self.selection = Some(Selection {
+                            anchor: TextPosition { line: 0, column: 0 },
+                            cursor: TextPosition {
+                                line,
+                                column: usize::MAX,
+                            },
+                        });
+                        context.set_needs_redraw();
+                    }
+                    HANDLED
+                }
+                _ => IGNORED,
+            };
+        }
+
+        if self.overflow != CodeOverflow::Scroll || !modifiers.is_empty() {
+            return IGNORED;
+        }
+
+        let page = (self.visible_height - self.line_height).max(self.line_height);
+        let scroll = match input.logical_key {
+            Key::Named(NamedKey::ArrowDown) => self.scroll + self.line_height,
+            Key::Named(NamedKey::ArrowUp) => self.scroll - self.line_height,
+            Key::Named(NamedKey::PageDown) => self.scroll + page,
+            Key::Named(NamedKey::PageUp) => self.scroll - page,
+            Key::Named(NamedKey::Home) => Px::ZERO,
+            Key::Named(NamedKey::End) => self.max_scroll(),
+            _ => return IGNORED,
+        };
+
+        if input.state.is_pressed() {
+            self.scroll_to(scroll, context);
+        }
+        HANDLED
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::tokens;
+
+    #[test]
+    fn splits_words_and_punctuation() {
+        assert_eq!(
+            tokens("foo.bar(baz_1, 2)").collect::<Vec<_>>(),
+            ["foo", ".", "bar", "(", "baz_1", ",", " ", "2", ")"]
+        );
+    }
+
+    #[test]
+    fn keeps_whitespace_runs() {
+        assert_eq!(
+            tokens("    let x\n").collect::<Vec<_>>(),
+            ["    ", "let", " ", "x", "\n"]
+        );
     }
 }
@@ -0,0 +1,1080 @@
+use std::collections::HashMap;
+use std::iter::Peekable;
+use std::str::Chars;
+use std::sync::Arc;
+
+use cushy::context::{GraphicsContext, LayoutContext};
+use cushy::figures::units::{Px, UPx};
+use cushy::figures::{FloatConversion, Rect, ScreenScale, Size};
+use cushy::kludgine::shapes::{Path, Shape, StrokeOptions};
+use cushy::kludgine::text::{MeasuredText, Text, TextOrigin};
+use cushy::kludgine::DrawableExt;
+use cushy::styles::components::TextSize;
+use cushy::styles::Color;
+use cushy::value::{Dynamic, Source};
+use cushy::widget::Widget;
+use cushy::ConstraintLimit;
+
+use crate::shapes::{arrow_head, dashes, ellipse, point, polygon, polyline, rounded_rect};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum RankDir {
+    TopToBottom,
+    BottomToTop,
+    LeftToRight,
+    RightToLeft,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum NodeShape {
+    Box,
+    Rounded,
+    Ellipse,
+    Circle,
+    Diamond,
+    Plain,
+}
+
+#[derive(Debug)]
+struct GraphNode {
+    label: String,
+    shape: NodeShape,
+}
+
+#[derive(Debug)]
+struct GraphEdge {
+    from: usize,
+    to: usize,
+    label: Option<String>,
+    dashed: bool,
+}
+
+#[derive(Debug)]
+pub(crate) struct Graph {
+    directed: bool,
+    rank_dir: RankDir,
+    nodes: Vec<GraphNode>,
+    edges: Vec<GraphEdge>,
+}
+
+impl Graph {
+    pub(crate) fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+}
+
+pub(crate) fn parse(source: &str) -> Result<Arc<Graph>, String> {
+    let mut parser = Parser {
+        chars: source.chars().peekable(),
+        peeked: None,
+        graph: Graph {
+            directed: true,
+            rank_dir: RankDir::TopToBottom,
+            nodes: Vec::new(),
+            edges: Vec::new(),
+        },
+        ids: HashMap::new(),
+        node_defaults: Vec::new(),
+        edge_defaults: Vec::new(),
+    };
+    parser.graph()?;
+    Ok(Arc::new(parser.graph))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Id(String),
+    Punct(char),
+    Edge(bool),
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Id(id) => format!("`{id}`"),
+            Token::Punct(ch) => format!("`{ch}`"),
+            Token::Edge(true) => String::from("`->`"),
+            Token::Edge(false) => String::from("`--`"),
+        }
+    }
+}
+
+type Attributes = Vec<(String, String)>;
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+    peeked: Option<Option<Token>>,
+    graph: Graph,
+    ids: HashMap<String, usize>,
+    node_defaults: Attributes,
+    edge_defaults: Attributes,
+}
+
+impl Parser<'_> {
+    fn skip_trivia(&mut self) {
+        loop {
+            while self.chars.next_if(|ch| ch.is_whitespace()).is_some() {}
+            match self.chars.peek() {
+                Some('#') => while self.chars.next_if(|ch| *ch != '\n').is_some() {},
+                Some('/') => {
+                    let mut lookahead = self.chars.clone();
+                    lookahead.next();
+                    match lookahead.next() {
+                        Some('/') => while self.chars.next_if(|ch| *ch != '\n').is_some() {},
+                        Some('*') => {
+                            self.chars.next();
+                            self.chars.next();
+                            let mut previous = '\0';
+                            for ch in self.chars.by_ref() {
+                                if previous == '*' && ch == '/' {
+                                    break;
+                                }
+                                previous = ch;
+                            }
+                        }
+                        _ => return,
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn read_token(&mut self) -> Result<Option<Token>, String> {
+        self.skip_trivia();
+        let Some(ch) = self.chars.next() else {
+            return Ok(None);
+        };
+        let token = match ch {
+            '"' => {
+                let mut id = String::new();
+                loop {
+                    match self.chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match self.chars.next() {
+                            Some('n' | 'l' | 'r') => id.push('\n'),
+                            Some(ch) => id.push(ch),
+                            None => return Err(String::from("unterminated string")),
+                        },
+                        Some(ch) => id.push(ch),
+                        None => return Err(String::from("unterminated string")),
+                    }
+                }
+                Token::Id(id)
+            }
+            '-' if self.chars.next_if_eq(&'>').is_some() => Token::Edge(true),
+            '-' if self.chars.next_if_eq(&'-').is_some() => Token::Edge(false),
+            ch if ch.is_alphanumeric() || ch == '_' || ch == '.' || ch == '-' => {
+                let mut id = ch.to_string();
+                while let Some(ch) = self
+                    .chars
+                    .next_if(|ch| ch.is_alphanumeric() || *ch == '_' || *ch == '.')
+                {
+                    id.push(ch);
+                }
+                Token::Id(id)
+            }
+            '{' | '}' | '[' | ']' | '=' | ';' | ',' => Token::Punct(ch),
+            ch => return Err(format!("unexpected `{ch}`")),
+        };
+        Ok(Some(token))
+    }
+
+    fn peek(&mut self) -> Result<Option<&Token>, String> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.read_token()?);
+        }
+        Ok(self.peeked.as_ref().and_then(Option::as_ref))
+    }
+
+    fn next(&mut self) -> Result<Option<Token>, String> {
+        match self.peeked.take() {
+            Some(token) => Ok(token),
+            None => self.read_token(),
+        }
+    }
+
+    fn next_if(&mut self, token: &Token) -> Result<bool, String> {
+        if self.peek()? == Some(token) {
+            self.next()?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn expect(&mut self, expected: &Token) -> Result<(), String> {
+        match self.next()? {
+            Some(token) if &token == expected => Ok(()),
+            Some(token) => Err(format!(
+                "expected {}, found {}",
+                expected.describe(),
+                token.describe()
+            )),
+            None => Err(format!("expected {}", expected.describe())),
+        }
+    }
+
+    fn id(&mut self) -> Result<String, String> {
+        match self.next()? {
+            Some(Token::Id(id)) => Ok(id),
+            Some(token) => Err(format!(
+                "expected an identifier, found {}",
+                token.describe()
+            )),
+            None => Err(String::from("expected an identifier")),
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str) -> Result<bool, String> {
+        match self.peek()? {
+            Some(Token::Id(id)) if id.eq_ignore_ascii_case(keyword) => {
+                self.next()?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn graph(&mut self) -> Result<(), String> {
+        // The `digraph name { ... }` wrapper is optional, so a slide can
+        // contain just the statements.
+        self.keyword("strict")?;
+        let wrapped = if self.keyword("digraph")? {
+            true
+        } else if self.keyword("graph")? {
+            self.graph.directed = false;
+            true
+        } else {
+            false
+        };
+
+        if wrapped {
+            if matches!(self.peek()?, Some(Token::Id(_))) {
+                self.next()?;
+            }
+            self.expect(&Token::Punct('{'))?;
+            self.statements(true)?;
+            if let Some(token) = self.next()? {
+                return Err(format!("unexpected {} after graph", token.describe()));
+            }
+            Ok(())
+        } else {
+            self.statements(false)
+        }
+    }
+
+    fn statements(&mut self, braced: bool) -> Result<(), String> {
+        loop {
+            match self.peek()? {
+                None if braced => return Err(String::from("missing `}`")),
+                None => return Ok(()),
+                Some(Token::Punct('}')) if braced => {
+                    self.next()?;
+                    return Ok(());
+                }
+                Some(Token::Punct(';' | ',')) => {
+                    self.next()?;
+                }
+                Some(_) => self.statement()?,
+            }
+        }
+    }
+
+    fn statement(&mut self) -> Result<(), String> {
+        if self.keyword("subgraph")? || self.peek()? == Some(&Token::Punct('{')) {
+            return Err(String::from("subgraphs are not supported"));
+        }
+
+        let id = self.id()?;
+        match id.as_str() {
+            "graph" => {
+                for (key, value) in self.attributes()? {
+                    self.graph_attribute(&key, &value)?;
+                }
+                return Ok(());
+            }
+            "node" => {
+                let attributes = self.attributes()?;
+                self.node_defaults.extend(attributes);
+                return Ok(());
+            }
+            "edge" => {
+                let attributes = self.attributes()?;
+                self.edge_defaults.extend(attributes);
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        if self.next_if(&Token::Punct('='))? {
+            let value = self.id()?;
+            return self.graph_attribute(&id, &value);
+        }
+
+        let mut chain = vec![id];
+        while let Some(&Token::Edge(directed)) = self.peek()? {
+            self.next()?;
+            if directed != self.graph.directed {
+                return Err(if directed {
+                    String::from("`->` used in an undirected graph")
+                } else {
+                    String::from("`--` used in a directed graph")
+                });
+            }
+            chain.push(self.id()?);
+        }
+        let attributes = self.attributes()?;
+
+        if chain.len() == 1 {
+            let node = self.node(&chain[0])?;
+            for (key, value) in &attributes {
+                Self::node_attribute(&mut self.graph.nodes[node], key, value)?;
+            }
+        } else {
+            let nodes = chain
+                .iter()
+                .map(|id| self.node(id))
+                .collect::<Result<Vec<_>, _>>()?;
+            for pair in nodes.windows(2) {
+                let mut edge = GraphEdge {
+                    from: pair[0],
+                    to: pair[1],
+                    label: None,
+                    dashed: false,
+                };
+                for (key, value) in self.edge_defaults.iter().chain(&attributes) {
+                    match key.as_str() {
+                        "label" => edge.label = Some(value.clone()),
+                        "style" => edge.dashed = matches!(value.as_str(), "dashed" | "dotted"),
+                        _ => {}
+                    }
+                }
+                self.graph.edges.push(edge);
+            }
+        }
+        Ok(())
+    }
+
+    fn attributes(&mut self) -> Result<Attributes, String> {
+        let mut attributes = Vec::new();
+        while self.next_if(&Token::Punct('['))? {
+            while !self.next_if(&Token::Punct(']'))? {
+                let key = self.id()?;
+                self.expect(&Token::Punct('='))?;
+                let value = self.id()?;
+                attributes.push((key, value));
+                if !self.next_if(&Token::Punct(','))? {
+                    self.next_if(&Token::Punct(';'))?;
+                }
+            }
+        }
+        Ok(attributes)
+    }
+
+    fn graph_attribute(&mut self, key: &str, value: &str) -> Result<(), String> {
+        if key == "rankdir" {
+            self.graph.rank_dir = match value.to_ascii_uppercase().as_str() {
+                "TB" => RankDir::TopToBottom,
+                "BT" => RankDir::BottomToTop,
+                "LR" => RankDir::LeftToRight,
+                "RL" => RankDir::RightToLeft,
+                _ => return Err(format!("unknown rankdir `{value}`")),
+            };
+        }
+        Ok(())
+    }
+
+    fn node_attribute(node: &mut GraphNode, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "label" => node.label = value.to_string(),
+            "shape" => {
+                node.shape = match value {
+                    "box" | "rect" | "rectangle" | "square" => NodeShape::Box,
+                    "ellipse" | "oval" => NodeShape::Ellipse,
+                    "circle" | "doublecircle" | "point" => NodeShape::Circle,
+                    "diamond" => NodeShape::Diamond,
+                    "plaintext" | "plain" | "none" => NodeShape::Plain,
+                    _ => return Err(format!("unknown shape `{value}`")),
+                };
+            }
+            "style" if value == "rounded" && node.shape == NodeShape::Box => {
+                node.shape = NodeShape::Rounded;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn node(&mut self, id: &str) -> Result<usize, String> {
+        if let Some(index) = self.ids.get(id) {
+            return Ok(*index);
+        }
+
+        let mut node = GraphNode {
+            label: id.to_string(),
+            shape: NodeShape::Ellipse,
+        };
+        for (key, value) in &self.node_defaults {
+            Self::node_attribute(&mut node, key, value)?;
+        }
+        let index = self.graph.nodes.len();
+        self.graph.nodes.push(node);
+        self.ids.insert(id.to_string(), index);
+        Ok(index)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Extent {
+    breadth: f32,
+    depth: f32,
+}
+
+#[derive(Debug, Default)]
+struct Arrangement {
+    centers: Vec<(f32, f32)>,
+    edges: Vec<Vec<(f32, f32)>>,
+    width: f32,
+    height: f32,
+}
+
+fn ranks(graph: &Graph) -> (Vec<usize>, Vec<bool>) {
+    let mut outgoing = vec![Vec::new(); graph.nodes.len()];
+    for (index, edge) in graph.edges.iter().enumerate() {
+        outgoing[edge.from].push(index);
+    }
+
+    // Edges that close a cycle are reversed, which turns the graph into a
+    // DAG that can be layered.
+    fn visit(
+        node: usize,
+        graph: &Graph,
+        outgoing: &[Vec<usize>],
+        state: &mut [u8],
+        reversed: &mut [bool],
+    ) {
+        state[node] = 1;
+        for &edge in &outgoing[node] {
+            let to = graph.edges[edge].to;
+            match state[to] {
+                0 => visit(to, graph, outgoing, state, reversed),
+                1 => reversed[edge] = true,
+                _ => {}
+            }
+        }
+        state[node] = 2;
+    }
+    let mut state = vec![0; graph.nodes.len()];
+    let mut reversed = vec![false; graph.edges.len()];
+    for node in 0..graph.nodes.len() {
+        if state[node] == 0 {
+            visit(node, graph, &outgoing, &mut state, &mut reversed);
+        }
+    }
+
+    let forward = graph
+        .edges
+        .iter()
+        .zip(&reversed)
+        .filter(|(edge, _)| edge.from != edge.to)
+        .map(|(edge, &reversed)| {
+            if reversed {
+                (edge.to, edge.from)
+            } else {
+                (edge.from, edge.to)
+            }
+        })
+        .collect::<Vec<_>>();
+    let mut incoming = vec![0; graph.nodes.len()];
+    for &(_, to) in &forward {
+        incoming[to] += 1;
+    }
+    let mut ranks = vec![0; graph.nodes.len()];
+    let mut ready = (0..graph.nodes.len())
+        .filter(|node| incoming[*node] == 0)
+        .collect::<Vec<_>>();
+    while let Some(node) = ready.pop() {
+        for &(from, to) in &forward {
+            if from == node {
+                ranks[to] = ranks[to].max(ranks[node] + 1);
+                incoming[to] -= 1;
+                if incoming[to] == 0 {
+                    ready.push(to);
+                }
+            }
+        }
+    }
+    (ranks, reversed)
+}
+
+fn arrange(graph: &Graph, extents: &[Extent], node_gap: f32, rank_gap: f32) -> Arrangement {
+    let (node_ranks, reversed) = ranks(graph);
+
+    // Edges spanning several ranks are routed through virtual vertices so
+    // that they bend around the nodes in between.
+    let mut vertex_ranks = node_ranks.clone();
+    let mut vertex_extents = extents.to_vec();
+    let mut chains = Vec::with_capacity(graph.edges.len());
+    for (edge, &reversed) in graph.edges.iter().zip(&reversed) {
+        let (from, to) = if reversed {
+            (edge.to, edge.from)
+        } else {
+            (edge.from, edge.to)
+        };
+        let mut chain = vec![from];
+        if from != to {
+            for rank in node_ranks[from] + 1..node_ranks[to] {
+                chain.push(vertex_ranks.len());
+                vertex_ranks.push(rank);
+                vertex_extents.push(Extent::default());
+            }
+        }
+        chain.push(to);
+        if reversed {
+            chain.reverse();
+        }
+        chains.push(chain);
+    }
+
+    let vertices = vertex_ranks.len();
+    let mut up = vec![Vec::new(); vertices];
+    let mut down = vec![Vec::new(); vertices];
+    for chain in &chains {
+        for pair in chain.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            if vertex_ranks[a] + 1 == vertex_ranks[b] {
+                down[a].push(b);
+                up[b].push(a);
+            } else if vertex_ranks[b] + 1 == vertex_ranks[a] {
+                down[b].push(a);
+                up[a].push(b);
+            }
+        }
+    }
+
+    let rank_count = vertex_ranks.iter().max().map_or(0, |rank| rank + 1);
+    let mut layers = vec![Vec::new(); rank_count];
+    for (vertex, &rank) in vertex_ranks.iter().enumerate() {
+        layers[rank].push(vertex);
+    }
+
+    // Barycenter ordering reduces crossings by sorting each layer by the
+    // average position of its neighbors in the previous layer.
+    let mut order = vec![0.; vertices];
+    for layer in &layers {
+        for (index, &vertex) in layer.iter().enumerate() {
+            order[vertex] = index as f32;
+        }
+    }
+    for iteration in 0..8 {
+        let downward = iteration % 2 == 0;
+        let ranks = if downward {
+            (1..rank_count).collect::<Vec<_>>()
+        } else {
+            (0..rank_count.saturating_sub(1)).rev().collect()
+        };
+        for rank in ranks {
+            let neighbors = if downward { &up } else { &down };
+            let barycenters = layers[rank]
+                .iter()
+                .map(|&vertex| {
+                    mean(neighbors[vertex].iter().map(|n| order[*n])).unwrap_or(order[vertex])
+                })
+                .collect::<Vec<_>>();
+            let mut sorted = layers[rank]
+                .iter()
+                .copied()
+                .zip(barycenters)
+                .collect::<Vec<_>>();
+            sorted.sort_by(|a, b| a.1.total_cmp(&b.1));
+            layers[rank] = sorted.into_iter().map(|(vertex, _)| vertex).collect();
+            for (index, &vertex) in layers[rank].iter().enumerate() {
+                order[vertex] = index as f32;
+            }
+        }
+    }
+
+    let mut x = vec![0.; vertices];
+    for layer in &layers {
+        let mut cursor = 0.;
+        for &vertex in layer {
+            x[vertex] = cursor + vertex_extents[vertex].breadth / 2.;
+            cursor += vertex_extents[vertex].breadth + node_gap;
+        }
+    }
+    for iteration in 0..8 {
+        let downward = iteration % 2 == 0;
+        let neighbors = if downward { &up } else { &down };
+        for rank in 0..rank_count {
+            let rank = if downward {
+                rank
+            } else {
+                rank_count - 1 - rank
+            };
+            let layer = &layers[rank];
+            let desired = layer
+                .iter()
+                .map(|&vertex| mean(neighbors[vertex].iter().map(|n| x[*n])).unwrap_or(x[vertex]))
+                .collect::<Vec<_>>();
+            let separation = |a: usize, b: usize| {
+                (vertex_extents[layer[a]].breadth + vertex_extents[layer[b]].breadth) / 2.
+                    + node_gap
+            };
+            // Packing from both sides and averaging keeps the nodes as close
+            // to their neighbors as possible without overlapping.
+            let mut left = desired.clone();
+            for index in 1..layer.len() {
+                left[index] = left[index].max(left[index - 1] + separation(index - 1, index));
+            }
+            let mut right = desired;
+            for index in (0..layer.len().saturating_sub(1)).rev() {
+                right[index] = right[index].min(right[index + 1] - separation(index, index + 1));
+            }
+            for (index, &vertex) in layer.iter().enumerate() {
+                x[vertex] = (left[index] + right[index]) / 2.;
+            }
+        }
+    }
+
+    let min_x = (0..vertices)
+        .map(|vertex| x[vertex] - vertex_extents[vertex].breadth / 2.)
+        .fold(f32::INFINITY, f32::min);
+    let breadth = (0..vertices)
+        .map(|vertex| x[vertex] + vertex_extents[vertex].breadth / 2. - min_x)
+        .fold(0., f32::max);
+    let mut rank_positions = Vec::with_capacity(rank_count);
+    let mut depth = 0.;
+    for layer in &layers {
+        let rank_depth = layer
+            .iter()
+            .map(|&vertex| vertex_extents[vertex].depth)
+            .fold(0., f32::max);
+        rank_positions.push(depth + rank_depth / 2.);
+        depth += rank_depth + rank_gap;
+    }
+    let depth = (depth - rank_gap).max(0.);
+
+    let position = |vertex: usize| {
+        let along = x[vertex] - min_x;
+        let across = rank_positions[vertex_ranks[vertex]];
+        match graph.rank_dir {
+            RankDir::TopToBottom => (along, across),
+            RankDir::BottomToTop => (along, depth - across),
+            RankDir::LeftToRight => (across, along),
+            RankDir::RightToLeft => (depth - across, along),
+        }
+    };
+    let (width, height) = match graph.rank_dir {
+        RankDir::TopToBottom | RankDir::BottomToTop => (breadth, depth),
+        RankDir::LeftToRight | RankDir::RightToLeft => (depth, breadth),
+    };
+
+    Arrangement {
+        centers: (0..graph.nodes.len()).map(position).collect(),
+        edges: chains
+            .iter()
+            .map(|chain| chain.iter().map(|&vertex| position(vertex)).collect())
+            .collect(),
+        width,
+        height,
+    }
+}
+
+fn mean(values: impl Iterator<Item = f32>) -> Option<f32> {
+    let (sum, count) = values.fold((0., 0), |(sum, count), value| (sum + value, count + 1));
+    (count > 0).then(|| sum / count as f32)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DiagramColors {
+    pub node: Color,
+    pub outline: Color,
+    pub text: Color,
+    pub edge: Color,
+    pub background: Color,
+}
+
+#[derive(Debug)]
+struct PlacedNode {
+    center: (f32, f32),
+    size: (f32, f32),
+    label: MeasuredText<Px>,
+}
+
+#[derive(Debug)]
+struct PlacedEdge {
+    points: Vec<(f32, f32)>,
+    label: Option<(MeasuredText<Px>, (f32, f32))>,
+}
+
+#[derive(Debug)]
+pub struct DiagramView {
+    graph: Arc<Graph>,
+    colors: DiagramColors,
+    visible: Dynamic<usize>,
+    cached: Option<(f32, Size<UPx>)>,
+    nodes: Vec<PlacedNode>,
+    edges: Vec<PlacedEdge>,
+    stroke: f32,
+    size: Size<UPx>,
+}
+
+impl DiagramView {
+    pub fn new(graph: Arc<Graph>, colors: DiagramColors, visible: Dynamic<usize>) -> Self {
+        Self {
+            graph,
+            colors,
+            visible,
+            cached: None,
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            stroke: 1.,
+            size: Size::default(),
+        }
+    }
+
+    fn arrange(&mut self, context: &mut LayoutContext<'_, '_, '_, '_>, text_size: f32) {
+        context.apply_current_font_settings();
+        context.gfx.set_font_size(Px::from(text_size));
+        let padding = (text_size * 0.6, text_size * 0.4);
+        let labels = self
+            .graph
+            .nodes
+            .iter()
+            .map(|node| {
+                context
+                    .gfx
+                    .measure_text(Text::new(&node.label, self.colors.text))
+            })
+            .collect::<Vec<MeasuredText<Px>>>();
+        let sizes = self
+            .graph
+            .nodes
+            .iter()
+            .zip(&labels)
+            .map(|(node, label)| {
+                let width = label.size.width.into_float() + padding.0 * 2.;
+                let height = label.size.height.into_float() + padding.1 * 2.;
+                match node.shape {
+                    NodeShape::Box | NodeShape::Rounded | NodeShape::Plain => (width, height),
+                    NodeShape::Ellipse => (width * 1.25, height * 1.25),
+                    NodeShape::Circle => {
+                        let diameter = width.max(height) * 1.1;
+                        (diameter, diameter)
+                    }
+                    NodeShape::Diamond => (width * 1.6, height * 1.6),
+                }
+            })
+            .collect::<Vec<_>>();
+        let extents = sizes
+            .iter()
+            .map(|&(width, height)| match self.graph.rank_dir {
+                RankDir::TopToBottom | RankDir::BottomToTop => Extent {
+                    breadth: width,
+                    depth: height,
+                },
+                RankDir::LeftToRight | RankDir::RightToLeft => Extent {
+                    breadth: height,
+                    depth: width,
+                },
+            })
+            .collect::<Vec<_>>();
+
+        context.gfx.set_font_size(Px::from(text_size * 0.75));
+        let edge_labels = self
+            .graph
+            .edges
+            .iter()
+            .map(|edge| {
+                edge.label
+                    .as_ref()
+                    .map(|label| context.gfx.measure_text(Text::new(label, self.colors.text)))
+            })
+            .collect::<Vec<Option<MeasuredText<Px>>>>();
+        let label_depth = edge_labels
+            .iter()
+            .flatten()
+            .map(|label| match self.graph.rank_dir {
+                RankDir::TopToBottom | RankDir::BottomToTop => label.size.height.into_float(),
+                RankDir::LeftToRight | RankDir::RightToLeft => label.size.width.into_float(),
+            })
+            .fold(0., f32::max);
+        context.apply_current_font_settings();
+
+        let arrangement = arrange(
+            &self.graph,
+            &extents,
+            text_size,
+            text_size * 1.5 + label_depth,
+        );
+        let margin = text_size * 0.25;
+        let offset = |(x, y): (f32, f32)| (x + margin, y + margin);
+
+        self.nodes = labels
+            .into_iter()
+            .zip(&sizes)
+            .zip(&arrangement.centers)
+            .map(|((label, &size), &center)| PlacedNode {
+                center: offset(center),
+                size,
+                label,
+            })
+            .collect();
+        self.edges = self
+            .graph
+            .edges
+            .iter()
+            .zip(arrangement.edges)
+            .zip(edge_labels)
+            .map(|((edge, points), label)| {
+                let mut points = points.into_iter().map(offset).collect::<Vec<_>>();
+                let from = &self.nodes[edge.from];
+                let to = &self.nodes[edge.to];
+                if edge.from == edge.to {
+                    // Loops are drawn as a small square to the node's right.
+                    let right = from.center.0 + from.size.0 / 2.;
+                    let quarter = from.size.1 / 4.;
+                    let reach = text_size * 0.8;
+                    points = vec![
+                        (right - quarter / 2., from.center.1 - quarter),
+                        (right + reach, from.center.1 - quarter),
+                        (right + reach, from.center.1 + quarter),
+                        (right - quarter / 2., from.center.1 + quarter),
+                    ];
+                } else {
+                    let last = points.len() - 1;
+                    points[0] = boundary(from, self.graph.nodes[edge.from].shape, points[1]);
+                    points[last] = boundary(to, self.graph.nodes[edge.to].shape, points[last - 1]);
+                }
+                let label = label.map(|label| {
+                    let middle = points.len() / 2;
+                    let (a, b) = (points[middle - 1], points[middle]);
+                    (label, ((a.0 + b.0) / 2., (a.1 + b.1) / 2.))
+                });
+                PlacedEdge { points, label }
+            })
+            .collect();
+
+        self.stroke = (text_size / 14.).max(1.);
+        self.size = Size::new(
+            UPx::from((arrangement.width + margin * 2.).ceil()),
+            UPx::from((arrangement.height + margin * 2.).ceil()),
+        );
+    }
+}
+
+fn boundary(node: &PlacedNode, shape: NodeShape, toward: (f32, f32)) -> (f32, f32) {
+    let (dx, dy) = (toward.0 - node.center.0, toward.1 - node.center.1);
+    let (half_width, half_height) = (node.size.0 / 2., node.size.1 / 2.);
+    if dx == 0. && dy == 0. {
+        return node.center;
+    }
+    let scale = match shape {
+        NodeShape::Box | NodeShape::Rounded | NodeShape::Plain => {
+            (half_width / dx.abs()).min(half_height / dy.abs())
+        }
+        NodeShape::Ellipse | NodeShape::Circle => {
+            1. / ((dx / half_width).powi(2) + (dy / half_height).powi(2)).sqrt()
+        }
+        NodeShape::Diamond => 1. / (dx.abs() / half_width + dy.abs() / half_height),
+    };
+    (node.center.0 + dx * scale, node.center.1 + dy * scale)
+}
+
+fn outline(
+    shape: NodeShape,
+    (x, y): (f32, f32),
+    (width, height): (f32, f32),
+) -> Option<Path<Px, false>> {
+    let (left, top, right, bottom) = (
+        x - width / 2.,
+        y - height / 2.,
+        x + width / 2.,
+        y + height / 2.,
+    );
+    match shape {
+        NodeShape::Plain => None,
+        NodeShape::Box => polygon(&[(left, top), (right, top), (right, bottom), (left, bottom)]),
+        NodeShape::Rounded => Some(rounded_rect(
+            (left, top),
+            (right, bottom),
+            (height / 4.).min(width / 4.),
+        )),
+        NodeShape::Ellipse | NodeShape::Circle => Some(ellipse((x, y), (width / 2., height / 2.))),
+        NodeShape::Diamond => polygon(&[(x, top), (right, y), (x, bottom), (left, y)]),
+    }
+}
+
+impl Widget for DiagramView {
+    fn redraw(&mut self, context: &mut GraphicsContext<'_, '_, '_, '_>) {
+        context.redraw_when_changed(&self.visible);
+        let visible = self.visible.get();
+        let stroke = StrokeOptions::px_wide(Px::from(self.stroke)).colored(self.colors.edge);
+
+        for (edge, placed) in self.graph.edges.iter().zip(&self.edges) {
+            if edge.from >= visible || edge.to >= visible {
+                continue;
+            }
+            if edge.dashed {
+                for segment in dashes(&placed.points, self.stroke * 4.) {
+                    if let Some(path) = polyline(&segment) {
+                        context.gfx.draw_shape(&path.stroke(stroke));
+                    }
+                }
+            } else if let Some(path) = polyline(&placed.points) {
+                context.gfx.draw_shape(&path.stroke(stroke));
+            }
+
+            if self.graph.directed {
+                let tip = placed.points[placed.points.len() - 1];
+                let from = placed.points[placed.points.len() - 2];
+                context
+                    .gfx
+                    .draw_shape(&arrow_head(tip, from, self.stroke * 6.).fill(self.colors.edge));
+            }
+
+            if let Some((label, center)) = &placed.label {
+                let (width, height) = (
+                    label.size.width.into_float(),
+                    label.size.height.into_float(),
+                );
+                context.gfx.draw_shape(&Shape::filled_rect(
+                    Rect::new(
+                        point((center.0 - width / 2., center.1 - height / 2.)),
+                        label.size,
+                    ),
+                    self.colors.background,
+                ));
+                context
+                    .gfx
+                    .draw_measured_text(label.translate_by(point(*center)), TextOrigin::Center);
+            }
+        }
+
+        let outline_stroke =
+            StrokeOptions::px_wide(Px::from(self.stroke)).colored(self.colors.outline);
+        for (node, placed) in self.graph.nodes.iter().zip(&self.nodes).take(visible) {
+            if let Some(path) = outline(node.shape, placed.center, placed.size) {
+                context.gfx.draw_shape(&path.fill(self.colors.node));
+                context.gfx.draw_shape(&path.stroke(outline_stroke));
+            }
+            context.gfx.draw_measured_text(
+                placed.label.translate_by(point(placed.center)),
+                TextOrigin::Center,
+            );
+        }
+    }
+
+    fn layout(
+        &mut self,
+        available_space: Size<ConstraintLimit>,
+        context: &mut LayoutContext<'_, '_, '_, '_>,
+    ) -> Size<UPx> {
+        let text_size = context
+            .get(&TextSize)
+            .into_px(context.gfx.scale())
+            .into_float();
+        let available = available_space.map(ConstraintLimit::max);
+        if self.cached != Some((text_size, available)) {
+            self.arrange(context, text_size);
+            // Diagrams shrink as a whole, text included, to fit the slide.
+            let scale = (available.width.into_float() / self.size.width.into_float())
+                .min(available.height.into_float() / self.size.height.into_float());
+            if scale < 1. {
+                self.arrange(context, text_size * scale);
+            }
+            self.cached = Some((text_size, available));
+        }
+        self.size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, ranks, NodeShape};
+
+    fn edges(source: &str) -> Vec<(usize, usize)> {
+        parse(source)
+            .unwrap()
+            .edges
+            .iter()
+            .map(|edge| (edge.from, edge.to))
+            .collect()
+    }
+
+    #[test]
+    fn edge_chains() {
+        assert_eq!(edges("a -> b -> c; b -> d"), [(0, 1), (1, 2), (1, 3)]);
+        assert_eq!(edges("graph { a -- b -- a }"), [(0, 1), (1, 0)]);
+
+        let graph = parse("a -> b -> c [label=next, style=dashed]").unwrap();
+        assert_eq!(graph.nodes.len(), 3);
+        assert!(graph
+            .edges
+            .iter()
+            .all(|edge| edge.dashed && edge.label.as_deref() == Some("next")));
+    }
+
+    #[test]
+    fn default_attributes() {
+        let graph = parse(
+            "digraph {
+                a;
+                node [shape=box, style=rounded];
+                edge [style=dashed, label=default];
+                b -> c [label=explicit];
+                d [shape=diamond];
+            }",
+        )
+        .unwrap();
+        let shapes = graph
+            .nodes
+            .iter()
+            .map(|node| node.shape)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            shapes,
+            [
+                NodeShape::Ellipse,
+                NodeShape::Rounded,
+                NodeShape::Rounded,
+                NodeShape::Diamond
+            ]
+        );
+        let edge = &graph.edges[0];
+        assert!(edge.dashed);
+        assert_eq!(edge.label.as_deref(), Some("explicit"));
+    }
+
+    #[test]
+    fn errors() {
+        let error = |source: &str| parse(source).expect_err("parsing should fail");
+        assert_eq!(error("a -- b"), "`--` used in a directed graph");
+        assert_eq!(
+            error("graph { a -> b }"),
+            "`->` used in an undirected graph"
+        );
+        assert_eq!(error("digraph { a -> b"), "missing `}`");
+        assert_eq!(error("a [shape=star]"), "unknown shape `star`");
+        assert_eq!(error("rankdir=up"), "unknown rankdir `up`");
+        assert_eq!(error("a -> \"b"), "unterminated string");
+        assert_eq!(error("a [label x]"), "expected `=`, found `x`");
+    }
+
+    #[test]
+    fn cycles_are_reversed() {
+        let graph = parse("a -> b -> c -> a; c -> d").unwrap();
+        let (ranks, reversed) = ranks(&graph);
+        assert_eq!(reversed, [false, false, true, false]);
+        assert_eq!(ranks, [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn self_loops_are_ignored() {
+        let graph = parse("a -> a -> b").unwrap();
+        let (ranks, reversed) = ranks(&graph);
+        assert_eq!(reversed, [true, false]);
+        assert_eq!(ranks, [0, 1]);
+    }
+}
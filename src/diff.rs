@@ -0,0 +1,438 @@
+use std::mem;
+
+use cushy::context::{GraphicsContext, LayoutContext};
+use cushy::figures::units::{Px, UPx};
+use cushy::figures::{IntoUnsigned, Point, Rect, ScreenScale, Size, Zero};
+use cushy::kludgine::shapes::Shape;
+use cushy::kludgine::text::{MeasuredText, Text, TextOrigin};
+use cushy::kludgine::DrawableExt;
+use cushy::styles::components::TextSize;
+use cushy::styles::{Color, FamilyOwned, Weight};
+use cushy::value::{Dynamic, Source};
+use cushy::widget::Widget;
+use cushy::ConstraintLimit;
+use similar::{ChangeTag, TextDiff};
+use syntect::highlighting::Theme;
+use syntect::parsing::SyntaxSet;
+
+use crate::code::{draw_spans, line_width, measure_highlighted, Span};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum DiffLayout {
+    #[default]
+    Unified,
+    SideBySide,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum DiffStage {
+    Before,
+    Changes,
+    After,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum LineKind {
+    Unchanged,
+    Removed,
+    Added,
+}
+
+impl LineKind {
+    fn gutter(self) -> &'static str {
+        match self {
+            LineKind::Unchanged => "  ",
+            LineKind::Removed => "- ",
+            LineKind::Added => "+ ",
+        }
+    }
+
+    fn color(self) -> Option<Color> {
+        match self {
+            LineKind::Unchanged => None,
+            LineKind::Removed => Some(Color::new(0xf8, 0x51, 0x49, 0xff)),
+            LineKind::Added => Some(Color::new(0x3f, 0xb9, 0x50, 0xff)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum DiffLine {
+    Before(usize),
+    After(usize),
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+struct Row {
+    before: Option<usize>,
+    after: Option<usize>,
+    changed: bool,
+}
+
+#[derive(Debug)]
+pub struct DiffView {
+    extension: String,
+    syntax_set: SyntaxSet,
+    theme: Theme,
+    before: String,
+    after: String,
+    layout: DiffLayout,
+    stage: Dynamic<DiffStage>,
+    unified: Vec<(LineKind, DiffLine)>,
+    side_by_side: Vec<Row>,
+    measured_before: Vec<Vec<Span>>,
+    measured_after: Vec<Vec<Span>>,
+    gutters: Vec<(LineKind, MeasuredText<Px>)>,
+    before_width: Px,
+    after_width: Px,
+    gutter_width: Px,
+    line_height: Px,
+    cached_text_size: Px,
+}
+
+impl DiffView {
+    pub fn new(
+        extension: impl Into<String>,
+        syntax_set: SyntaxSet,
+        theme: Theme,
+        before: impl Into<String>,
+        after: impl Into<String>,
+        layout: DiffLayout,
+        stage: Dynamic<DiffStage>,
+    ) -> Self {
+        let before = with_trailing_newline(before.into());
+        let after = with_trailing_newline(after.into());
+        let (unified, side_by_side) = diff_lines(&before, &after);
+
+        Self {
+            extension: extension.into(),
+            syntax_set,
+            theme,
+            before,
+            after,
+            layout,
+            stage,
+            unified,
+            side_by_side,
+            measured_before: Vec::new(),
+            measured_after: Vec::new(),
+            gutters: Vec::new(),
+            before_width: Px::ZERO,
+            after_width: Px::ZERO,
+            gutter_width: Px::ZERO,
+            line_height: Px::ZERO,
+            cached_text_size: Px::ZERO,
+        }
+    }
+
+    fn measure(&mut self, context: &mut LayoutContext<'_, '_, '_, '_>) {
+        let text_size = context.get(&TextSize).into_px(context.gfx.scale());
+        if text_size == self.cached_text_size && !self.gutters.is_empty() {
+            return;
+        }
+
+        self.cached_text_size = text_size;
+        context.gfx.set_font_family(FamilyOwned::Monospace);
+        self.line_height = context.gfx.line_height().into_px(context.gfx.scale());
+        self.measured_before = measure_highlighted(
+            &self.before,
+            &self.extension,
+            &self.syntax_set,
+            &self.theme,
+            context,
+        );
+        self.measured_after = measure_highlighted(
+            &self.after,
+            &self.extension,
+            &self.syntax_set,
+            &self.theme,
+            context,
+        );
+        self.before_width = widest(&self.measured_before);
+        self.after_width = widest(&self.measured_after);
+
+        context.gfx.set_font_weight(Weight::BOLD);
+        self.gutters = [LineKind::Unchanged, LineKind::Removed, LineKind::Added]
+            .into_iter()
+            .map(|kind| {
+                let color = kind.color().unwrap_or(Color::CLEAR_BLACK);
+                (
+                    kind,
+                    context.gfx.measure_text(Text::new(kind.gutter(), color)),
+                )
+            })
+            .collect();
+        self.gutter_width = self
+            .gutters
+            .iter()
+            .map(|(_, gutter)| gutter.size.width)
+            .max()
+            .unwrap_or(Px::ZERO);
+    }
+
+    fn column_gap(&self) -> Px {
+        self.gutter_width
+    }
+
+    fn draw_line(
+        &self,
+        kind: LineKind,
+        spans: &[Span],
+        origin: Point<Px>,
+        width: Px,
+        context: &mut GraphicsContext<'_, '_, '_, '_>,
+    ) {
+        if let Some(color) = kind.color() {
+            context.gfx.draw_shape(&Shape::filled_rect(
+                Rect::new(origin, Size::new(width, self.line_height)),
+                color.with_alpha(0x40),
+            ));
+            if let Some((_, gutter)) = self.gutters.iter().find(|(gutter, _)| *gutter == kind) {
+                context
+                    .gfx
+                    .draw_measured_text(gutter.translate_by(origin), TextOrigin::TopLeft);
+            }
+        }
+
+        draw_spans(
+            spans,
+            Point::new(origin.x + self.gutter_width, origin.y),
+            context,
+        );
+    }
+}
+
+// Lines are compared including their endings, so a missing newline at the
+// end of one side would otherwise mark its last line as changed.
+fn with_trailing_newline(mut text: String) -> String {
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+    text
+}
+
+fn diff_lines(before: &str, after: &str) -> (Vec<(LineKind, DiffLine)>, Vec<Row>) {
+    let mut unified = Vec::new();
+    let mut side_by_side = Vec::new();
+    let mut removed = Vec::new();
+    let mut added = Vec::new();
+
+    for change in TextDiff::from_lines(before, after).iter_all_changes() {
+        match (change.tag(), change.old_index(), change.new_index()) {
+            (ChangeTag::Equal, Some(old), Some(new)) => {
+                pair_changes(&mut removed, &mut added, &mut side_by_side);
+                unified.push((LineKind::Unchanged, DiffLine::After(new)));
+                side_by_side.push(Row {
+                    before: Some(old),
+                    after: Some(new),
+                    changed: false,
+                });
+            }
+            (ChangeTag::Delete, Some(old), _) => {
+                unified.push((LineKind::Removed, DiffLine::Before(old)));
+                removed.push(old);
+            }
+            (ChangeTag::Insert, _, Some(new)) => {
+                unified.push((LineKind::Added, DiffLine::After(new)));
+                added.push(new);
+            }
+            _ => unreachable!("changes always have the index of their side"),
+        }
+    }
+    pair_changes(&mut removed, &mut added, &mut side_by_side);
+    (unified, side_by_side)
+}
+
+fn pair_changes(removed: &mut Vec<usize>, added: &mut Vec<usize>, rows: &mut Vec<Row>) {
+    let removed = mem::take(removed);
+    let added = mem::take(added);
+    for row in 0..removed.len().max(added.len()) {
+        rows.push(Row {
+            before: removed.get(row).copied(),
+            after: added.get(row).copied(),
+            changed: true,
+        });
+    }
+}
+
+fn widest(lines: &[Vec<Span>]) -> Px {
+    lines
+        .iter()
+        .map(|line| line_width(line))
+        .max()
+        .unwrap_or(Px::ZERO)
+}
+
+fn stacked_height(line_height: Px, lines: usize) -> Px {
+    (0..lines).fold(Px::ZERO, |height, _| height + line_height)
+}
+
+impl Widget for DiffView {
+    fn redraw(&mut self, context: &mut GraphicsContext<'_, '_, '_, '_>) {
+        let mut y = Px::ZERO;
+        match (self.stage.get(), self.layout) {
+            // The plain stages reserve the gutter too, so the code doesn't
+            // shift sideways when the changes are revealed.
+            (DiffStage::Before, _) => {
+                for line in &self.measured_before {
+                    draw_spans(line, Point::new(self.gutter_width, y), context);
+                    y += self.line_height;
+                }
+            }
+            (DiffStage::After, _) => {
+                for line in &self.measured_after {
+                    draw_spans(line, Point::new(self.gutter_width, y), context);
+                    y += self.line_height;
+                }
+            }
+            (DiffStage::Changes, DiffLayout::Unified) => {
+                let width = self.gutter_width + self.before_width.max(self.after_width);
+                for (kind, line) in &self.unified {
+                    let spans = match line {
+                        DiffLine::Before(index) => &self.measured_before[*index],
+                        DiffLine::After(index) => &self.measured_after[*index],
+                    };
+                    self.draw_line(*kind, spans, Point::new(Px::ZERO, y), width, context);
+                    y += self.line_height;
+                }
+            }
+            (DiffStage::Changes, DiffLayout::SideBySide) => {
+                let before_width = self.gutter_width + self.before_width;
+                let after_x = before_width + self.column_gap();
+                let after_width = self.gutter_width + self.after_width;
+                for row in &self.side_by_side {
+                    let (before_kind, after_kind) = if row.changed {
+                        (LineKind::Removed, LineKind::Added)
+                    } else {
+                        (LineKind::Unchanged, LineKind::Unchanged)
+                    };
+                    if let Some(before) = row.before {
+                        self.draw_line(
+                            before_kind,
+                            &self.measured_before[before],
+                            Point::new(Px::ZERO, y),
+                            before_width,
+                            context,
+                        );
+                    }
+                    if let Some(after) = row.after {
+                        self.draw_line(
+                            after_kind,
+                            &self.measured_after[after],
+                            Point::new(after_x, y),
+                            after_width,
+                            context,
+                        );
+                    }
+                    y += self.line_height;
+                }
+            }
+        }
+    }
+
+    fn layout(
+        &mut self,
+        _available_space: Size<ConstraintLimit>,
+        context: &mut LayoutContext<'_, '_, '_, '_>,
+    ) -> Size<UPx> {
+        context.invalidate_when_changed(&self.stage);
+        self.measure(context);
+
+        let (width, lines) = match (self.stage.get(), self.layout) {
+            (DiffStage::Before, _) => (
+                self.gutter_width + self.before_width.max(self.after_width),
+                self.measured_before.len(),
+            ),
+            (DiffStage::After, _) => (
+                self.gutter_width + self.before_width.max(self.after_width),
+                self.measured_after.len(),
+            ),
+            (DiffStage::Changes, DiffLayout::Unified) => (
+                self.gutter_width + self.before_width.max(self.after_width),
+                self.unified.len(),
+            ),
+            (DiffStage::Changes, DiffLayout::SideBySide) => (
+                self.gutter_width
+                    + self.before_width
+                    + self.column_gap()
+                    + self.gutter_width
+                    + self.after_width,
+                self.side_by_side.len(),
+            ),
+        };
+        Size::new(width, stacked_height(self.line_height, lines)).into_unsigned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff_lines, with_trailing_newline, DiffLine, LineKind, Row};
+
+    fn row(before: Option<usize>, after: Option<usize>, changed: bool) -> Row {
+        Row {
+            before,
+            after,
+            changed,
+        }
+    }
+
+    #[test]
+    fn unchanged() {
+        let (unified, side_by_side) = diff_lines("a\nb\n", "a\nb\n");
+        assert_eq!(
+            unified,
+            [
+                (LineKind::Unchanged, DiffLine::After(0)),
+                (LineKind::Unchanged, DiffLine::After(1))
+            ]
+        );
+        assert_eq!(
+            side_by_side,
+            [row(Some(0), Some(0), false), row(Some(1), Some(1), false)]
+        );
+    }
+
+    #[test]
+    fn replaced_line() {
+        let (unified, side_by_side) = diff_lines("a\nb\nc\n", "a\nx\nc\n");
+        assert_eq!(
+            unified,
+            [
+                (LineKind::Unchanged, DiffLine::After(0)),
+                (LineKind::Removed, DiffLine::Before(1)),
+                (LineKind::Added, DiffLine::After(1)),
+                (LineKind::Unchanged, DiffLine::After(2))
+            ]
+        );
+        assert_eq!(
+            side_by_side,
+            [
+                row(Some(0), Some(0), false),
+                row(Some(1), Some(1), true),
+                row(Some(2), Some(2), false)
+            ]
+        );
+    }
+
+    #[test]
+    fn removed_lines_pair_with_nothing() {
+        let (_, side_by_side) = diff_lines("a\nb\nc\n", "a\n");
+        assert_eq!(
+            side_by_side,
+            [
+                row(Some(0), Some(0), false),
+                row(Some(1), None, true),
+                row(Some(2), None, true)
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_trailing_newline() {
+        let before = with_trailing_newline(String::from("a\nb"));
+        let after = with_trailing_newline(String::from("a\nb\n"));
+        let (unified, _) = diff_lines(&before, &after);
+        assert!(unified.iter().all(|(kind, _)| *kind == LineKind::Unchanged));
+        assert_eq!(with_trailing_newline(String::new()), "");
+    }
+}
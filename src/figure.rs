@@ -0,0 +1,385 @@
+use cushy::context::{EventContext, GraphicsContext, LayoutContext};
+use cushy::figures::units::{Px, UPx};
+use cushy::figures::{FloatConversion, IntoSigned, Rect, Size};
+use cushy::kludgine::shapes::{Path, StrokeOptions};
+use cushy::kludgine::text::{MeasuredText, Text, TextOrigin};
+use cushy::kludgine::DrawableExt;
+use cushy::styles::Color;
+use cushy::value::{Dynamic, Source};
+use cushy::widget::{MakeWidget, Widget, WidgetRef};
+use cushy::ConstraintLimit;
+
+use crate::morph::CodeMorphs;
+use crate::shapes::{arrow_head, ellipse, point, polygon, polyline, rounded_rect};
+use crate::{slide_unit, DESIGN_UNIT};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum FigureKind {
+    Rectangle,
+    RoundedBox(f32),
+    Circle,
+    Ellipse,
+    Line,
+    Arrow,
+    Callout,
+}
+
+// A figure with its colors resolved. Positions are fractions of the reference
+// bounds: the annotated element, or the figure's own bounds when it stands
+// alone. Sizes that aren't positions are in design pixels.
+#[derive(Debug, Clone)]
+pub(crate) struct Drawing {
+    pub kind: FigureKind,
+    pub start: (f32, f32),
+    pub end: (f32, f32),
+    pub target: Option<(f32, f32)>,
+    pub fill: Option<Color>,
+    pub stroke: Option<Color>,
+    pub stroke_width: f32,
+    pub text: Option<(String, Color)>,
+    pub line: Option<CodeLine>,
+}
+
+// A line of the code element with a matching id, which the figure surrounds
+// or points at once the code has been drawn.
+#[derive(Debug, Clone)]
+pub(crate) struct CodeLine {
+    pub id: String,
+    pub line: usize,
+    pub morphs: Dynamic<CodeMorphs>,
+}
+
+impl Drawing {
+    fn arrow_size(&self, scale: f32) -> f32 {
+        self.stroke_width * scale * 4.
+    }
+}
+
+#[derive(Debug)]
+pub struct FigureView {
+    child: Option<WidgetRef>,
+    drawings: Vec<Drawing>,
+    texts: Vec<Option<MeasuredText<Px>>>,
+    origin: (f32, f32),
+    reference: (f32, f32),
+    scale: f32,
+}
+
+impl FigureView {
+    pub fn new(drawings: Vec<Drawing>) -> Self {
+        Self {
+            child: None,
+            drawings,
+            texts: Vec::new(),
+            origin: (0., 0.),
+            reference: (0., 0.),
+            scale: 1.,
+        }
+    }
+
+    pub fn annotating(child: impl MakeWidget, drawings: Vec<Drawing>) -> Self {
+        Self {
+            child: Some(WidgetRef::new(child)),
+            ..Self::new(drawings)
+        }
+    }
+
+    fn locate(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        (
+            self.origin.0 + x * self.reference.0,
+            self.origin.1 + y * self.reference.1,
+        )
+    }
+
+    fn padding(&self, text: &MeasuredText<Px>) -> f32 {
+        text.line_height.into_float() * 0.4
+    }
+
+    // The callout box in pixels relative to the reference origin.
+    fn callout_box(&self, drawing: &Drawing, text: Option<&MeasuredText<Px>>) -> [(f32, f32); 2] {
+        let (left, top) = (
+            drawing.start.0 * self.reference.0,
+            drawing.start.1 * self.reference.1,
+        );
+        let (width, height) = text.map_or((0., 0.), |text| {
+            let padding = self.padding(text);
+            (
+                text.size.width.into_float() + padding * 2.,
+                text.size.height.into_float() + padding * 2.,
+            )
+        });
+        [(left, top), (left + width, top + height)]
+    }
+
+    // The pixel extents of every figure relative to the reference origin,
+    // including room for strokes and arrow heads.
+    fn extents(&self) -> [(f32, f32); 2] {
+        let mut min = (0_f32, 0_f32);
+        let mut max = self.reference;
+        let mut include = |(x, y): (f32, f32), margin: f32| {
+            min = (min.0.min(x - margin), min.1.min(y - margin));
+            max = (max.0.max(x + margin), max.1.max(y + margin));
+        };
+        for (drawing, text) in self.drawings.iter().zip(&self.texts) {
+            let margin = match drawing.kind {
+                FigureKind::Arrow => drawing.arrow_size(self.scale),
+                _ => drawing.stroke_width * self.scale / 2.,
+            };
+            let relative = |(x, y): (f32, f32)| (x * self.reference.0, y * self.reference.1);
+            if drawing.kind == FigureKind::Callout {
+                let [top_left, bottom_right] = self.callout_box(drawing, text.as_ref());
+                include(top_left, margin);
+                include(bottom_right, margin);
+            } else {
+                include(relative(drawing.start), margin);
+                include(relative(drawing.end), margin);
+            }
+            if let Some(target) = drawing.target {
+                include(relative(target), margin);
+            }
+        }
+        [min, max]
+    }
+
+    // Moves a figure that targets a line of code onto where that line was
+    // drawn, or returns None if it doesn't target a line.
+    fn anchored(
+        &self,
+        context: &mut GraphicsContext<'_, '_, '_, '_>,
+        drawing: &Drawing,
+    ) -> Option<Drawing> {
+        let target = drawing.line.as_ref()?;
+        context.redraw_when_changed(&target.morphs);
+        let line = target
+            .morphs
+            .map_ref(|morphs| morphs.line(&target.id, target.line))?;
+        let offset = context.gfx.region().origin;
+        let margin = line.size.height.into_float() * 0.2;
+        let fraction = |x: Px, y: Px, margin: f32| {
+            (
+                ((x - offset.x).into_float() + margin - self.origin.0) / self.reference.0.max(1.),
+                ((y - offset.y).into_float() + margin - self.origin.1) / self.reference.1.max(1.),
+            )
+        };
+        let top_left = fraction(line.origin.x, line.origin.y, -margin);
+        let bottom_right = fraction(
+            line.origin.x + line.size.width,
+            line.origin.y + line.size.height,
+            margin,
+        );
+        let middle_left = (top_left.0, (top_left.1 + bottom_right.1) / 2.);
+
+        let mut anchored = drawing.clone();
+        match drawing.kind {
+            FigureKind::Rectangle
+            | FigureKind::RoundedBox(_)
+            | FigureKind::Circle
+            | FigureKind::Ellipse => {
+                anchored.start = top_left;
+                anchored.end = bottom_right;
+            }
+            FigureKind::Line | FigureKind::Arrow => anchored.end = middle_left,
+            FigureKind::Callout => anchored.target = Some(middle_left),
+        }
+        Some(anchored)
+    }
+
+    fn draw(
+        &self,
+        context: &mut GraphicsContext<'_, '_, '_, '_>,
+        drawing: &Drawing,
+        text: Option<&MeasuredText<Px>>,
+    ) {
+        let stroke_width = drawing.stroke_width * self.scale;
+        let paint = |context: &mut GraphicsContext<'_, '_, '_, '_>, path: &Path<Px, false>| {
+            if let Some(fill) = drawing.fill {
+                context.gfx.draw_shape(&path.fill(fill));
+            }
+            if let Some(stroke) = drawing.stroke.filter(|_| stroke_width > 0.) {
+                context.gfx.draw_shape(
+                    &path.stroke(StrokeOptions::px_wide(Px::from(stroke_width)).colored(stroke)),
+                );
+            }
+        };
+
+        let (start, end) = (self.locate(drawing.start), self.locate(drawing.end));
+        let (left, top) = (start.0.min(end.0), start.1.min(end.1));
+        let (right, bottom) = (start.0.max(end.0), start.1.max(end.1));
+        match drawing.kind {
+            FigureKind::Rectangle => {
+                paint(context, &rounded_rect((left, top), (right, bottom), 0.))
+            }
+            FigureKind::RoundedBox(radius) => paint(
+                context,
+                &rounded_rect((left, top), (right, bottom), radius * self.scale),
+            ),
+            FigureKind::Circle => {
+                let radius = (right - left).min(bottom - top) / 2.;
+                paint(
+                    context,
+                    &ellipse(((left + right) / 2., (top + bottom) / 2.), (radius, radius)),
+                );
+            }
+            FigureKind::Ellipse => paint(
+                context,
+                &ellipse(
+                    ((left + right) / 2., (top + bottom) / 2.),
+                    ((right - left) / 2., (bottom - top) / 2.),
+                ),
+            ),
+            FigureKind::Line | FigureKind::Arrow => {
+                let Some(color) = drawing.stroke else {
+                    return;
+                };
+                let mut tip = end;
+                if drawing.kind == FigureKind::Arrow {
+                    // End the line inside the head so the stroke's square cap
+                    // doesn't poke out of the point.
+                    let size = drawing.arrow_size(self.scale);
+                    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+                    let length = (dx * dx + dy * dy).sqrt().max(f32::EPSILON);
+                    tip = (
+                        end.0 - dx / length * size / 2.,
+                        end.1 - dy / length * size / 2.,
+                    );
+                    context
+                        .gfx
+                        .draw_shape(&arrow_head(end, start, size).fill(color));
+                }
+                if let Some(path) = polyline(&[start, tip]) {
+                    context.gfx.draw_shape(
+                        &path.stroke(StrokeOptions::px_wide(Px::from(stroke_width)).colored(color)),
+                    );
+                }
+            }
+            FigureKind::Callout => {
+                let [(left, top), (right, bottom)] = self.callout_box(drawing, text);
+                let (left, top) = (left + self.origin.0, top + self.origin.1);
+                let (right, bottom) = (right + self.origin.0, bottom + self.origin.1);
+                if let Some(target) = drawing.target {
+                    // The tail grows out of the middle of the bubble, which
+                    // then covers its base.
+                    let center = ((left + right) / 2., (top + bottom) / 2.);
+                    let target = self.locate(target);
+                    let (dx, dy) = (target.0 - center.0, target.1 - center.1);
+                    let length = (dx * dx + dy * dy).sqrt().max(f32::EPSILON);
+                    let half = (right - left).min(bottom - top) / 4.;
+                    let (nx, ny) = (-dy / length * half, dx / length * half);
+                    if let Some(tail) = polygon(&[
+                        (center.0 + nx, center.1 + ny),
+                        target,
+                        (center.0 - nx, center.1 - ny),
+                    ]) {
+                        paint(context, &tail);
+                    }
+                }
+                let radius = text.map_or(0., |text| self.padding(text));
+                paint(context, &rounded_rect((left, top), (right, bottom), radius));
+                if let Some(text) = text {
+                    let padding = self.padding(text);
+                    context.gfx.draw_measured_text(
+                        text.translate_by(point((left + padding, top + padding))),
+                        TextOrigin::TopLeft,
+                    );
+                }
+            }
+        }
+    }
+}
+
+impl Widget for FigureView {
+    fn redraw(&mut self, context: &mut GraphicsContext<'_, '_, '_, '_>) {
+        if let Some(child) = &mut self.child {
+            let child = child.mounted(&mut context.as_event_context());
+            context.for_other(&child).redraw();
+        }
+        for (drawing, text) in self.drawings.iter().zip(&self.texts) {
+            match self.anchored(context, drawing) {
+                Some(anchored) => self.draw(context, &anchored, text.as_ref()),
+                None => self.draw(context, drawing, text.as_ref()),
+            }
+        }
+    }
+
+    fn layout(
+        &mut self,
+        available_space: Size<ConstraintLimit>,
+        context: &mut LayoutContext<'_, '_, '_, '_>,
+    ) -> Size<UPx> {
+        self.scale = slide_unit(context) / DESIGN_UNIT;
+        context.apply_current_font_settings();
+        self.texts = self
+            .drawings
+            .iter()
+            .map(|drawing| {
+                drawing
+                    .text
+                    .as_ref()
+                    .map(|(text, color)| context.gfx.measure_text(Text::new(text, *color)))
+            })
+            .collect();
+
+        let available = available_space.map(ConstraintLimit::max);
+        let available = (available.width.into_float(), available.height.into_float());
+        let Some(child) = &mut self.child else {
+            // A figure on its own fills its space, inset so that strokes
+            // along the edges aren't clipped.
+            let inset = self
+                .drawings
+                .iter()
+                .map(|drawing| drawing.stroke_width * self.scale / 2.)
+                .fold(0., f32::max);
+            self.origin = (inset, inset);
+            self.reference = (available.0 - inset * 2., available.1 - inset * 2.);
+            return available_space.map(ConstraintLimit::max);
+        };
+
+        // Figures may reach outside of the annotated element, so the element
+        // gives up enough space for them to be drawn without being clipped.
+        let child = child.mounted(&mut context.as_event_context());
+        let mut shrink = (1., 1.);
+        let mut child_size = Size::default();
+        for _ in 0..2 {
+            let limits = Size::new(
+                scale_limit(available_space.width, shrink.0),
+                scale_limit(available_space.height, shrink.1),
+            );
+            child_size = context.for_other(&child).layout(limits).into_signed();
+            self.reference = (
+                child_size.width.into_float(),
+                child_size.height.into_float(),
+            );
+            let [min, max] = self.extents();
+            let (width, height) = (max.0 - min.0, max.1 - min.1);
+            self.origin = (-min.0, -min.1);
+            if width <= available.0 && height <= available.1 {
+                break;
+            }
+            shrink = (
+                shrink.0 * (available.0 / width).min(1.),
+                shrink.1 * (available.1 / height).min(1.),
+            );
+        }
+
+        context.set_child_layout(&child, Rect::new(point(self.origin), child_size));
+        let [min, max] = self.extents();
+        Size::new(
+            UPx::from(max.0 - min.0).min(available_space.width.max()),
+            UPx::from(max.1 - min.1).min(available_space.height.max()),
+        )
+    }
+
+    fn unmounted(&mut self, context: &mut EventContext<'_>) {
+        if let Some(child) = &mut self.child {
+            child.unmount_in(context);
+        }
+    }
+}
+
+fn scale_limit(limit: ConstraintLimit, scale: f32) -> ConstraintLimit {
+    let scaled = UPx::from(limit.max().into_float() * scale);
+    match limit {
+        ConstraintLimit::Fill(_) => ConstraintLimit::Fill(scaled),
+        ConstraintLimit::SizeToFit(_) => ConstraintLimit::SizeToFit(scaled),
+    }
+}
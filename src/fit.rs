@@ -0,0 +1,100 @@
+use cushy::context::LayoutContext;
+use cushy::figures::units::{Px, UPx};
+use cushy::figures::{FloatConversion, Round, ScreenScale, Size};
+use cushy::styles::components::{BaseLineHeight, BaseTextSize};
+use cushy::styles::Dimension;
+use cushy::value::{Destination, Dynamic};
+use cushy::widget::{MakeWidget, WidgetRef, WrappedLayout, WrapperWidget};
+use cushy::ConstraintLimit;
+
+#[derive(Debug)]
+pub struct FitText {
+    child: WidgetRef,
+    text_size: Dynamic<Dimension>,
+    line_height: Dynamic<Dimension>,
+    fitted: Option<(Size<UPx>, Px, Px, f32)>,
+}
+
+impl FitText {
+    pub fn new(child: impl MakeWidget) -> Self {
+        let text_size = Dynamic::new(Dimension::Px(Px::new(1)));
+        let line_height = Dynamic::new(Dimension::Px(Px::new(1)));
+        Self {
+            child: WidgetRef::new(
+                child
+                    .with_dynamic(&BaseTextSize, text_size.clone())
+                    .with_dynamic(&BaseLineHeight, line_height.clone()),
+            ),
+            text_size,
+            line_height,
+            fitted: None,
+        }
+    }
+
+    fn scale_to(&self, scale: f32, base_text_size: Px, base_line_height: Px) {
+        self.text_size.set(Dimension::Px(
+            Px::from(base_text_size.into_float() * scale)
+                .floor()
+                .max(Px::new(1)),
+        ));
+        self.line_height.set(Dimension::Px(
+            Px::from(base_line_height.into_float() * scale)
+                .floor()
+                .max(Px::new(1)),
+        ));
+    }
+}
+
+impl WrapperWidget for FitText {
+    fn child_mut(&mut self) -> &mut WidgetRef {
+        &mut self.child
+    }
+
+    fn layout_child(
+        &mut self,
+        available_space: Size<ConstraintLimit>,
+        context: &mut LayoutContext<'_, '_, '_, '_>,
+    ) -> WrappedLayout {
+        let available = available_space.map(ConstraintLimit::max);
+        let constraints = available.map(ConstraintLimit::SizeToFit);
+        let base_text_size = context.get(&BaseTextSize).into_px(context.gfx.scale());
+        let base_line_height = context.get(&BaseLineHeight).into_px(context.gfx.scale());
+        let child = self.child.mounted(&mut context.as_event_context());
+
+        // Searching changes the child's styles, which invalidates it. Only
+        // search again when the inputs change so that the layout settles.
+        let scale = match self.fitted {
+            Some((fitted_for, text_size, line_height, scale))
+                if fitted_for == available
+                    && text_size == base_text_size
+                    && line_height == base_line_height =>
+            {
+                scale
+            }
+            _ => {
+                // A single line of text can't be taller than the available
+                // space, which bounds the search. Wrapping text only gets
+                // taller as it grows, so a binary search finds the largest
+                // scale that still fits.
+                let mut fits = 0.;
+                let mut too_large =
+                    (available.height.into_float() / base_line_height.into_float().max(1.)).max(1.);
+                for _ in 0..12 {
+                    let scale = (fits + too_large) / 2.;
+                    self.scale_to(scale, base_text_size, base_line_height);
+                    let size = context.for_other(&child).layout(constraints);
+                    if size.width <= available.width && size.height <= available.height {
+                        fits = scale;
+                    } else {
+                        too_large = scale;
+                    }
+                }
+                self.fitted = Some((available, base_text_size, base_line_height, fits));
+                fits
+            }
+        };
+
+        self.scale_to(scale, base_text_size, base_line_height);
+        context.for_other(&child).layout(constraints).into()
+    }
+}
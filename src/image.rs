@@ -0,0 +1,197 @@
+use std::path::{Path, PathBuf};
+
+use cushy::context::LayoutContext;
+use cushy::figures::units::{Px, UPx};
+use cushy::figures::{FloatConversion, IntoUnsigned, Size, Zero};
+use cushy::kludgine::image::{self, DynamicImage, RgbaImage};
+use cushy::kludgine::{wgpu, LazyTexture};
+use cushy::widget::{MakeWidget, WidgetRef, WrapperWidget};
+use cushy::ConstraintLimit;
+
+use crate::slide_unit;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum ImageFit {
+    #[default]
+    Fit,
+    Fill,
+    Stretch,
+    Original,
+}
+
+#[derive(Debug, Clone)]
+pub enum ImageSource {
+    Texture(LazyTexture),
+    File(ImageFile),
+}
+
+// Cropping and rounding corners happen while decoding, so they are only
+// offered for images loaded from files.
+#[derive(Debug, Clone)]
+pub struct ImageFile {
+    path: PathBuf,
+    crop: Option<Crop>,
+    corner_radius: u32,
+}
+
+impl ImageFile {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            crop: None,
+            corner_radius: 0,
+        }
+    }
+
+    pub fn crop(mut self, x: u32, y: u32, width: u32, height: u32) -> Self {
+        self.crop = Some(Crop {
+            x,
+            y,
+            width,
+            height,
+        });
+        self
+    }
+
+    pub fn corner_radius(mut self, radius: u32) -> Self {
+        self.corner_radius = radius;
+        self
+    }
+
+    pub(crate) fn load(&self) -> Result<LazyTexture, String> {
+        load(&self.path, self.crop, self.corner_radius)
+    }
+}
+
+impl From<ImageFile> for ImageSource {
+    fn from(file: ImageFile) -> Self {
+        Self::File(file)
+    }
+}
+
+impl From<LazyTexture> for ImageSource {
+    fn from(texture: LazyTexture) -> Self {
+        Self::Texture(texture)
+    }
+}
+
+impl From<PathBuf> for ImageSource {
+    fn from(path: PathBuf) -> Self {
+        Self::File(ImageFile::new(path))
+    }
+}
+
+impl<'a> From<&'a Path> for ImageSource {
+    fn from(path: &'a Path) -> Self {
+        Self::File(ImageFile::new(path))
+    }
+}
+
+impl<'a> From<&'a str> for ImageSource {
+    fn from(path: &'a str) -> Self {
+        Self::File(ImageFile::new(path))
+    }
+}
+
+impl From<String> for ImageSource {
+    fn from(path: String) -> Self {
+        Self::File(ImageFile::new(path))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) struct Crop {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+pub(crate) fn load(
+    path: &Path,
+    crop: Option<Crop>,
+    corner_radius: u32,
+) -> Result<LazyTexture, String> {
+    let mut image = image::open(path).map_err(|err| format!("{}: {err}", path.display()))?;
+    if let Some(crop) = crop {
+        image = image.crop_imm(crop.x, crop.y, crop.width, crop.height);
+    }
+    if corner_radius > 0 {
+        let mut rgba = image.into_rgba8();
+        round_corners(&mut rgba, corner_radius);
+        image = DynamicImage::ImageRgba8(rgba);
+    }
+    Ok(LazyTexture::from_image(image, wgpu::FilterMode::Linear))
+}
+
+fn round_corners(image: &mut RgbaImage, radius: u32) {
+    let (width, height) = image.dimensions();
+    let radius = radius.min(width / 2).min(height / 2) as f32;
+    if radius < 1. {
+        return;
+    }
+
+    let (width, height) = (width as f32, height as f32);
+    for (x, y, pixel) in image.enumerate_pixels_mut() {
+        let (x, y) = (x as f32 + 0.5, y as f32 + 0.5);
+        let dx = x - x.clamp(radius, width - radius);
+        let dy = y - y.clamp(radius, height - radius);
+        let coverage = (radius - (dx * dx + dy * dy).sqrt() + 0.5).clamp(0., 1.);
+        if coverage < 1. {
+            pixel[3] = (f32::from(pixel[3]) * coverage).round() as u8;
+        }
+    }
+}
+
+pub(crate) fn aspect_fit(natural: Size<UPx>, available_space: Size<ConstraintLimit>) -> Size<UPx> {
+    if natural.width == UPx::ZERO || natural.height == UPx::ZERO {
+        return Size::ZERO;
+    }
+    let available = available_space.map(ConstraintLimit::max);
+    let scale = (available.width.into_float() / natural.width.into_float())
+        .min(available.height.into_float() / natural.height.into_float());
+    Size::new(
+        UPx::from((natural.width.into_float() * scale).round()),
+        UPx::from((natural.height.into_float() * scale).round()),
+    )
+}
+
+#[derive(Debug)]
+pub struct SlideSized {
+    child: WidgetRef,
+    width: Option<f32>,
+    height: Option<f32>,
+}
+
+impl SlideSized {
+    pub fn new(child: impl MakeWidget, width: Option<f32>, height: Option<f32>) -> Self {
+        Self {
+            child: WidgetRef::new(child),
+            width,
+            height,
+        }
+    }
+}
+
+impl WrapperWidget for SlideSized {
+    fn child_mut(&mut self) -> &mut WidgetRef {
+        &mut self.child
+    }
+
+    fn adjust_child_constraints(
+        &mut self,
+        available_space: Size<ConstraintLimit>,
+        context: &mut LayoutContext<'_, '_, '_, '_>,
+    ) -> Size<ConstraintLimit> {
+        let unit = slide_unit(context);
+        let sized = |units: Option<f32>, limit: ConstraintLimit| {
+            units.map_or(limit, |units| {
+                ConstraintLimit::Fill(Px::from(units * unit).into_unsigned().min(limit.max()))
+            })
+        };
+        Size::new(
+            sized(self.width, available_space.width),
+            sized(self.height, available_space.height),
+        )
+    }
+}
@@ -1,33 +1,60 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Display};
+use std::ops::RangeBounds;
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock};
 
 use cushy::animation::ZeroToOne;
 use cushy::context::{EventContext, LayoutContext};
 use cushy::figures::units::Px;
-use cushy::figures::{FloatConversion, Round, Size};
+use cushy::figures::{FloatConversion, Round, ScreenScale, Size};
 use cushy::kludgine::app::winit::keyboard::{Key, NamedKey};
 use cushy::kludgine::LazyTexture;
 use cushy::styles::components::{
-    BaseLineHeight, BaseTextSize, IntrinsicPadding, PrimaryColor, TextColor,
+    BaseLineHeight, BaseTextSize, FontWeight, IntrinsicPadding, PrimaryColor, TextColor,
 };
-use cushy::styles::{Color, Dimension, Styles, Theme, ThemePair};
+use cushy::styles::{Color, Dimension, Styles, Theme, ThemePair, Weight};
 use cushy::value::{Destination, Dynamic, Source, Switchable};
 use cushy::widget::{
     EventHandling, MakeWidget, WidgetInstance, WidgetList, WidgetRef, WrappedLayout, WrapperWidget,
     HANDLED, IGNORED,
 };
-use cushy::widgets::grid::Orientation;
-use cushy::widgets::{Delimiter, Image, Label, Stack};
+use cushy::widgets::grid::{GridDimension, GridWidgets, Orientation};
+use cushy::widgets::{Delimiter, Grid, Image, Label, Space, Stack};
 use cushy::window::{DeviceId, KeyEvent};
 use cushy::{ConstraintLimit, Run};
 use syntect::highlighting::ThemeSet;
 use syntect::parsing::SyntaxSet;
 
+const DESIGN_UNIT: f32 = 120.;
+const DESIGN_TEXT_SIZE: f32 = 28.;
+const DESIGN_LINE_HEIGHT: f32 = 34.;
+const DESIGN_PADDING: f32 = 10.;
+
+fn slide_unit(context: &mut LayoutContext<'_, '_, '_, '_>) -> f32 {
+    let base_text_size = context.get(&BaseTextSize).into_px(context.gfx.scale());
+    base_text_size.into_float() * DESIGN_UNIT / DESIGN_TEXT_SIZE
+}
+
 pub struct ShowSettings {}
 
 #[derive(Default)]
 pub struct Show {
     first_slide: String,
     slides: HashMap<String, Slide>,
+    background: Option<Background>,
+    templates: HashMap<String, Element>,
+    deck: DeckInfo,
+    header: Option<Element>,
+    footer: Option<Element>,
+}
+
+#[derive(Default)]
+struct DeckInfo {
+    title: String,
+    author: String,
+    date: String,
+    cache_dir: Option<PathBuf>,
 }
 
 impl Show {
@@ -40,39 +67,186 @@ impl Show {
         self
     }
 
+    pub fn with_background(mut self, background: impl Into<Background>) -> Self {
+        self.background = Some(background.into());
+        self
+    }
+
+    // Templates are layouts containing placeholders that are filled in by each
+    // slide using the template. There is no deck file format yet, so templates
+    // can only be registered from Rust until the slide DSL exists.
+    pub fn with_template(mut self, name: impl Into<String>, layout: impl Into<Element>) -> Self {
+        self.templates.insert(name.into(), layout.into());
+        self
+    }
+
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.deck.title = title.into();
+        self
+    }
+
+    pub fn with_author(mut self, author: impl Into<String>) -> Self {
+        self.deck.author = author.into();
+        self
+    }
+
+    pub fn with_date(mut self, date: impl Into<String>) -> Self {
+        self.deck.date = date.into();
+        self
+    }
+
+    // Where runnable code caches its output for `replay_cached`, unless the
+    // element sets its own directory. Relative paths are resolved against
+    // the working directory, so decks launched from elsewhere should use an
+    // absolute path, such as one built with `env!("CARGO_MANIFEST_DIR")`.
+    pub fn with_cache_dir(mut self, cache: impl Into<PathBuf>) -> Self {
+        self.deck.cache_dir = Some(cache.into());
+        self
+    }
+
+    // The header and footer are shown above and below every slide that
+    // doesn't opt out of them.
+    pub fn with_header(mut self, header: impl Into<Element>) -> Self {
+        self.header = Some(header.into());
+        self
+    }
+
+    pub fn with_footer(mut self, footer: impl Into<Element>) -> Self {
+        self.footer = Some(footer.into());
+        self
+    }
+
     pub fn push(&mut self, mut slide: Slide) {
         slide.meta.index = self.slides.len();
         self.slides.insert(slide.meta.path.clone(), slide);
     }
 
+    // Slides are numbered along the path from the first slide through each
+    // slide's next slide. Slides that are only reachable some other way, and
+    // slides that opt out, aren't numbered.
+    fn slide_numbers(&self) -> HashMap<String, usize> {
+        let mut numbers = HashMap::new();
+        let mut visited = HashSet::new();
+        let mut path = self.first_slide.as_str();
+        while let Some(slide) = self.slides.get(path) {
+            if !visited.insert(path) {
+                break;
+            }
+            if slide.meta.numbered {
+                numbers.insert(path.to_string(), numbers.len());
+            }
+            path = &slide.meta.next_slide;
+        }
+        numbers
+    }
+
     pub fn present(self) {
         let theme = ThemePair::default().dark;
         self.present_themed(theme)
     }
 
+    pub fn validate(&self) -> Vec<ValidationWarning> {
+        let mut slides = self.slides.values().collect::<Vec<_>>();
+        slides.sort_by_key(|slide| slide.meta.index);
+
+        let mut warnings = Vec::new();
+        for slide in slides {
+            let mut context = ValidationContext {
+                slide: &slide.meta.path,
+                available_space: Size::new(Px::from(16. * DESIGN_UNIT), Px::from(9. * DESIGN_UNIT)),
+                text_size: Px::from(DESIGN_TEXT_SIZE),
+                line_height: Px::from(DESIGN_LINE_HEIGHT),
+                placeholders: &slide.placeholders,
+                filled_placeholders: HashSet::new(),
+                warnings: &mut warnings,
+            };
+            match &slide.layout {
+                SlideLayout::Contents(contents) => {
+                    contents.validate(&mut context);
+                    if !slide.placeholders.is_empty() {
+                        context.warn("placeholders are only used by slides with a template");
+                    }
+                }
+                SlideLayout::Template(name) => match self.templates.get(name) {
+                    Some(template) => {
+                        template.validate(&mut context);
+                        let mut unused = slide
+                            .placeholders
+                            .keys()
+                            .filter(|placeholder| {
+                                !context.filled_placeholders.contains(placeholder.as_str())
+                            })
+                            .collect::<Vec<_>>();
+                        unused.sort();
+                        for placeholder in unused {
+                            context.warn(format!(
+                                "template `{name}` has no placeholder `{placeholder}`"
+                            ));
+                        }
+                    }
+                    None => context.warn(format!("unknown template: {name}")),
+                },
+            }
+            if let Some(background) = slide.meta.background.as_ref().or(self.background.as_ref()) {
+                background.validate(&mut context);
+            }
+        }
+        for (name, element) in [("header", &self.header), ("footer", &self.footer)] {
+            if let Some(element) = element {
+                element.validate(&mut ValidationContext {
+                    slide: name,
+                    available_space: Size::new(
+                        Px::from(16. * DESIGN_UNIT),
+                        Px::from(9. * DESIGN_UNIT),
+                    ),
+                    text_size: Px::from(DESIGN_TEXT_SIZE),
+                    line_height: Px::from(DESIGN_LINE_HEIGHT),
+                    placeholders: &HashMap::new(),
+                    filled_placeholders: HashSet::new(),
+                    warnings: &mut warnings,
+                });
+            }
+        }
+        warnings
+    }
+
     pub fn present_themed(self, theme: Theme) {
-        let current_slide = Dynamic::new(self.first_slide);
+        let current_slide = Dynamic::new(self.first_slide.clone());
         let next_slide = Dynamic::default();
         let slide_history = Dynamic::default();
+        let step = Dynamic::new(0);
+        let step_count = Dynamic::new(0);
+        let code_morphs = Dynamic::default();
         let default_text_color = theme.surface.on_color;
+        let slide_numbers = self.slide_numbers();
         SlideSurface {
             next_slide: next_slide.clone(),
             current_slide: current_slide.clone(),
             slide_history: slide_history.clone(),
+            step: step.clone(),
+            step_count: step_count.clone(),
             contents: WidgetRef::new(current_slide.switcher(move |slide, _dynamic| {
                 self.slides
                     .get(slide)
                     .map(|slide| {
-                        slide
-                            .present(&Context {
+                        slide.present(
+                            &Context {
                                 next_slide: &next_slide,
+                                step: &step,
+                                step_count: &step_count,
+                                code_morphs: &code_morphs,
+                                attrs: &HashMap::new(),
+                                placeholders: &HashMap::new(),
                                 align: HAlign::Center,
+                                list_depth: 0,
                                 theme: &theme,
                                 color: default_text_color.into(),
-                                slide_index: slide.meta.index,
-                                slide_count: self.slides.len(),
-                            })
-                            .make_widget()
+                                deck: &self.deck,
+                                slide_index: slide_numbers.get(&slide.meta.path).copied(),
+                                slide_count: slide_numbers.len(),
+                            },
+                            &self,
+                        )
                     })
                     .unwrap_or_else(|| format!("unknown slide: {slide}").centered().make_widget())
             })),
@@ -86,20 +260,77 @@ impl Show {
 
 pub struct Slide {
     meta: SlideMeta,
-    contents: Element,
+    layout: SlideLayout,
+    placeholders: HashMap<String, Element>,
+}
+
+enum SlideLayout {
+    Contents(Element),
+    Template(String),
 }
 
 impl Slide {
     pub fn new(meta: impl Into<SlideMeta>, elements: impl Into<Element>) -> Self {
         Self {
             meta: meta.into(),
-            contents: elements.into(),
+            layout: SlideLayout::Contents(elements.into()),
+            placeholders: HashMap::new(),
+        }
+    }
+
+    pub fn from_template(meta: impl Into<SlideMeta>, template: impl Into<String>) -> Self {
+        Self {
+            meta: meta.into(),
+            layout: SlideLayout::Template(template.into()),
+            placeholders: HashMap::new(),
         }
     }
 
-    fn present(&self, context: &Context) -> impl MakeWidget {
+    pub fn fill(mut self, placeholder: impl Into<String>, element: impl Into<Element>) -> Self {
+        self.placeholders.insert(placeholder.into(), element.into());
+        self
+    }
+
+    fn present(&self, context: &Context, show: &Show) -> WidgetInstance {
         context.next_slide.set(self.meta.next_slide.clone());
-        self.contents.make_widget(context)
+        context.step.set(0);
+        context.step_count.set(0);
+        context.code_morphs.map_mut(|mut morphs| morphs.advance());
+        let mut context = context.clone();
+        context.placeholders = &self.placeholders;
+        let contents = match &self.layout {
+            SlideLayout::Contents(contents) => contents.make_widget(&context),
+            SlideLayout::Template(name) => match show.templates.get(name) {
+                Some(template) => template.make_widget(&context),
+                None => format!("unknown template: {name}")
+                    .with(&TextColor, context.theme.error.color)
+                    .centered()
+                    .make_widget(),
+            },
+        };
+        let header = show.header.as_ref().filter(|_| self.meta.header);
+        let footer = show.footer.as_ref().filter(|_| self.meta.footer);
+        let contents = if header.is_some() || footer.is_some() {
+            let mut rows = WidgetList::new();
+            if let Some(header) = header {
+                rows.push(header.make_widget(&context));
+            }
+            rows.push(contents.expand());
+            if let Some(footer) = footer {
+                rows.push(footer.make_widget(&context));
+            }
+            rows.into_rows().make_widget()
+        } else {
+            contents
+        };
+        match self.meta.background.as_ref().or(show.background.as_ref()) {
+            Some(background) => canvas::CanvasView::full_bleed(vec![
+                (background.make_widget(&context), canvas::Placement::FULL),
+                (contents, canvas::Placement::FULL),
+            ])
+            .make_widget(),
+            None => contents,
+        }
     }
 }
 
@@ -109,6 +340,8 @@ struct SlideSurface {
     contents: WidgetRef,
     current_slide: Dynamic<String>,
     slide_history: Dynamic<Vec<String>>,
+    step: Dynamic<usize>,
+    step_count: Dynamic<usize>,
     styles: Styles,
     base_font_size: Px,
 }
@@ -181,8 +414,11 @@ impl WrapperWidget for SlideSurface {
                 if input.state.is_pressed() {
                     match action {
                         Action::Next => {
+                            let step = self.step.get();
                             let next_slide = self.next_slide.get();
-                            if !next_slide.is_empty() {
+                            if step < self.step_count.get() {
+                                self.step.set(step + 1);
+                            } else if !next_slide.is_empty() {
                                 if let Some(previous_slide) = self.current_slide.replace(next_slide)
                                 {
                                     self.slide_history
@@ -190,11 +426,21 @@ impl WrapperWidget for SlideSurface {
                                 }
                             }
                         }
-                        Action::Previous => self.slide_history.map_mut(|mut history| {
-                            if let Some(previous_slide) = history.pop() {
-                                self.current_slide.set(previous_slide);
+                        Action::Previous => {
+                            let step = self.step.get();
+                            if step > 0 {
+                                self.step.set(step - 1);
+                            } else {
+                                self.slide_history.map_mut(|mut history| {
+                                    if let Some(previous_slide) = history.pop() {
+                                        self.current_slide.set(previous_slide);
+                                        // Returning to a slide shows it as it
+                                        // was left, with every step revealed.
+                                        self.step.set(self.step_count.get());
+                                    }
+                                });
                             }
-                        }),
+                        }
                     }
                 }
                 HANDLED
@@ -212,12 +458,11 @@ impl WrapperWidget for SlideSurface {
         let width_radio = size.width / 16.;
         let height_ratio = size.height / 9.;
         let min_ratio = width_radio.min(height_ratio);
-        let expected_ratio = 120.;
-        let base_font_size = Px::from(28. * min_ratio / expected_ratio).ceil();
+        let base_font_size = Px::from(DESIGN_TEXT_SIZE * min_ratio / DESIGN_UNIT).ceil();
 
         if base_font_size != self.base_font_size {
-            let base_line_height = Px::from(34. * min_ratio / expected_ratio).ceil();
-            let padding = Px::from(10. * min_ratio / expected_ratio).ceil();
+            let base_line_height = Px::from(DESIGN_LINE_HEIGHT * min_ratio / DESIGN_UNIT).ceil();
+            let padding = Px::from(DESIGN_PADDING * min_ratio / DESIGN_UNIT).ceil();
             self.styles
                 .insert(&BaseTextSize, Dimension::Px(base_font_size));
             self.styles
@@ -280,20 +525,180 @@ impl From<PrimaryColor> for ElementColor {
     }
 }
 
+pub struct Background {
+    kind: BackgroundKind,
+    fit: ImageFit,
+    opacity: f32,
+    loaded: OnceLock<Result<LazyTexture, String>>,
+}
+
+enum BackgroundKind {
+    Color(ElementColor),
+    LinearGradient {
+        from: ElementColor,
+        to: ElementColor,
+        angle: f32,
+    },
+    RadialGradient {
+        center: ElementColor,
+        edge: ElementColor,
+    },
+    Image(ImageSource),
+}
+
+impl Background {
+    fn new(kind: BackgroundKind) -> Self {
+        Self {
+            kind,
+            fit: ImageFit::Fill,
+            opacity: 1.,
+            loaded: OnceLock::new(),
+        }
+    }
+
+    pub fn color(color: impl Into<ElementColor>) -> Self {
+        Self::new(BackgroundKind::Color(color.into()))
+    }
+
+    // The angle is in degrees clockwise from left-to-right.
+    pub fn linear_gradient(
+        from: impl Into<ElementColor>,
+        to: impl Into<ElementColor>,
+        angle: f32,
+    ) -> Self {
+        Self::new(BackgroundKind::LinearGradient {
+            from: from.into(),
+            to: to.into(),
+            angle,
+        })
+    }
+
+    pub fn radial_gradient(center: impl Into<ElementColor>, edge: impl Into<ElementColor>) -> Self {
+        Self::new(BackgroundKind::RadialGradient {
+            center: center.into(),
+            edge: edge.into(),
+        })
+    }
+
+    pub fn image(source: impl Into<ImageSource>) -> Self {
+        Self::new(BackgroundKind::Image(source.into()))
+    }
+
+    pub fn fit(mut self, fit: ImageFit) -> Self {
+        self.fit = fit;
+        self
+    }
+
+    pub fn opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity.clamp(0., 1.);
+        self
+    }
+
+    fn texture(&self, source: &ImageSource) -> Result<LazyTexture, String> {
+        self.loaded
+            .get_or_init(|| match source {
+                ImageSource::Texture(texture) => Ok(texture.clone()),
+                ImageSource::File(file) => file.load(),
+            })
+            .clone()
+    }
+
+    fn make_widget(&self, context: &Context) -> WidgetInstance {
+        let resolve = |color: &ElementColor| {
+            let color = color.resolve(context);
+            color.with_alpha((f32::from(color.alpha()) * self.opacity).round() as u8)
+        };
+        let paint = match &self.kind {
+            BackgroundKind::Color(color) => background::Paint::Solid(resolve(color)),
+            BackgroundKind::LinearGradient { from, to, angle } => background::Paint::Linear {
+                from: resolve(from),
+                to: resolve(to),
+                angle: *angle,
+            },
+            BackgroundKind::RadialGradient { center, edge } => background::Paint::Radial {
+                center: resolve(center),
+                edge: resolve(edge),
+            },
+            BackgroundKind::Image(source) => {
+                // Errors are reported by validation, and the slide is still
+                // readable without its background.
+                let Ok(texture) = self.texture(source) else {
+                    return Space::clear().make_widget();
+                };
+                return background::ImageView::new(texture, self.fit, self.opacity).make_widget();
+            }
+        };
+        background::PaintView::new(paint).make_widget()
+    }
+
+    fn validate(&self, context: &mut ValidationContext<'_>) {
+        if let BackgroundKind::Image(source) = &self.kind {
+            if let Err(err) = self.texture(source) {
+                context.warn(format!("error loading background image: {err}"));
+            }
+        }
+    }
+}
+
+impl From<ElementColor> for Background {
+    fn from(color: ElementColor) -> Self {
+        Self::color(color)
+    }
+}
+
+impl From<Color> for Background {
+    fn from(color: Color) -> Self {
+        Self::color(color)
+    }
+}
+
+impl From<PrimaryColor> for Background {
+    fn from(color: PrimaryColor) -> Self {
+        Self::color(color)
+    }
+}
+
 #[derive(Clone)]
 pub struct Context<'a> {
     next_slide: &'a Dynamic<String>,
+    step: &'a Dynamic<usize>,
+    step_count: &'a Dynamic<usize>,
+    code_morphs: &'a Dynamic<morph::CodeMorphs>,
+    attrs: &'a HashMap<String, String>,
+    placeholders: &'a HashMap<String, Element>,
     align: HAlign,
+    list_depth: usize,
     theme: &'a Theme,
     color: ElementColor,
-    slide_index: usize,
+    deck: &'a DeckInfo,
+    slide_index: Option<usize>,
     slide_count: usize,
 }
 
+impl Context<'_> {
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs.get(name).map(String::as_str)
+    }
+
+    pub fn steps(&self, count: usize) -> Dynamic<usize> {
+        let first = self.step_count.map_mut(|mut step_count| {
+            let first = *step_count;
+            *step_count += count;
+            first
+        });
+        self.step
+            .map_each(move |step| step.saturating_sub(first).min(count))
+    }
+}
+
 pub struct SlideMeta {
     path: String,
     index: usize,
     next_slide: String,
+    background: Option<Background>,
+    header: bool,
+    footer: bool,
+    numbered: bool,
 }
 
 impl SlideMeta {
@@ -302,6 +707,10 @@ impl SlideMeta {
             path: path.into(),
             next_slide: String::new(),
             index: usize::MAX,
+            background: None,
+            header: true,
+            footer: true,
+            numbered: true,
         }
     }
 
@@ -310,6 +719,26 @@ impl SlideMeta {
         self
     }
 
+    pub fn with_background(mut self, background: impl Into<Background>) -> Self {
+        self.background = Some(background.into());
+        self
+    }
+
+    pub fn without_header(mut self) -> Self {
+        self.header = false;
+        self
+    }
+
+    pub fn without_footer(mut self) -> Self {
+        self.footer = false;
+        self
+    }
+
+    pub fn unnumbered(mut self) -> Self {
+        self.numbered = false;
+        self
+    }
+
     pub fn path(&self) -> &str {
         &self.path
     }
@@ -342,6 +771,67 @@ pub struct Element {
 
 pub trait SlideElement: Send + 'static {
     fn make_widget(&self, context: &Context) -> WidgetInstance;
+
+    fn validate(&self, _context: &mut ValidationContext<'_>) {}
+}
+
+pub struct ValidationContext<'a> {
+    slide: &'a str,
+    available_space: Size<Px>,
+    text_size: Px,
+    line_height: Px,
+    placeholders: &'a HashMap<String, Element>,
+    filled_placeholders: HashSet<String>,
+    warnings: &'a mut Vec<ValidationWarning>,
+}
+
+impl ValidationContext<'_> {
+    pub fn slide(&self) -> &str {
+        self.slide
+    }
+
+    pub fn available_space(&self) -> Size<Px> {
+        self.available_space
+    }
+
+    pub fn text_size(&self) -> Px {
+        self.text_size
+    }
+
+    pub fn line_height(&self) -> Px {
+        self.line_height
+    }
+
+    // Validates an element that only gets part of the available space, such
+    // as a column of a split, so that size estimates match what it will get.
+    pub fn validate_within(&mut self, element: &Element, width: f32, height: f32) {
+        let available_space = self.available_space;
+        self.available_space = Size::new(
+            Px::from(available_space.width.into_float() * width),
+            Px::from(available_space.height.into_float() * height),
+        );
+        element.validate(self);
+        self.available_space = available_space;
+    }
+
+    pub fn warn(&mut self, message: impl Into<String>) {
+        self.warnings.push(ValidationWarning {
+            slide: self.slide.to_string(),
+            message: message.into(),
+        });
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ValidationWarning {
+    pub slide: String,
+    pub message: String,
+}
+
+impl Display for ValidationWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "slide {}: {}", self.slide, self.message)
+    }
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
@@ -387,21 +877,53 @@ impl Element {
         self
     }
 
+    pub fn fit_text(self) -> Self {
+        FitText(self).into()
+    }
+
+    pub fn attr(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.attrs.insert(name.into(), value.into());
+        self
+    }
+
+    pub fn id(self, id: impl Into<String>) -> Self {
+        self.attr("id", id)
+    }
+
+    pub fn annotate(self, figure: Figure) -> Annotated {
+        Annotated {
+            element: self,
+            figures: vec![figure],
+        }
+    }
+
     fn make_widget(&self, context: &Context) -> WidgetInstance {
+        let (widget, align) = self.make_unaligned_widget(context);
+        aligned(widget, align)
+    }
+
+    fn make_unaligned_widget(&self, context: &Context) -> (WidgetInstance, HAlign) {
         let mut context = context.clone();
+        context.attrs = &self.attrs;
         context.align = self.align.unwrap_or(context.align);
         if let Some(color) = self.color {
             context.color = color;
         }
 
-        let widget = self.kind.make_widget(&context);
+        (self.kind.make_widget(&context), context.align)
+    }
 
-        match context.align {
-            HAlign::Left => widget.align_left().make_widget(),
-            HAlign::Center => widget.centered().make_widget(),
-            HAlign::Right => widget.align_right().make_widget(),
-            HAlign::Fill => widget,
-        }
+    fn validate(&self, context: &mut ValidationContext<'_>) {
+        self.kind.validate(context);
+    }
+}
+
+fn aligned(widget: WidgetInstance, align: HAlign) -> WidgetInstance {
+    match align {
+        HAlign::Left => widget.align_left().make_widget(),
+        HAlign::Center => widget.centered().make_widget(),
+        HAlign::Right => widget.align_right().make_widget(),
+        HAlign::Fill => widget,
     }
 }
 
@@ -422,69 +944,898 @@ impl SlideElement for LazyTexture {
     }
 }
 
-pub struct Text(pub String);
+pub struct SlideImage {
+    source: ImageSource,
+    fit: ImageFit,
+    width: Option<f32>,
+    height: Option<f32>,
+    caption: Option<Element>,
+    alt: String,
+    loaded: OnceLock<Result<LazyTexture, String>>,
+}
 
-impl SlideElement for Text {
-    fn make_widget(&self, context: &Context) -> WidgetInstance {
-        self.0
+impl SlideImage {
+    pub fn fit(mut self, fit: ImageFit) -> Self {
+        self.fit = fit;
+        self
+    }
+
+    pub fn width(mut self, units: f32) -> Self {
+        self.width = Some(units);
+        self
+    }
+
+    pub fn height(mut self, units: f32) -> Self {
+        self.height = Some(units);
+        self
+    }
+
+    pub fn caption(mut self, caption: impl Into<Element>) -> Self {
+        self.caption = Some(caption.into());
+        self
+    }
+
+    pub fn alt(mut self, alt: impl Into<String>) -> Self {
+        self.alt = alt.into();
+        self
+    }
+
+    fn texture(&self) -> Result<LazyTexture, String> {
+        self.loaded
+            .get_or_init(|| match &self.source {
+                ImageSource::Texture(texture) => Ok(texture.clone()),
+                ImageSource::File(file) => file.load(),
+            })
             .clone()
-            .with(&TextColor, context.color.resolve(context))
-            .make_widget()
     }
 }
 
-macro_rules! impl_heading {
-    ($type:ident, $fn:ident) => {
-        pub struct $type(Element);
-
-        impl SlideElement for $type {
-            fn make_widget(&self, context: &Context) -> WidgetInstance {
-                self.0.make_widget(context).$fn().make_widget()
+impl SlideElement for SlideImage {
+    fn make_widget(&self, context: &Context) -> WidgetInstance {
+        let image = match self.texture() {
+            Ok(texture) => {
+                let image = Image::new(texture);
+                let image = match self.fit {
+                    ImageFit::Fit => image.aspect_fit(),
+                    ImageFit::Fill => image.aspect_fill(),
+                    ImageFit::Stretch => image.stretch(),
+                    ImageFit::Original => image.scaled(1.),
+                };
+                image::SlideSized::new(image, self.width, self.height).make_widget()
             }
+            Err(_) if !self.alt.is_empty() => self
+                .alt
+                .clone()
+                .with(&TextColor, context.color.resolve(context))
+                .make_widget(),
+            Err(err) => format!("error loading image: {err}")
+                .with(&TextColor, context.theme.error.color)
+                .make_widget(),
+        };
+
+        match &self.caption {
+            Some(caption) => image
+                .and(caption.make_widget(context))
+                .into_rows()
+                .make_widget(),
+            None => image,
         }
+    }
 
-        pub fn $fn(contents: impl Into<Element>) -> Element {
-            $type(contents.into()).into()
+    fn validate(&self, context: &mut ValidationContext<'_>) {
+        if let Err(err) = self.texture() {
+            context.warn(format!("error loading image: {err}"));
         }
-    };
+        if let Some(caption) = &self.caption {
+            caption.validate(context);
+        }
+    }
 }
 
-impl_heading!(H1, h1);
-impl_heading!(H2, h2);
-impl_heading!(H3, h3);
-impl_heading!(H4, h4);
-impl_heading!(H5, h5);
-impl_heading!(H6, h6);
+pub fn image(source: impl Into<ImageSource>) -> SlideImage {
+    SlideImage {
+        source: source.into(),
+        fit: ImageFit::default(),
+        width: None,
+        height: None,
+        caption: None,
+        alt: String::new(),
+        loaded: OnceLock::new(),
+    }
+}
 
-pub struct Split {
-    elements: Vec<SplitElement>,
-    orientation: Orientation,
+enum AnimationSource {
+    File(PathBuf),
+    Sequence(PathBuf, f32),
 }
 
-impl SlideElement for Split {
-    fn make_widget(&self, context: &Context) -> WidgetInstance {
-        let mut expand = false;
-        let widgets = self
-            .elements
-            .iter()
-            .map(|e| {
-                expand |= matches!(e.measurement, SplitMeasurement::Expand { .. });
+pub struct AnimatedImage {
+    source: AnimationSource,
+    playback: Playback,
+    autoplay: bool,
+    width: Option<f32>,
+    height: Option<f32>,
+    frames: OnceLock<Result<Arc<animated::Frames>, String>>,
+}
 
-                let widget = e.element.make_widget(context);
-                match &e.measurement {
-                    SplitMeasurement::Fit => widget,
-                    SplitMeasurement::Expand { weight } => {
-                        expand = true;
-                        if *weight == 1 {
-                            match self.orientation {
-                                Orientation::Row => widget.expand_vertically(),
-                                Orientation::Column => widget.expand_horizontally(),
-                            }
-                        } else {
-                            widget.expand_weighted(*weight)
-                        }
-                        .make_widget()
-                    }
+impl AnimatedImage {
+    pub fn once(mut self) -> Self {
+        self.playback = Playback::Once;
+        self
+    }
+
+    pub fn looping(mut self) -> Self {
+        self.playback = Playback::Loop;
+        self
+    }
+
+    pub fn play_on_click(mut self) -> Self {
+        self.autoplay = false;
+        self
+    }
+
+    pub fn width(mut self, units: f32) -> Self {
+        self.width = Some(units);
+        self
+    }
+
+    pub fn height(mut self, units: f32) -> Self {
+        self.height = Some(units);
+        self
+    }
+
+    fn frames(&self) -> Result<Arc<animated::Frames>, String> {
+        self.frames
+            .get_or_init(|| match &self.source {
+                AnimationSource::File(path) => animated::decode(path),
+                AnimationSource::Sequence(directory, frame_rate) => {
+                    animated::sequence(directory, *frame_rate)
+                }
+            })
+            .clone()
+    }
+}
+
+impl SlideElement for AnimatedImage {
+    fn make_widget(&self, context: &Context) -> WidgetInstance {
+        // Widgets only exist while their slide is shown, so playback starts
+        // when the slide is entered and stops as soon as it is left.
+        match self.frames() {
+            Ok(frames) => image::SlideSized::new(
+                animated::AnimationView::new(frames, self.playback, self.autoplay),
+                self.width,
+                self.height,
+            )
+            .make_widget(),
+            Err(err) => format!("error loading animation: {err}")
+                .with(&TextColor, context.theme.error.color)
+                .make_widget(),
+        }
+    }
+
+    fn validate(&self, context: &mut ValidationContext<'_>) {
+        if let Err(err) = self.frames() {
+            context.warn(format!("error loading animation: {err}"));
+        }
+    }
+}
+
+pub fn animated_image(path: impl Into<PathBuf>) -> AnimatedImage {
+    AnimatedImage {
+        source: AnimationSource::File(path.into()),
+        playback: Playback::default(),
+        autoplay: true,
+        width: None,
+        height: None,
+        frames: OnceLock::new(),
+    }
+}
+
+pub fn image_sequence(directory: impl Into<PathBuf>, frame_rate: f32) -> AnimatedImage {
+    AnimatedImage {
+        source: AnimationSource::Sequence(directory.into(), frame_rate),
+        ..animated_image(PathBuf::new())
+    }
+}
+
+pub struct Video {
+    path: PathBuf,
+    poster: Option<PathBuf>,
+    autoplay: bool,
+    width: Option<f32>,
+    height: Option<f32>,
+    info: OnceLock<Result<video::VideoInfo, String>>,
+}
+
+impl Video {
+    pub fn paused(mut self) -> Self {
+        self.autoplay = false;
+        self
+    }
+
+    pub fn poster(mut self, path: impl Into<PathBuf>) -> Self {
+        self.poster = Some(path.into());
+        self
+    }
+
+    pub fn width(mut self, units: f32) -> Self {
+        self.width = Some(units);
+        self
+    }
+
+    pub fn height(mut self, units: f32) -> Self {
+        self.height = Some(units);
+        self
+    }
+
+    fn info(&self) -> Result<video::VideoInfo, String> {
+        self.info.get_or_init(|| video::probe(&self.path)).clone()
+    }
+}
+
+impl SlideElement for Video {
+    fn make_widget(&self, context: &Context) -> WidgetInstance {
+        // The poster is shown until the first frame is decoded, and is all
+        // that a paused video shows when the slide is exported.
+        let poster = self
+            .poster
+            .as_ref()
+            .and_then(|poster| image::load(poster, None, 0).ok());
+        match self.info() {
+            Ok(info) => image::SlideSized::new(
+                video::VideoView::new(self.path.clone(), info, poster, self.autoplay)
+                    .error_colors(context.theme.error.color, context.theme.surface.color),
+                self.width,
+                self.height,
+            )
+            .make_widget(),
+            Err(err) => format!("error loading video: {err}")
+                .with(&TextColor, context.theme.error.color)
+                .make_widget(),
+        }
+    }
+
+    fn validate(&self, context: &mut ValidationContext<'_>) {
+        if let Err(err) = self.info().and_then(|_| video::check_ffmpeg()) {
+            context.warn(format!("error loading video: {err}"));
+        }
+        if let Some(Err(err)) = self
+            .poster
+            .as_ref()
+            .map(|poster| image::load(poster, None, 0))
+        {
+            context.warn(format!("error loading poster: {err}"));
+        }
+    }
+}
+
+// Videos are probed and decoded by the `ffprobe` and `ffmpeg` programs, which
+// must be installed and on the `PATH`. Missing programs are reported by
+// `Show::validate` and shown in place of the video.
+pub fn video(path: impl Into<PathBuf>) -> Video {
+    Video {
+        path: path.into(),
+        poster: None,
+        autoplay: true,
+        width: None,
+        height: None,
+        info: OnceLock::new(),
+    }
+}
+
+pub struct Math {
+    source: String,
+    display: bool,
+    parsed: OnceLock<Result<Arc<Vec<math::Node>>, String>>,
+}
+
+impl Math {
+    pub fn inline(mut self) -> Self {
+        self.display = false;
+        self
+    }
+
+    pub fn display(mut self) -> Self {
+        self.display = true;
+        self
+    }
+
+    fn parsed(&self) -> Result<Arc<Vec<math::Node>>, String> {
+        self.parsed
+            .get_or_init(|| math::parse(&self.source))
+            .clone()
+    }
+}
+
+impl SlideElement for Math {
+    fn make_widget(&self, context: &Context) -> WidgetInstance {
+        match self.parsed() {
+            Ok(nodes) => math::MathView::new(nodes, self.display)
+                .with(&TextColor, context.color.resolve(context))
+                .make_widget(),
+            Err(err) => format!("error in formula: {err}")
+                .with(&TextColor, context.theme.error.color)
+                .make_widget(),
+        }
+    }
+
+    fn validate(&self, context: &mut ValidationContext<'_>) {
+        if let Err(err) = self.parsed() {
+            context.warn(format!("error in formula: {err}"));
+        }
+    }
+}
+
+pub fn math(source: impl Into<String>) -> Math {
+    Math {
+        source: source.into(),
+        display: true,
+        parsed: OnceLock::new(),
+    }
+}
+
+pub struct Diagram {
+    source: String,
+    stepped: bool,
+    graph: OnceLock<Result<Arc<diagram::Graph>, String>>,
+}
+
+impl Diagram {
+    pub fn stepped(mut self) -> Self {
+        self.stepped = true;
+        self
+    }
+
+    fn graph(&self) -> Result<Arc<diagram::Graph>, String> {
+        self.graph
+            .get_or_init(|| diagram::parse(&self.source))
+            .clone()
+    }
+}
+
+impl SlideElement for Diagram {
+    fn make_widget(&self, context: &Context) -> WidgetInstance {
+        let graph = match self.graph() {
+            Ok(graph) => graph,
+            Err(err) => {
+                return format!("error in diagram: {err}")
+                    .with(&TextColor, context.theme.error.color)
+                    .make_widget()
+            }
+        };
+
+        // Stepped diagrams reveal one node per step, in the order the nodes
+        // first appear in the source.
+        let visible = if self.stepped {
+            context
+                .steps(graph.node_count().saturating_sub(1))
+                .map_each(|step| step + 1)
+        } else {
+            Dynamic::new(usize::MAX)
+        };
+        diagram::DiagramView::new(
+            graph,
+            diagram::DiagramColors {
+                node: context.theme.surface.highest_container,
+                outline: context.theme.primary.color,
+                text: context.color.resolve(context),
+                edge: context.theme.surface.outline,
+                background: context.theme.surface.color,
+            },
+            visible,
+        )
+        .make_widget()
+    }
+
+    fn validate(&self, context: &mut ValidationContext<'_>) {
+        if let Err(err) = self.graph() {
+            context.warn(format!("error in diagram: {err}"));
+        }
+    }
+}
+
+pub fn diagram(dot_source: impl Into<String>) -> Diagram {
+    Diagram {
+        source: dot_source.into(),
+        stepped: false,
+        graph: OnceLock::new(),
+    }
+}
+
+pub struct Chart {
+    kind: chart::ChartKind,
+    source: ChartSource,
+    x_label: Option<String>,
+    y_label: Option<String>,
+    animated: bool,
+    data: OnceLock<Result<Arc<ChartData>, String>>,
+}
+
+impl Chart {
+    pub fn x_label(mut self, label: impl Into<String>) -> Self {
+        self.x_label = Some(label.into());
+        self
+    }
+
+    pub fn y_label(mut self, label: impl Into<String>) -> Self {
+        self.y_label = Some(label.into());
+        self
+    }
+
+    pub fn animated(mut self) -> Self {
+        self.animated = true;
+        self
+    }
+
+    fn data(&self) -> Result<Arc<ChartData>, String> {
+        self.data.get_or_init(|| chart::load(&self.source)).clone()
+    }
+}
+
+impl SlideElement for Chart {
+    fn make_widget(&self, context: &Context) -> WidgetInstance {
+        match self.data() {
+            Ok(data) => chart::ChartView::new(
+                self.kind,
+                data,
+                chart::ChartColors {
+                    text: context.color.resolve(context),
+                    axis: context.theme.surface.outline,
+                    grid: context.theme.surface.outline_variant,
+                    palette: vec![
+                        context.theme.primary.color,
+                        context.theme.secondary.color,
+                        context.theme.tertiary.color,
+                        context.theme.primary.container,
+                        context.theme.secondary.container,
+                        context.theme.tertiary.container,
+                    ],
+                },
+                self.x_label.clone(),
+                self.y_label.clone(),
+                self.animated,
+            )
+            .make_widget(),
+            Err(err) => format!("error loading chart: {err}")
+                .with(&TextColor, context.theme.error.color)
+                .make_widget(),
+        }
+    }
+
+    fn validate(&self, context: &mut ValidationContext<'_>) {
+        match self.data() {
+            Ok(data) if self.kind == chart::ChartKind::Pie => {
+                if data.series_count() > 1 {
+                    context.warn("pie charts only show the first series");
+                }
+                if data.has_negative_values() {
+                    context.warn("pie charts can't show negative values");
+                }
+            }
+            Ok(_) => {}
+            Err(err) => context.warn(format!("error loading chart: {err}")),
+        }
+    }
+}
+
+fn chart(kind: chart::ChartKind, data: impl Into<ChartSource>) -> Chart {
+    Chart {
+        kind,
+        source: data.into(),
+        x_label: None,
+        y_label: None,
+        animated: false,
+        data: OnceLock::new(),
+    }
+}
+
+pub fn bar_chart(data: impl Into<ChartSource>) -> Chart {
+    chart(chart::ChartKind::Bar, data)
+}
+
+pub fn line_chart(data: impl Into<ChartSource>) -> Chart {
+    chart(chart::ChartKind::Line, data)
+}
+
+pub fn pie_chart(data: impl Into<ChartSource>) -> Chart {
+    chart(chart::ChartKind::Pie, data)
+}
+
+pub struct Figure {
+    kind: figure::FigureKind,
+    start: (f32, f32),
+    end: (f32, f32),
+    target: Option<(f32, f32)>,
+    fill: Option<ElementColor>,
+    stroke: Option<ElementColor>,
+    stroked: bool,
+    stroke_width: f32,
+    text: Option<String>,
+    line: Option<(String, usize)>,
+    width: Option<f32>,
+    height: Option<f32>,
+}
+
+impl Figure {
+    fn new(kind: figure::FigureKind) -> Self {
+        Self {
+            kind,
+            start: (0., 0.),
+            end: (1., 1.),
+            target: None,
+            fill: None,
+            stroke: None,
+            stroked: true,
+            stroke_width: 3.,
+            text: None,
+            line: None,
+            width: None,
+            height: None,
+        }
+    }
+
+    // Positions are fractions of the annotated element's bounds, or of the
+    // figure's own bounds when it is placed on a slide by itself. Values
+    // outside of 0..1 reach past the element.
+    pub fn at(mut self, x: f32, y: f32) -> Self {
+        let size = (self.end.0 - self.start.0, self.end.1 - self.start.1);
+        self.start = (x, y);
+        self.end = (x + size.0, y + size.1);
+        self
+    }
+
+    // The extent is a fraction of the reference bounds, like the position.
+    // `width` and `height` size a standalone figure in slide units instead.
+    pub fn extent(mut self, width: f32, height: f32) -> Self {
+        self.end = (self.start.0 + width, self.start.1 + height);
+        self
+    }
+
+    // Targets a line of the code element whose id matches, counting from 1
+    // at the first line shown. Boxes and circles surround the line, while
+    // lines, arrows and callouts point at its start.
+    pub fn on_line(mut self, id: impl Into<String>, line: usize) -> Self {
+        self.line = Some((id.into(), line));
+        self
+    }
+
+    pub fn pointing_at(mut self, x: f32, y: f32) -> Self {
+        self.target = Some((x, y));
+        self
+    }
+
+    pub fn fill(mut self, color: impl Into<ElementColor>) -> Self {
+        self.fill = Some(color.into());
+        self
+    }
+
+    pub fn stroke(mut self, color: impl Into<ElementColor>) -> Self {
+        self.stroke = Some(color.into());
+        self.stroked = true;
+        self
+    }
+
+    pub fn no_stroke(mut self) -> Self {
+        self.stroked = false;
+        self
+    }
+
+    pub fn stroke_width(mut self, width: f32) -> Self {
+        self.stroke_width = width;
+        self
+    }
+
+    pub fn width(mut self, width: f32) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    pub fn height(mut self, height: f32) -> Self {
+        self.height = Some(height);
+        self
+    }
+
+    fn drawing(&self, context: &Context) -> figure::Drawing {
+        let callout = self.kind == figure::FigureKind::Callout;
+        let fill = match self.fill {
+            Some(fill) => Some(fill.resolve(context)),
+            None if callout => Some(context.theme.surface.highest_container),
+            None => None,
+        };
+        let stroke = self
+            .stroked
+            .then(|| self.stroke.unwrap_or(context.color).resolve(context));
+        figure::Drawing {
+            kind: self.kind,
+            start: self.start,
+            end: self.end,
+            target: self.target,
+            fill,
+            stroke,
+            stroke_width: self.stroke_width,
+            text: self
+                .text
+                .clone()
+                .map(|text| (text, context.color.resolve(context))),
+            line: self.line.clone().map(|(id, line)| figure::CodeLine {
+                id,
+                line,
+                morphs: context.code_morphs.clone(),
+            }),
+        }
+    }
+}
+
+impl SlideElement for Figure {
+    fn make_widget(&self, context: &Context) -> WidgetInstance {
+        image::SlideSized::new(
+            figure::FigureView::new(vec![self.drawing(context)]),
+            self.width,
+            self.height,
+        )
+        .make_widget()
+    }
+
+    fn validate(&self, context: &mut ValidationContext<'_>) {
+        if self.target.is_some() && self.kind != figure::FigureKind::Callout {
+            context.warn("only callouts can point at a location");
+        }
+        if matches!(self.line, Some((_, 0))) {
+            context.warn("code lines are numbered from 1");
+        }
+    }
+}
+
+pub fn rectangle() -> Figure {
+    Figure::new(figure::FigureKind::Rectangle)
+}
+
+pub fn rounded_box(radius: f32) -> Figure {
+    Figure::new(figure::FigureKind::RoundedBox(radius))
+}
+
+// Circles keep their aspect ratio within the figure's bounds, while
+// ellipses stretch to fill them.
+pub fn circle() -> Figure {
+    Figure::new(figure::FigureKind::Circle)
+}
+
+pub fn ellipse() -> Figure {
+    Figure::new(figure::FigureKind::Ellipse)
+}
+
+pub fn line(from: (f32, f32), to: (f32, f32)) -> Figure {
+    Figure::new(figure::FigureKind::Line)
+        .at(from.0, from.1)
+        .extent(to.0 - from.0, to.1 - from.1)
+}
+
+pub fn arrow(from: (f32, f32), to: (f32, f32)) -> Figure {
+    Figure {
+        kind: figure::FigureKind::Arrow,
+        ..line(from, to)
+    }
+}
+
+pub fn callout(text: impl Into<String>) -> Figure {
+    Figure {
+        text: Some(text.into()),
+        ..Figure::new(figure::FigureKind::Callout)
+    }
+}
+
+pub struct Annotated {
+    element: Element,
+    figures: Vec<Figure>,
+}
+
+impl Annotated {
+    pub fn annotate(mut self, figure: Figure) -> Self {
+        self.figures.push(figure);
+        self
+    }
+}
+
+impl SlideElement for Annotated {
+    fn make_widget(&self, context: &Context) -> WidgetInstance {
+        // The figures are placed relative to the element itself, so the
+        // element's alignment is applied around the annotations.
+        let (widget, align) = self.element.make_unaligned_widget(context);
+        let drawings = self
+            .figures
+            .iter()
+            .map(|figure| figure.drawing(context))
+            .collect();
+        aligned(
+            figure::FigureView::annotating(widget, drawings).make_widget(),
+            align,
+        )
+    }
+
+    fn validate(&self, context: &mut ValidationContext<'_>) {
+        self.element.validate(context);
+        for figure in &self.figures {
+            figure.validate(context);
+        }
+    }
+}
+
+pub struct Svg {
+    source: String,
+    width: Option<f32>,
+    height: Option<f32>,
+    tree: OnceLock<Result<Arc<resvg::usvg::Tree>, String>>,
+}
+
+impl Svg {
+    pub fn width(mut self, units: f32) -> Self {
+        self.width = Some(units);
+        self
+    }
+
+    pub fn height(mut self, units: f32) -> Self {
+        self.height = Some(units);
+        self
+    }
+
+    fn tree(&self) -> Result<Arc<resvg::usvg::Tree>, String> {
+        self.tree.get_or_init(|| svg::parse(&self.source)).clone()
+    }
+}
+
+impl SlideElement for Svg {
+    fn make_widget(&self, context: &Context) -> WidgetInstance {
+        match self.tree() {
+            Ok(tree) => image::SlideSized::new(svg::SvgView::new(tree), self.width, self.height)
+                .make_widget(),
+            Err(err) => format!("error loading svg: {err}")
+                .with(&TextColor, context.theme.error.color)
+                .make_widget(),
+        }
+    }
+
+    fn validate(&self, context: &mut ValidationContext<'_>) {
+        if let Err(err) = self.tree() {
+            context.warn(format!("error loading svg: {err}"));
+        }
+    }
+}
+
+pub fn svg(path_or_source: impl Into<String>) -> Svg {
+    Svg {
+        source: path_or_source.into(),
+        width: None,
+        height: None,
+        tree: OnceLock::new(),
+    }
+}
+
+pub struct Text(pub String);
+
+impl SlideElement for Text {
+    fn make_widget(&self, context: &Context) -> WidgetInstance {
+        self.0
+            .clone()
+            .with(&TextColor, context.color.resolve(context))
+            .make_widget()
+    }
+}
+
+impl RichText {
+    fn view(&self, context: &Context) -> rich::RichTextView {
+        let color = context.color.resolve(context);
+        let runs = self
+            .runs()
+            .iter()
+            .map(|run| {
+                let run_color = match (run.style.color, &run.style.link) {
+                    (Some(color), _) => color.resolve(context),
+                    (None, Some(_)) => context.theme.primary.color,
+                    (None, None) => color,
+                };
+                (run.clone(), run_color)
+            })
+            .collect();
+        rich::RichTextView::new(runs, context.theme.surface.highest_container)
+            .error_color(context.theme.error.color)
+    }
+}
+
+impl SlideElement for RichText {
+    fn make_widget(&self, context: &Context) -> WidgetInstance {
+        self.view(context).make_widget()
+    }
+}
+
+pub fn rich(markup: impl AsRef<str>) -> RichText {
+    RichText::new().markup(markup.as_ref())
+}
+
+pub struct Paragraph {
+    text: RichText,
+    line_spacing: f32,
+}
+
+impl Paragraph {
+    pub fn line_spacing(mut self, line_spacing: f32) -> Self {
+        self.line_spacing = line_spacing;
+        self
+    }
+}
+
+impl SlideElement for Paragraph {
+    fn make_widget(&self, context: &Context) -> WidgetInstance {
+        self.text
+            .view(context)
+            .align(context.align)
+            .line_spacing(self.line_spacing)
+            .make_widget()
+    }
+}
+
+pub fn paragraph(text: impl Into<RichText>) -> Paragraph {
+    Paragraph {
+        text: text.into(),
+        line_spacing: 1.,
+    }
+}
+
+macro_rules! impl_heading {
+    ($type:ident, $fn:ident) => {
+        pub struct $type(Element);
+
+        impl SlideElement for $type {
+            fn make_widget(&self, context: &Context) -> WidgetInstance {
+                self.0.make_widget(context).$fn().make_widget()
+            }
+
+            fn validate(&self, context: &mut ValidationContext<'_>) {
+                self.0.validate(context);
+            }
+        }
+
+        pub fn $fn(contents: impl Into<Element>) -> Element {
+            $type(contents.into()).into()
+        }
+    };
+}
+
+impl_heading!(H1, h1);
+impl_heading!(H2, h2);
+impl_heading!(H3, h3);
+impl_heading!(H4, h4);
+impl_heading!(H5, h5);
+impl_heading!(H6, h6);
+
+pub struct Split {
+    elements: Vec<SplitElement>,
+    orientation: Orientation,
+}
+
+impl SlideElement for Split {
+    fn make_widget(&self, context: &Context) -> WidgetInstance {
+        let mut expand = false;
+        let widgets = self
+            .elements
+            .iter()
+            .map(|e| {
+                expand |= matches!(e.measurement, SplitMeasurement::Expand { .. });
+
+                let widget = e.element.make_widget(context);
+                match &e.measurement {
+                    SplitMeasurement::Fit => widget,
+                    SplitMeasurement::Expand { weight } => {
+                        expand = true;
+                        if *weight == 1 {
+                            match self.orientation {
+                                Orientation::Row => widget.expand_vertically(),
+                                Orientation::Column => widget.expand_horizontally(),
+                            }
+                        } else {
+                            widget.expand_weighted(*weight)
+                        }
+                        .make_widget()
+                    }
                 }
             })
             .collect::<WidgetList>();
@@ -502,6 +1853,32 @@ impl SlideElement for Split {
         stack.make_widget()
         // }
     }
+
+    fn validate(&self, context: &mut ValidationContext<'_>) {
+        // Expanding elements share the space by weight. Elements that fit
+        // their contents could take up to all of it, so they are checked
+        // against the whole space.
+        let total_weight = self
+            .elements
+            .iter()
+            .map(|element| match element.measurement {
+                SplitMeasurement::Fit => 0.,
+                SplitMeasurement::Expand { weight } => f32::from(weight),
+            })
+            .sum::<f32>();
+        for element in &self.elements {
+            let share = match element.measurement {
+                SplitMeasurement::Expand { weight } if total_weight > 0. => {
+                    f32::from(weight) / total_weight
+                }
+                _ => 1.,
+            };
+            match self.orientation {
+                Orientation::Column => context.validate_within(&element.element, share, 1.),
+                Orientation::Row => context.validate_within(&element.element, 1., share),
+            }
+        }
+    }
 }
 
 pub struct SplitElement {
@@ -527,182 +1904,707 @@ pub fn vsplit(elements: impl SplitElements) -> Element {
         orientation: Orientation::Row,
         elements: elements.into_elements(),
     }
-    .into()
+    .into()
+}
+
+pub trait SplitElements {
+    fn into_elements(self) -> Vec<SplitElement>;
+}
+
+// impl<T> SplitElements for T
+// where
+//     T: IntoSplitElement,
+// {
+//     fn into_elements(self) -> Vec<SplitElement> {
+//         vec![self.into_split_element()]
+//     }
+// }
+
+impl SplitElements for Vec<SplitElement> {
+    fn into_elements(self) -> Vec<SplitElement> {
+        self
+    }
+}
+
+macro_rules! impl_all_tuples {
+    ($macro_name:ident) => {
+        impl_all_tuples!($macro_name, 1);
+    };
+    ($macro_name:ident, 1) => {
+        $macro_name!(T0 0 t0);
+        $macro_name!(T0 0 t0, T1 1 t1);
+        $macro_name!(T0 0 t0, T1 1 t1, T2 2 t2);
+        $macro_name!(T0 0 t0, T1 1 t1, T2 2 t2, T3 3 t3);
+        $macro_name!(T0 0 t0, T1 1 t1, T2 2 t2, T3 3 t3, T4 4 t4);
+        $macro_name!(T0 0 t0, T1 1 t1, T2 2 t2, T3 3 t3, T4 4 t4, T5 5 t5);
+    };
+}
+
+macro_rules! impl_split_elements_for_tuples {
+    ($($type:ident $field:tt $var:ident),+) => {
+        impl<$($type),+> SplitElements for ($($type,)+)
+        where
+            $($type: IntoSplitElement),+
+        {
+            fn into_elements(self) -> Vec<SplitElement> {
+                vec![$(self.$field.into_split_element()),+]
+            }
+        }
+    };
+}
+
+impl_all_tuples!(impl_split_elements_for_tuples);
+
+pub trait IntoSplitElement {
+    fn into_split_element(self) -> SplitElement;
+}
+
+impl<T> IntoSplitElement for T
+where
+    T: Into<Element>,
+{
+    fn into_split_element(self) -> SplitElement {
+        SplitElement {
+            element: self.into(),
+            measurement: SplitMeasurement::Expand { weight: 1 },
+        }
+    }
+}
+
+impl IntoSplitElement for SplitElement {
+    fn into_split_element(self) -> SplitElement {
+        self
+    }
+}
+
+pub fn fit(element: impl Into<Element>) -> SplitElement {
+    SplitElement {
+        element: element.into(),
+        measurement: SplitMeasurement::Fit,
+    }
+}
+
+pub fn expand(element: impl Into<Element>) -> SplitElement {
+    expand_weighted(1, element)
+}
+
+pub fn expand_weighted(weight: u8, element: impl Into<Element>) -> SplitElement {
+    SplitElement {
+        element: element.into(),
+        measurement: SplitMeasurement::Expand { weight },
+    }
+}
+
+pub fn stack(elements: impl Elements) -> Element {
+    vsplit(
+        elements
+            .into_elements()
+            .into_iter()
+            .map(fit)
+            .collect::<Vec<_>>(),
+    )
+}
+
+pub fn hstack(elements: impl Elements) -> Element {
+    hsplit(
+        elements
+            .into_elements()
+            .into_iter()
+            .map(fit)
+            .collect::<Vec<_>>(),
+    )
+}
+
+pub trait Elements {
+    fn into_elements(self) -> Vec<Element>;
+}
+
+// impl<T> Elements for T
+// where
+//     T: Into<Element>,
+// {
+//     fn into_elements(self) -> Vec<Element> {
+//         vec![self.into()]
+//     }
+// }
+
+impl Elements for Vec<Element> {
+    fn into_elements(self) -> Vec<Element> {
+        self
+    }
+}
+
+macro_rules! impl_elements_for_tuples {
+    ($($type:ident $field:tt $var:ident),+) => {
+        impl<$($type),+> Elements for ($($type,)+)
+        where
+            $($type: Into<Element>),+
+        {
+            fn into_elements(self) -> Vec<Element> {
+                vec![$(self.$field.into()),+]
+            }
+        }
+    };
+}
+
+impl_all_tuples!(impl_elements_for_tuples);
+
+mod animated;
+mod background;
+mod canvas;
+mod chart;
+mod code;
+mod diagram;
+mod diff;
+mod figure;
+mod fit;
+mod image;
+mod math;
+mod morph;
+mod rich;
+mod run;
+mod shapes;
+mod snippet;
+mod svg;
+mod table;
+mod terminal;
+mod video;
+
+pub use animated::Playback;
+pub use chart::{ChartData, ChartSource};
+pub use code::CodeOverflow;
+pub use diff::DiffLayout;
+pub use image::{ImageFile, ImageFit, ImageSource};
+pub use rich::RichText;
+pub use run::RunCommand;
+pub use snippet::SnippetError;
+
+#[derive(Clone)]
+pub struct Code {
+    lang: String,
+    source: snippet::Snippet,
+    overflow: CodeOverflow,
+}
+
+impl Code {
+    pub fn new(lang: impl Into<String>, source: impl Into<String>) -> Self {
+        Self {
+            lang: lang.into(),
+            source: snippet::Snippet::from_source(source),
+            overflow: CodeOverflow::default(),
+        }
+    }
+
+    pub fn from_file(path: impl Into<PathBuf>, lang: impl Into<String>) -> Self {
+        Self {
+            lang: lang.into(),
+            source: snippet::Snippet::from_file(path),
+            overflow: CodeOverflow::default(),
+        }
+    }
+
+    pub fn anchor(mut self, name: impl Into<String>) -> Self {
+        self.source.set_anchor(name);
+        self
+    }
+
+    pub fn lines(mut self, lines: impl RangeBounds<usize>) -> Self {
+        self.source.set_lines(lines);
+        self
+    }
+
+    pub fn load(&self) -> Result<String, SnippetError> {
+        self.source.load()
+    }
+
+    pub fn overflow(mut self, overflow: CodeOverflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    pub fn fit_to_size(self) -> Self {
+        self.overflow(CodeOverflow::Fit)
+    }
+
+    pub fn scrollable(self) -> Self {
+        self.overflow(CodeOverflow::Scroll)
+    }
+
+    pub fn runnable(self, command: RunCommand) -> RunnableCode {
+        RunnableCode {
+            code: self,
+            command,
+            cache: None,
+            replay: false,
+        }
+    }
+}
+
+impl SlideElement for Code {
+    fn make_widget(&self, context: &Context) -> WidgetInstance {
+        let source = match self.load() {
+            Ok(source) => source,
+            Err(err) => {
+                return err
+                    .to_string()
+                    .with(&TextColor, context.theme.error.color)
+                    .contain()
+                    .make_widget()
+            }
+        };
+        let mut view = code::CodeView::new(
+            self.lang.clone(),
+            SyntaxSet::load_defaults_newlines(),
+            code_theme(),
+            source,
+        )
+        .overflow(self.overflow)
+        .error_color(context.theme.error.color);
+        // Errors setting up the watcher are reported by validation, and the
+        // code is still shown without live reloading.
+        if let Ok(Some(watcher)) = self.source.watch() {
+            view = view.reload_from(watcher);
+        }
+        if let Some(id) = context.attr("id") {
+            view = view.morph_with(morph::Morph::new(id, context.code_morphs.clone()));
+        }
+        view.contain().make_widget()
+    }
+
+    fn validate(&self, context: &mut ValidationContext<'_>) {
+        let source = match self.load() {
+            Ok(source) => source,
+            Err(err) => {
+                context.warn(err.to_string());
+                return;
+            }
+        };
+        if let Err(err) = self.source.watch() {
+            context.warn(format!("live reloading disabled: {err}"));
+        }
+        if self.overflow == CodeOverflow::Scroll {
+            return;
+        }
+
+        // Monospace glyphs are roughly 0.6em wide, which is close enough to
+        // flag snippets that can't possibly fit on a slide.
+        let lines = source.lines().count();
+        let columns = source
+            .lines()
+            .map(|line| {
+                line.chars()
+                    .map(|ch| if ch == '\t' { 4 } else { 1 })
+                    .sum::<usize>()
+            })
+            .max()
+            .unwrap_or(0);
+        let estimated = Size::new(
+            context.text_size().into_float() * 0.6 * columns as f32,
+            context.line_height().into_float() * lines as f32,
+        );
+        let available = context.available_space().into_float();
+        let ratio = (available.width / estimated.width).min(available.height / estimated.height);
+        if ratio < 1. {
+            let message = match self.overflow {
+                CodeOverflow::Fit => format!(
+                    "{} code block ({lines} lines, {columns} columns) will be shrunk to about {}% of its size",
+                    self.lang,
+                    (ratio * 100.) as u32
+                ),
+                CodeOverflow::Clip | CodeOverflow::Scroll => format!(
+                    "{} code block ({lines} lines, {columns} columns) does not fit the slide and will be clipped",
+                    self.lang
+                ),
+            };
+            context.warn(message);
+        }
+    }
+}
+
+// These return an `Element` like the other text elements. Use `Code::new` or
+// `Code::from_file` to configure anchors, line ranges or overflow first.
+pub fn code(lang: impl Into<String>, source: impl Into<String>) -> Element {
+    Code::new(lang, source).into()
+}
+
+fn code_theme() -> syntect::highlighting::Theme {
+    ThemeSet::load_defaults()
+        .themes
+        .remove("base16-mocha.dark")
+        .expect("missing theme")
+}
+
+pub fn code_from_file(path: impl Into<PathBuf>, lang: impl Into<String>) -> Element {
+    Code::from_file(path, lang).into()
+}
+
+pub struct CodeDiff {
+    lang: String,
+    before: String,
+    after: String,
+    layout: DiffLayout,
+    stepped: bool,
+}
+
+impl CodeDiff {
+    pub fn layout(mut self, layout: DiffLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    pub fn side_by_side(self) -> Self {
+        self.layout(DiffLayout::SideBySide)
+    }
+
+    pub fn stepped(mut self) -> Self {
+        self.stepped = true;
+        self
+    }
+}
+
+impl SlideElement for CodeDiff {
+    fn make_widget(&self, context: &Context) -> WidgetInstance {
+        let stage = if self.stepped {
+            context.steps(2).map_each(|step| match step {
+                0 => diff::DiffStage::Before,
+                1 => diff::DiffStage::Changes,
+                _ => diff::DiffStage::After,
+            })
+        } else {
+            Dynamic::new(diff::DiffStage::Changes)
+        };
+
+        diff::DiffView::new(
+            self.lang.clone(),
+            SyntaxSet::load_defaults_newlines(),
+            code_theme(),
+            self.before.clone(),
+            self.after.clone(),
+            self.layout,
+            stage,
+        )
+        .contain()
+        .make_widget()
+    }
+}
+
+pub fn code_diff(
+    lang: impl Into<String>,
+    before: impl Into<String>,
+    after: impl Into<String>,
+) -> CodeDiff {
+    CodeDiff {
+        lang: lang.into(),
+        before: before.into(),
+        after: after.into(),
+        layout: DiffLayout::default(),
+        stepped: false,
+    }
 }
 
-pub trait SplitElements {
-    fn into_elements(self) -> Vec<SplitElement>;
+pub struct RunnableCode {
+    code: Code,
+    command: RunCommand,
+    cache: Option<PathBuf>,
+    replay: bool,
 }
 
-// impl<T> SplitElements for T
-// where
-//     T: IntoSplitElement,
-// {
-//     fn into_elements(self) -> Vec<SplitElement> {
-//         vec![self.into_split_element()]
-//     }
-// }
+impl RunnableCode {
+    pub fn cache_dir(mut self, cache: impl Into<PathBuf>) -> Self {
+        self.cache = Some(cache.into());
+        self
+    }
 
-impl SplitElements for Vec<SplitElement> {
-    fn into_elements(self) -> Vec<SplitElement> {
+    pub fn replay_cached(mut self) -> Self {
+        self.replay = true;
         self
     }
 }
 
-macro_rules! impl_all_tuples {
-    ($macro_name:ident) => {
-        impl_all_tuples!($macro_name, 1);
-    };
-    ($macro_name:ident, 1) => {
-        $macro_name!(T0 0 t0);
-        $macro_name!(T0 0 t0, T1 1 t1);
-        $macro_name!(T0 0 t0, T1 1 t1, T2 2 t2);
-        $macro_name!(T0 0 t0, T1 1 t1, T2 2 t2, T3 3 t3);
-        $macro_name!(T0 0 t0, T1 1 t1, T2 2 t2, T3 3 t3, T4 4 t4);
-        $macro_name!(T0 0 t0, T1 1 t1, T2 2 t2, T3 3 t3, T4 4 t4, T5 5 t5);
-    };
-}
+impl SlideElement for RunnableCode {
+    fn make_widget(&self, context: &Context) -> WidgetInstance {
+        let cache = self
+            .cache
+            .clone()
+            .or_else(|| context.deck.cache_dir.clone())
+            .unwrap_or_else(|| PathBuf::from(".cushy-show-cache"));
+        let runner = run::Runner::new(self.command.clone(), cache, self.replay);
+        let running = runner
+            .state
+            .map_each(|state| *state == run::RunState::Running);
+
+        let run = "Run"
+            .into_button()
+            .on_click({
+                let runner = runner.clone();
+                let code = self.code.clone();
+                move |_| match code.load() {
+                    Ok(source) => runner.run(source),
+                    Err(err) => runner.fail(err),
+                }
+            })
+            .with_enabled(running.map_each(|running| !running));
+        let kill = "Kill"
+            .into_button()
+            .on_click({
+                let runner = run::KillOnDrop(runner.clone());
+                move |_| runner.0.kill()
+            })
+            .with_enabled(running);
+
+        self.code
+            .make_widget(context)
+            .and(
+                run.and(kill)
+                    .and(runner.state.map_each(ToString::to_string))
+                    .into_columns(),
+            )
+            .and(runner.output.vertical_scroll().expand())
+            .into_rows()
+            .make_widget()
+    }
 
-macro_rules! impl_split_elements_for_tuples {
-    ($($type:ident $field:tt $var:ident),+) => {
-        impl<$($type),+> SplitElements for ($($type,)+)
-        where
-            $($type: IntoSplitElement),+
-        {
-            fn into_elements(self) -> Vec<SplitElement> {
-                vec![$(self.$field.into_split_element()),+]
-            }
-        }
-    };
+    fn validate(&self, context: &mut ValidationContext<'_>) {
+        self.code.validate(context);
+    }
 }
 
-impl_all_tuples!(impl_split_elements_for_tuples);
+pub fn runnable_code(
+    lang: impl Into<String>,
+    source: impl Into<String>,
+    command: RunCommand,
+) -> RunnableCode {
+    Code::new(lang, source).runnable(command)
+}
 
-pub trait IntoSplitElement {
-    fn into_split_element(self) -> SplitElement;
+pub struct Terminal {
+    command: terminal::TerminalCommand,
 }
 
-impl<T> IntoSplitElement for T
-where
-    T: Into<Element>,
-{
-    fn into_split_element(self) -> SplitElement {
-        SplitElement {
-            element: self.into(),
-            measurement: SplitMeasurement::Expand { weight: 1 },
+impl Terminal {
+    pub fn arg(mut self, arg: impl Into<String>) -> Self {
+        self.command.arg(arg.into());
+        self
+    }
+
+    pub fn args<Arg>(mut self, args: impl IntoIterator<Item = Arg>) -> Self
+    where
+        Arg: Into<String>,
+    {
+        for arg in args {
+            self.command.arg(arg.into());
         }
+        self
     }
-}
 
-impl IntoSplitElement for SplitElement {
-    fn into_split_element(self) -> SplitElement {
+    pub fn current_dir(mut self, cwd: impl Into<PathBuf>) -> Self {
+        self.command.set_cwd(cwd.into());
         self
     }
 }
 
-pub fn fit(element: impl Into<Element>) -> SplitElement {
-    SplitElement {
-        element: element.into(),
-        measurement: SplitMeasurement::Fit,
+impl SlideElement for Terminal {
+    fn make_widget(&self, context: &Context) -> WidgetInstance {
+        terminal::TerminalView::new(self.command.clone())
+            .error_colors(context.theme.error.color, context.theme.surface.color)
+            .make_widget()
     }
 }
 
-pub fn expand(element: impl Into<Element>) -> SplitElement {
-    expand_weighted(1, element)
+pub fn terminal(command: impl Into<String>) -> Terminal {
+    Terminal {
+        command: terminal::TerminalCommand::new(Some(command.into())),
+    }
 }
 
-pub fn expand_weighted(weight: u8, element: impl Into<Element>) -> SplitElement {
-    SplitElement {
-        element: element.into(),
-        measurement: SplitMeasurement::Expand { weight },
+pub fn shell() -> Terminal {
+    Terminal {
+        command: terminal::TerminalCommand::new(None),
     }
 }
 
-pub fn stack(elements: impl Elements) -> Element {
-    vsplit(
-        elements
-            .into_elements()
-            .into_iter()
-            .map(fit)
-            .collect::<Vec<_>>(),
-    )
+pub struct Table {
+    header: Vec<Element>,
+    rows: Vec<Vec<Element>>,
+    alignments: Vec<HAlign>,
+    striped: bool,
+    bordered: bool,
 }
 
-pub fn hstack(elements: impl Elements) -> Element {
-    hsplit(
-        elements
-            .into_elements()
-            .into_iter()
-            .map(fit)
-            .collect::<Vec<_>>(),
-    )
-}
+impl Table {
+    pub fn row(mut self, cells: impl Elements) -> Self {
+        self.rows.push(cells.into_elements());
+        self
+    }
 
-pub trait Elements {
-    fn into_elements(self) -> Vec<Element>;
-}
+    pub fn align(mut self, column: usize, align: HAlign) -> Self {
+        if self.alignments.len() <= column {
+            self.alignments.resize(column + 1, HAlign::Left);
+        }
+        self.alignments[column] = align;
+        self
+    }
 
-// impl<T> Elements for T
-// where
-//     T: Into<Element>,
-// {
-//     fn into_elements(self) -> Vec<Element> {
-//         vec![self.into()]
-//     }
-// }
+    pub fn striped(mut self) -> Self {
+        self.striped = true;
+        self
+    }
 
-impl Elements for Vec<Element> {
-    fn into_elements(self) -> Vec<Element> {
+    pub fn bordered(mut self) -> Self {
+        self.bordered = true;
         self
     }
 }
 
-macro_rules! impl_elements_for_tuples {
-    ($($type:ident $field:tt $var:ident),+) => {
-        impl<$($type),+> Elements for ($($type,)+)
-        where
-            $($type: Into<Element>),+
-        {
-            fn into_elements(self) -> Vec<Element> {
-                vec![$(self.$field.into()),+]
-            }
+impl SlideElement for Table {
+    fn make_widget(&self, context: &Context) -> WidgetInstance {
+        let has_header = !self.header.is_empty();
+        let rows = has_header
+            .then_some(&self.header)
+            .into_iter()
+            .chain(&self.rows)
+            .enumerate()
+            .map(|(row, cells)| {
+                cells
+                    .iter()
+                    .enumerate()
+                    .map(|(column, cell)| {
+                        let mut context = context.clone();
+                        context.align =
+                            self.alignments.get(column).copied().unwrap_or(HAlign::Left);
+                        let cell = cell.make_widget(&context);
+                        if has_header && row == 0 {
+                            cell.with(&FontWeight, Weight::BOLD).make_widget()
+                        } else {
+                            cell
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        table::TableView::new(
+            rows,
+            has_header,
+            self.striped,
+            self.bordered,
+            table::TableColors {
+                header: context.theme.surface.highest_container,
+                stripe: context.theme.surface.low_container,
+                border: context.theme.surface.outline_variant,
+            },
+        )
+        .make_widget()
+    }
+
+    fn validate(&self, context: &mut ValidationContext<'_>) {
+        for cell in self.header.iter().chain(self.rows.iter().flatten()) {
+            cell.validate(context);
         }
-    };
+    }
 }
 
-impl_all_tuples!(impl_elements_for_tuples);
+pub fn table(header: impl Elements) -> Table {
+    Table {
+        header: header.into_elements(),
+        rows: Vec::new(),
+        alignments: Vec::new(),
+        striped: false,
+        bordered: false,
+    }
+}
 
-mod code;
+pub struct Canvas {
+    layers: Vec<Layer>,
+}
+
+struct Layer {
+    element: Element,
+    placement: canvas::Placement,
+    z: i32,
+}
+
+impl Canvas {
+    // Positions and sizes are fractions of the 16:9 design frame, centered in
+    // the space the canvas is given.
+    pub fn place(
+        mut self,
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        element: impl Into<Element>,
+    ) -> Self {
+        self.layers.push(Layer {
+            element: element.into(),
+            placement: canvas::Placement {
+                x,
+                y,
+                width,
+                height,
+            },
+            z: 0,
+        });
+        self
+    }
 
-struct Code {
-    lang: String,
-    source: String,
+    // Sets the depth of the most recently placed element. Layers with a
+    // higher depth are drawn on top, and ties are drawn in placement order.
+    pub fn z(mut self, z: i32) -> Self {
+        let layer = self.layers.last_mut();
+        debug_assert!(layer.is_some(), "Canvas::z must follow Canvas::place");
+        if let Some(layer) = layer {
+            layer.z = z;
+        }
+        self
+    }
 }
 
-impl SlideElement for Code {
-    fn make_widget(&self, _context: &Context) -> WidgetInstance {
-        let ts = ThemeSet::load_defaults();
-
-        code::CodeView::new(
-            self.lang.clone(),
-            SyntaxSet::load_defaults_newlines(),
-            ts.themes
-                .get("base16-mocha.dark")
-                .expect("missing theme")
-                .clone(),
-            self.source.clone(),
+impl SlideElement for Canvas {
+    fn make_widget(&self, context: &Context) -> WidgetInstance {
+        let mut layers = self.layers.iter().collect::<Vec<_>>();
+        layers.sort_by_key(|layer| layer.z);
+        canvas::CanvasView::new(
+            layers
+                .into_iter()
+                .map(|layer| (layer.element.make_widget(context), layer.placement))
+                .collect(),
         )
-        .contain()
         .make_widget()
     }
+
+    fn validate(&self, context: &mut ValidationContext<'_>) {
+        for layer in &self.layers {
+            let canvas::Placement {
+                x,
+                y,
+                width,
+                height,
+            } = layer.placement;
+            if width <= 0. || height <= 0. {
+                context.warn("canvas element has no area");
+            } else if x < 0. || y < 0. || x + width > 1. || y + height > 1. {
+                context.warn("canvas element extends past the edge of the canvas");
+            }
+            context.validate_within(&layer.element, width.max(0.), height.max(0.));
+        }
+    }
 }
 
-pub fn code(lang: impl Into<String>, source: impl Into<String>) -> Element {
-    Code {
-        lang: lang.into(),
-        source: source.into(),
+pub fn canvas() -> Canvas {
+    Canvas { layers: Vec::new() }
+}
+
+struct FitText(Element);
+
+impl SlideElement for FitText {
+    fn make_widget(&self, context: &Context) -> WidgetInstance {
+        fit::FitText::new(self.0.make_widget(context)).make_widget()
+    }
+
+    fn validate(&self, context: &mut ValidationContext<'_>) {
+        self.0.validate(context);
     }
-    .into()
 }
 
 struct Group(Element);
@@ -711,17 +2613,57 @@ impl SlideElement for Group {
     fn make_widget(&self, context: &Context) -> WidgetInstance {
         self.0.make_widget(context).contain().make_widget()
     }
+
+    fn validate(&self, context: &mut ValidationContext<'_>) {
+        self.0.validate(context);
+    }
 }
 
 pub fn group(elements: impl Elements) -> Element {
     Group(stack(elements)).into()
 }
 
+struct Placeholder(String);
+
+impl SlideElement for Placeholder {
+    fn make_widget(&self, context: &Context) -> WidgetInstance {
+        let Some(element) = context.placeholders.get(&self.0) else {
+            return Space::clear().make_widget();
+        };
+        // Placeholders inside of a filled placeholder would fill themselves
+        // forever.
+        let empty = HashMap::new();
+        let mut context = context.clone();
+        context.placeholders = &empty;
+        element.make_widget(&context)
+    }
+
+    fn validate(&self, context: &mut ValidationContext<'_>) {
+        let placeholders = context.placeholders;
+        match placeholders.get(&self.0) {
+            Some(element) => {
+                context.filled_placeholders.insert(self.0.clone());
+                element.validate(context);
+            }
+            None => context.warn(format!("placeholder `{}` is not filled", self.0)),
+        }
+    }
+}
+
+pub fn placeholder(name: impl Into<String>) -> Element {
+    Placeholder(name.into()).into()
+}
+
 pub struct SlideIndex;
 
 impl SlideElement for SlideIndex {
     fn make_widget(&self, context: &Context) -> WidgetInstance {
-        Label::new(context.slide_index + 1).make_widget()
+        Label::new(
+            context
+                .slide_index
+                .map_or_else(String::new, |index| (index + 1).to_string()),
+        )
+        .make_widget()
     }
 }
 
@@ -733,6 +2675,49 @@ impl SlideElement for SlideCount {
     }
 }
 
+// Formats the slide number, replacing `{n}` with the slide's number and
+// `{total}` with the number of slides. Unnumbered slides show nothing.
+pub struct SlideNumber(pub String);
+
+impl SlideElement for SlideNumber {
+    fn make_widget(&self, context: &Context) -> WidgetInstance {
+        Label::new(context.slide_index.map_or_else(String::new, |index| {
+            self.0
+                .replace("{n}", &(index + 1).to_string())
+                .replace("{total}", &context.slide_count.to_string())
+        }))
+        .make_widget()
+    }
+}
+
+pub fn slide_number(format: impl Into<String>) -> Element {
+    SlideNumber(format.into()).into()
+}
+
+pub struct DeckTitle;
+
+impl SlideElement for DeckTitle {
+    fn make_widget(&self, context: &Context) -> WidgetInstance {
+        Label::new(context.deck.title.clone()).make_widget()
+    }
+}
+
+pub struct DeckAuthor;
+
+impl SlideElement for DeckAuthor {
+    fn make_widget(&self, context: &Context) -> WidgetInstance {
+        Label::new(context.deck.author.clone()).make_widget()
+    }
+}
+
+pub struct DeckDate;
+
+impl SlideElement for DeckDate {
+    fn make_widget(&self, context: &Context) -> WidgetInstance {
+        Label::new(context.deck.date.clone()).make_widget()
+    }
+}
+
 struct Hr;
 
 impl SlideElement for Hr {
@@ -757,27 +2742,207 @@ pub fn vr() -> Element {
     Hr.into()
 }
 
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ListMarker {
+    Disc,
+    Circle,
+    Square,
+    Custom(String),
+    Decimal,
+    LowerAlpha,
+    UpperAlpha,
+    LowerRoman,
+    UpperRoman,
+}
+
+impl ListMarker {
+    fn for_depth(depth: usize) -> Self {
+        match depth % 3 {
+            0 => ListMarker::Disc,
+            1 => ListMarker::Circle,
+            _ => ListMarker::Square,
+        }
+    }
+
+    fn label(&self, number: usize) -> String {
+        match self {
+            ListMarker::Disc => String::from("\u{2022}"),
+            ListMarker::Circle => String::from("\u{25e6}"),
+            ListMarker::Square => String::from("\u{25aa}"),
+            ListMarker::Custom(marker) => marker.clone(),
+            ListMarker::Decimal => format!("{number}."),
+            ListMarker::LowerAlpha => format!("{}.", alphabetic(number)),
+            ListMarker::UpperAlpha => format!("{}.", alphabetic(number).to_uppercase()),
+            ListMarker::LowerRoman => format!("{}.", roman(number)),
+            ListMarker::UpperRoman => format!("{}.", roman(number).to_uppercase()),
+        }
+    }
+}
+
+fn alphabetic(mut number: usize) -> String {
+    let mut letters = Vec::new();
+    while number > 0 {
+        number -= 1;
+        letters.push(char::from(b'a' + (number % 26) as u8));
+        number /= 26;
+    }
+    letters.iter().rev().collect()
+}
+
+fn roman(mut number: usize) -> String {
+    const NUMERALS: [(usize, &str); 13] = [
+        (1000, "m"),
+        (900, "cm"),
+        (500, "d"),
+        (400, "cd"),
+        (100, "c"),
+        (90, "xc"),
+        (50, "l"),
+        (40, "xl"),
+        (10, "x"),
+        (9, "ix"),
+        (5, "v"),
+        (4, "iv"),
+        (1, "i"),
+    ];
+    if number == 0 {
+        return String::from("0");
+    }
+
+    let mut numeral = String::new();
+    for (value, letters) in NUMERALS {
+        while number >= value {
+            numeral.push_str(letters);
+            number -= value;
+        }
+    }
+    numeral
+}
+
+struct ListItem {
+    // Only a sublist added before any item has no element of its own.
+    element: Option<Element>,
+    sublist: Option<List>,
+}
+
 pub struct List {
-    elements: Vec<Element>,
+    items: Vec<ListItem>,
+    marker: Option<ListMarker>,
+    start: usize,
+}
+
+impl List {
+    pub fn ordered(self) -> Self {
+        self.marker(ListMarker::Decimal)
+    }
+
+    pub fn marker(mut self, marker: ListMarker) -> Self {
+        self.marker = Some(marker);
+        self
+    }
+
+    pub fn start_at(mut self, start: usize) -> Self {
+        self.start = start;
+        self
+    }
+
+    pub fn sublist(mut self, sublist: List) -> Self {
+        if let Some(item) = self.items.last_mut() {
+            item.sublist = Some(sublist);
+        } else {
+            self.items.push(ListItem {
+                element: None,
+                sublist: Some(sublist),
+            });
+        }
+        self
+    }
 }
 
 impl SlideElement for List {
     fn make_widget(&self, context: &Context) -> WidgetInstance {
-        self.elements
+        let mut item_context = context.clone();
+        item_context.align = HAlign::Left;
+        let mut sublist_context = item_context.clone();
+        sublist_context.list_depth += 1;
+
+        let marker = self
+            .marker
+            .clone()
+            .unwrap_or_else(|| ListMarker::for_depth(context.list_depth));
+        let mut number = self.start;
+        let rows = self
+            .items
             .iter()
-            .map(|e| e.make_widget(context))
-            .collect::<WidgetList>()
-            .into_list()
+            .map(|item| {
+                let sublist = item
+                    .sublist
+                    .as_ref()
+                    .map(|sublist| sublist.make_widget(&sublist_context));
+                let Some(element) = &item.element else {
+                    return (
+                        Space::clear().make_widget(),
+                        sublist.unwrap_or_else(|| Space::clear().make_widget()),
+                    );
+                };
+
+                let color = element.color.unwrap_or(context.color);
+                let marker = marker
+                    .label(number)
+                    .with(&TextColor, color.resolve(context))
+                    .align_right()
+                    .align_top()
+                    .make_widget();
+                number += 1;
+                let contents = element.make_widget(&item_context);
+                let contents = match sublist {
+                    Some(sublist) => contents.and(sublist).into_rows().make_widget(),
+                    None => contents,
+                };
+                (marker, contents)
+            })
+            .collect::<GridWidgets<2>>();
+
+        Grid::from_rows(rows)
+            .dimensions([
+                GridDimension::FitContent,
+                GridDimension::Fractional { weight: 1 },
+            ])
             .make_widget()
     }
+
+    fn validate(&self, context: &mut ValidationContext<'_>) {
+        for item in &self.items {
+            match &item.element {
+                Some(element) => element.validate(context),
+                None => context.warn("sublist added before the first list item"),
+            }
+            if let Some(sublist) = &item.sublist {
+                sublist.validate(context);
+            }
+        }
+    }
 }
 
 pub fn list(elements: impl Elements) -> List {
     List {
-        elements: elements.into_elements(),
+        items: elements
+            .into_elements()
+            .into_iter()
+            .map(|element| ListItem {
+                element: Some(element),
+                sublist: None,
+            })
+            .collect(),
+        marker: None,
+        start: 1,
     }
 }
 
+pub fn ordered_list(elements: impl Elements) -> List {
+    list(elements).ordered()
+}
+
 impl From<String> for Element {
     fn from(value: String) -> Self {
         Text(value).into()
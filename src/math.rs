@@ -0,0 +1,1216 @@
+use std::iter::Peekable;
+use std::str::Chars;
+use std::sync::Arc;
+
+use cushy::context::{GraphicsContext, LayoutContext};
+use cushy::figures::units::{Px, UPx};
+use cushy::figures::{FloatConversion, Point, Rect, ScreenScale, Size};
+use cushy::kludgine::shapes::{Shape, StrokeOptions};
+use cushy::kludgine::text::{MeasuredText, Text, TextOrigin};
+use cushy::kludgine::DrawableExt;
+use cushy::styles::components::{TextColor, TextSize};
+use cushy::styles::{Color, FamilyOwned, Style, Weight};
+use cushy::widget::Widget;
+use cushy::ConstraintLimit;
+
+use crate::shapes::polyline;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Class {
+    Ord,
+    Op,
+    Bin,
+    Rel,
+    Open,
+    Close,
+    Punct,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Font {
+    Italic,
+    Upright,
+    Bold,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Atom {
+    text: String,
+    class: Class,
+    font: Font,
+    large: bool,
+    limits: bool,
+}
+
+impl Atom {
+    fn new(text: impl Into<String>, class: Class, font: Font) -> Self {
+        Self {
+            text: text.into(),
+            class,
+            font,
+            large: false,
+            limits: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Accent {
+    Hat,
+    Tilde,
+    Vec,
+    Dot,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum Node {
+    Atom(Atom),
+    Group(Vec<Node>),
+    Scripts {
+        base: Box<Node>,
+        sup: Option<Box<Node>>,
+        sub: Option<Box<Node>>,
+    },
+    Fraction(Box<Node>, Box<Node>),
+    Sqrt {
+        index: Option<Box<Node>>,
+        body: Box<Node>,
+    },
+    Delimited {
+        left: String,
+        right: String,
+        body: Vec<Node>,
+    },
+    Overline(Box<Node>),
+    Accent(Accent, Box<Node>),
+    Space(f32),
+}
+
+impl Node {
+    fn class(&self) -> Option<Class> {
+        match self {
+            Node::Atom(atom) => Some(atom.class),
+            Node::Scripts { base, .. } => base.class(),
+            Node::Space(_) => None,
+            _ => Some(Class::Ord),
+        }
+    }
+}
+
+pub(crate) fn parse(source: &str) -> Result<Arc<Vec<Node>>, String> {
+    Parser {
+        chars: source.chars().peekable(),
+    }
+    .list(End::Eof)
+    .map(Arc::new)
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum End {
+    Eof,
+    Brace,
+    Bracket,
+    Right,
+}
+
+enum Token {
+    Command(String),
+    Char(char),
+    Open,
+    Close,
+    Sup,
+    Sub,
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|ch| ch.is_whitespace()).is_some() {}
+    }
+
+    fn token(&mut self) -> Option<Token> {
+        self.skip_whitespace();
+        Some(match self.chars.next()? {
+            '\\' => {
+                let mut name = String::new();
+                while let Some(ch) = self.chars.next_if(char::is_ascii_alphabetic) {
+                    name.push(ch);
+                }
+                if name.is_empty() {
+                    name.extend(self.chars.next());
+                }
+                Token::Command(name)
+            }
+            '{' => Token::Open,
+            '}' => Token::Close,
+            '^' => Token::Sup,
+            '_' => Token::Sub,
+            ch => Token::Char(ch),
+        })
+    }
+
+    fn list(&mut self, end: End) -> Result<Vec<Node>, String> {
+        let mut nodes = Vec::new();
+        loop {
+            let Some(token) = self.token() else {
+                return match end {
+                    End::Eof => Ok(nodes),
+                    End::Brace => Err(String::from("missing }")),
+                    End::Bracket => Err(String::from("missing ]")),
+                    End::Right => Err(String::from("\\left without \\right")),
+                };
+            };
+            match token {
+                Token::Close if end == End::Brace => return Ok(nodes),
+                Token::Close => return Err(String::from("unexpected }")),
+                Token::Char(']') if end == End::Bracket => return Ok(nodes),
+                Token::Command(name) if name == "right" => {
+                    return if end == End::Right {
+                        Ok(nodes)
+                    } else {
+                        Err(String::from("\\right without \\left"))
+                    };
+                }
+                Token::Sup | Token::Sub => {
+                    let argument = self.argument()?;
+                    attach(&mut nodes, matches!(token, Token::Sup), argument)?;
+                }
+                token => nodes.push(self.node(token)?),
+            }
+        }
+    }
+
+    fn node(&mut self, token: Token) -> Result<Node, String> {
+        match token {
+            Token::Open => Ok(Node::Group(self.list(End::Brace)?)),
+            Token::Command(name) => self.command(&name),
+            Token::Char(ch) => Ok(self.character(ch)),
+            Token::Close | Token::Sup | Token::Sub => Err(String::from("missing argument")),
+        }
+    }
+
+    fn argument(&mut self) -> Result<Node, String> {
+        let token = self
+            .token()
+            .ok_or_else(|| String::from("missing argument"))?;
+        self.node(token)
+    }
+
+    fn raw_group(&mut self) -> Result<String, String> {
+        self.skip_whitespace();
+        if self.chars.next() != Some('{') {
+            return Err(String::from("expected {"));
+        }
+        let mut text = String::new();
+        let mut depth = 0;
+        loop {
+            match self.chars.next() {
+                Some('\\') => text.extend(self.chars.next()),
+                Some('{') => {
+                    depth += 1;
+                    text.push('{');
+                }
+                Some('}') if depth == 0 => return Ok(text),
+                Some('}') => {
+                    depth -= 1;
+                    text.push('}');
+                }
+                Some(ch) => text.push(ch),
+                None => return Err(String::from("missing }")),
+            }
+        }
+    }
+
+    fn delimiter(&mut self) -> Result<String, String> {
+        let delimiter = match self.token() {
+            Some(Token::Char('.')) => "",
+            Some(Token::Char(ch)) if "()[]|/".contains(ch) => return Ok(ch.to_string()),
+            Some(Token::Command(name)) => match name.as_str() {
+                "{" | "lbrace" => "{",
+                "}" | "rbrace" => "}",
+                "|" | "Vert" => "‖",
+                "vert" | "lvert" | "rvert" => "|",
+                "langle" => "⟨",
+                "rangle" => "⟩",
+                "lfloor" => "⌊",
+                "rfloor" => "⌋",
+                "lceil" => "⌈",
+                "rceil" => "⌉",
+                _ => return Err(format!("invalid delimiter \\{name}")),
+            },
+            _ => return Err(String::from("missing delimiter")),
+        };
+        Ok(delimiter.to_string())
+    }
+
+    fn command(&mut self, name: &str) -> Result<Node, String> {
+        let node = match name {
+            "frac" | "dfrac" | "tfrac" => {
+                Node::Fraction(Box::new(self.argument()?), Box::new(self.argument()?))
+            }
+            "sqrt" => {
+                self.skip_whitespace();
+                let index = if self.chars.next_if_eq(&'[').is_some() {
+                    Some(Box::new(Node::Group(self.list(End::Bracket)?)))
+                } else {
+                    None
+                };
+                Node::Sqrt {
+                    index,
+                    body: Box::new(self.argument()?),
+                }
+            }
+            "left" => {
+                let left = self.delimiter()?;
+                let body = self.list(End::Right)?;
+                let right = self.delimiter()?;
+                Node::Delimited { left, right, body }
+            }
+            "text" | "textrm" | "mathrm" | "mbox" => {
+                Node::Atom(Atom::new(self.raw_group()?, Class::Ord, Font::Upright))
+            }
+            "mathbf" | "textbf" => Node::Atom(Atom::new(self.raw_group()?, Class::Ord, Font::Bold)),
+            "mathit" | "textit" => {
+                Node::Atom(Atom::new(self.raw_group()?, Class::Ord, Font::Italic))
+            }
+            "operatorname" => Node::Atom(Atom::new(self.raw_group()?, Class::Op, Font::Upright)),
+            "overline" | "bar" => Node::Overline(Box::new(self.argument()?)),
+            "hat" | "widehat" => Node::Accent(Accent::Hat, Box::new(self.argument()?)),
+            "tilde" | "widetilde" => Node::Accent(Accent::Tilde, Box::new(self.argument()?)),
+            "vec" => Node::Accent(Accent::Vec, Box::new(self.argument()?)),
+            "dot" => Node::Accent(Accent::Dot, Box::new(self.argument()?)),
+            "," | "thinspace" => Node::Space(3. / 18.),
+            ":" | ">" | "medspace" => Node::Space(4. / 18.),
+            ";" | "thickspace" => Node::Space(5. / 18.),
+            " " => Node::Space(1. / 3.),
+            "quad" => Node::Space(1.),
+            "qquad" => Node::Space(2.),
+            "!" => Node::Space(-3. / 18.),
+            "{" | "lbrace" => Node::Atom(Atom::new("{", Class::Open, Font::Upright)),
+            "}" | "rbrace" => Node::Atom(Atom::new("}", Class::Close, Font::Upright)),
+            "%" | "$" | "#" | "&" | "_" => Node::Atom(Atom::new(name, Class::Ord, Font::Upright)),
+            "|" => Node::Atom(Atom::new("‖", Class::Ord, Font::Upright)),
+            "sin" | "cos" | "tan" | "sec" | "csc" | "cot" | "sinh" | "cosh" | "tanh" | "arcsin"
+            | "arccos" | "arctan" | "log" | "ln" | "lg" | "exp" | "deg" | "dim" | "ker" | "arg"
+            | "hom" => Node::Atom(Atom::new(name, Class::Op, Font::Upright)),
+            "lim" | "liminf" | "limsup" | "max" | "min" | "sup" | "inf" | "det" | "gcd" | "Pr" => {
+                Node::Atom(Atom {
+                    limits: true,
+                    ..Atom::new(name, Class::Op, Font::Upright)
+                })
+            }
+            "sum" | "prod" | "coprod" | "bigcup" | "bigcap" | "bigoplus" | "bigotimes" => {
+                Node::Atom(Atom {
+                    large: true,
+                    limits: true,
+                    ..Atom::new(large_operator(name), Class::Op, Font::Upright)
+                })
+            }
+            "int" | "iint" | "iiint" | "oint" => Node::Atom(Atom {
+                large: true,
+                ..Atom::new(large_operator(name), Class::Op, Font::Upright)
+            }),
+            _ => {
+                let (text, class) =
+                    symbol(name).ok_or_else(|| format!("unknown command \\{name}"))?;
+                let font = if class == Class::Ord && text.chars().all(char::is_lowercase) {
+                    Font::Italic
+                } else {
+                    Font::Upright
+                };
+                Node::Atom(Atom::new(text, class, font))
+            }
+        };
+        Ok(node)
+    }
+
+    fn character(&mut self, ch: char) -> Node {
+        if ch.is_ascii_digit() {
+            let mut number = ch.to_string();
+            while let Some(next) = self.chars.next_if(|ch| ch.is_ascii_digit() || *ch == '.') {
+                number.push(next);
+            }
+            return Node::Atom(Atom::new(number, Class::Ord, Font::Upright));
+        } else if ch.is_alphabetic() {
+            return Node::Atom(Atom::new(ch, Class::Ord, Font::Italic));
+        }
+
+        let (text, class) = match ch {
+            '+' => ("+", Class::Bin),
+            '-' => ("−", Class::Bin),
+            '*' => ("∗", Class::Bin),
+            '=' => ("=", Class::Rel),
+            '<' => ("<", Class::Rel),
+            '>' => (">", Class::Rel),
+            ':' => (":", Class::Rel),
+            '(' => ("(", Class::Open),
+            '[' => ("[", Class::Open),
+            ')' => (")", Class::Close),
+            ']' => ("]", Class::Close),
+            '!' => ("!", Class::Close),
+            '?' => ("?", Class::Close),
+            ',' => (",", Class::Punct),
+            ';' => (";", Class::Punct),
+            '\'' => ("′", Class::Ord),
+            '~' => return Node::Space(1. / 3.),
+            _ => return Node::Atom(Atom::new(ch, Class::Ord, Font::Upright)),
+        };
+        Node::Atom(Atom::new(text, class, Font::Upright))
+    }
+}
+
+fn attach(nodes: &mut Vec<Node>, superscript: bool, argument: Node) -> Result<(), String> {
+    let (base, mut sup, mut sub) = match nodes.pop() {
+        Some(Node::Scripts { base, sup, sub }) => (base, sup, sub),
+        base => (
+            Box::new(base.unwrap_or(Node::Group(Vec::new()))),
+            None,
+            None,
+        ),
+    };
+    let (slot, error) = if superscript {
+        (&mut sup, "double superscript")
+    } else {
+        (&mut sub, "double subscript")
+    };
+    if slot.is_some() {
+        return Err(String::from(error));
+    }
+    *slot = Some(Box::new(argument));
+    nodes.push(Node::Scripts { base, sup, sub });
+    Ok(())
+}
+
+fn large_operator(name: &str) -> &'static str {
+    match name {
+        "sum" => "∑",
+        "prod" => "∏",
+        "coprod" => "∐",
+        "bigcup" => "⋃",
+        "bigcap" => "⋂",
+        "bigoplus" => "⨁",
+        "bigotimes" => "⨂",
+        "iint" => "∬",
+        "iiint" => "∭",
+        "oint" => "∮",
+        _ => "∫",
+    }
+}
+
+fn symbol(name: &str) -> Option<(&'static str, Class)> {
+    let symbol = match name {
+        "alpha" => ("α", Class::Ord),
+        "beta" => ("β", Class::Ord),
+        "gamma" => ("γ", Class::Ord),
+        "delta" => ("δ", Class::Ord),
+        "epsilon" => ("ϵ", Class::Ord),
+        "varepsilon" => ("ε", Class::Ord),
+        "zeta" => ("ζ", Class::Ord),
+        "eta" => ("η", Class::Ord),
+        "theta" => ("θ", Class::Ord),
+        "vartheta" => ("ϑ", Class::Ord),
+        "iota" => ("ι", Class::Ord),
+        "kappa" => ("κ", Class::Ord),
+        "lambda" => ("λ", Class::Ord),
+        "mu" => ("μ", Class::Ord),
+        "nu" => ("ν", Class::Ord),
+        "xi" => ("ξ", Class::Ord),
+        "pi" => ("π", Class::Ord),
+        "varpi" => ("ϖ", Class::Ord),
+        "rho" => ("ρ", Class::Ord),
+        "varrho" => ("ϱ", Class::Ord),
+        "sigma" => ("σ", Class::Ord),
+        "varsigma" => ("ς", Class::Ord),
+        "tau" => ("τ", Class::Ord),
+        "upsilon" => ("υ", Class::Ord),
+        "phi" => ("ϕ", Class::Ord),
+        "varphi" => ("φ", Class::Ord),
+        "chi" => ("χ", Class::Ord),
+        "psi" => ("ψ", Class::Ord),
+        "omega" => ("ω", Class::Ord),
+        "Gamma" => ("Γ", Class::Ord),
+        "Delta" => ("Δ", Class::Ord),
+        "Theta" => ("Θ", Class::Ord),
+        "Lambda" => ("Λ", Class::Ord),
+        "Xi" => ("Ξ", Class::Ord),
+        "Pi" => ("Π", Class::Ord),
+        "Sigma" => ("Σ", Class::Ord),
+        "Upsilon" => ("Υ", Class::Ord),
+        "Phi" => ("Φ", Class::Ord),
+        "Psi" => ("Ψ", Class::Ord),
+        "Omega" => ("Ω", Class::Ord),
+        "infty" => ("∞", Class::Ord),
+        "partial" => ("∂", Class::Ord),
+        "nabla" => ("∇", Class::Ord),
+        "forall" => ("∀", Class::Ord),
+        "exists" => ("∃", Class::Ord),
+        "emptyset" | "varnothing" => ("∅", Class::Ord),
+        "hbar" => ("ℏ", Class::Ord),
+        "ell" => ("ℓ", Class::Ord),
+        "prime" => ("′", Class::Ord),
+        "ldots" | "dots" => ("…", Class::Ord),
+        "cdots" => ("⋯", Class::Ord),
+        "vdots" => ("⋮", Class::Ord),
+        "ddots" => ("⋱", Class::Ord),
+        "neg" | "lnot" => ("¬", Class::Ord),
+        "pm" => ("±", Class::Bin),
+        "mp" => ("∓", Class::Bin),
+        "times" => ("×", Class::Bin),
+        "div" => ("÷", Class::Bin),
+        "cdot" => ("⋅", Class::Bin),
+        "ast" => ("∗", Class::Bin),
+        "circ" => ("∘", Class::Bin),
+        "bullet" => ("∙", Class::Bin),
+        "oplus" => ("⊕", Class::Bin),
+        "otimes" => ("⊗", Class::Bin),
+        "cup" => ("∪", Class::Bin),
+        "cap" => ("∩", Class::Bin),
+        "setminus" => ("∖", Class::Bin),
+        "wedge" | "land" => ("∧", Class::Bin),
+        "vee" | "lor" => ("∨", Class::Bin),
+        "leq" | "le" => ("≤", Class::Rel),
+        "geq" | "ge" => ("≥", Class::Rel),
+        "neq" | "ne" => ("≠", Class::Rel),
+        "approx" => ("≈", Class::Rel),
+        "equiv" => ("≡", Class::Rel),
+        "sim" => ("∼", Class::Rel),
+        "simeq" => ("≃", Class::Rel),
+        "cong" => ("≅", Class::Rel),
+        "propto" => ("∝", Class::Rel),
+        "ll" => ("≪", Class::Rel),
+        "gg" => ("≫", Class::Rel),
+        "in" => ("∈", Class::Rel),
+        "notin" => ("∉", Class::Rel),
+        "ni" => ("∋", Class::Rel),
+        "subset" => ("⊂", Class::Rel),
+        "supset" => ("⊃", Class::Rel),
+        "subseteq" => ("⊆", Class::Rel),
+        "supseteq" => ("⊇", Class::Rel),
+        "mid" => ("∣", Class::Rel),
+        "parallel" => ("∥", Class::Rel),
+        "perp" => ("⊥", Class::Rel),
+        "to" | "rightarrow" => ("→", Class::Rel),
+        "gets" | "leftarrow" => ("←", Class::Rel),
+        "leftrightarrow" => ("↔", Class::Rel),
+        "Rightarrow" | "implies" => ("⇒", Class::Rel),
+        "Leftarrow" => ("⇐", Class::Rel),
+        "Leftrightarrow" | "iff" => ("⇔", Class::Rel),
+        "mapsto" => ("↦", Class::Rel),
+        "langle" => ("⟨", Class::Open),
+        "rangle" => ("⟩", Class::Close),
+        "lfloor" => ("⌊", Class::Open),
+        "rfloor" => ("⌋", Class::Close),
+        "lceil" => ("⌈", Class::Open),
+        "rceil" => ("⌉", Class::Close),
+        _ => return None,
+    };
+    Some(symbol)
+}
+
+#[derive(Debug)]
+enum Item {
+    Glyphs(MeasuredText<Px>, f32, f32),
+    Rule {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+    },
+    Stroke(Vec<(f32, f32)>, f32),
+}
+
+impl Item {
+    fn translate(mut self, dx: f32, dy: f32) -> Self {
+        match &mut self {
+            Item::Glyphs(_, x, y) | Item::Rule { x, y, .. } => {
+                *x += dx;
+                *y += dy;
+            }
+            Item::Stroke(points, _) => {
+                for (x, y) in points {
+                    *x += dx;
+                    *y += dy;
+                }
+            }
+        }
+        self
+    }
+}
+
+// Coordinates are relative to the left end of the baseline, with y growing
+// downwards like the rest of the drawing code.
+#[derive(Debug, Default)]
+struct MathBox {
+    width: f32,
+    ascent: f32,
+    descent: f32,
+    items: Vec<Item>,
+}
+
+impl MathBox {
+    fn place(&mut self, other: MathBox, x: f32, y: f32) {
+        self.width = self.width.max(x + other.width);
+        self.ascent = self.ascent.max(other.ascent - y);
+        self.descent = self.descent.max(other.descent + y);
+        self.items
+            .extend(other.items.into_iter().map(|item| item.translate(x, y)));
+    }
+
+    fn centered_in(self, width: f32) -> MathBox {
+        let mut centered = MathBox::default();
+        let x = (width - self.width) / 2.;
+        centered.place(self, x, 0.);
+        centered.width = width;
+        centered
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct MathStyle {
+    level: u8,
+    display: bool,
+}
+
+impl MathStyle {
+    fn scale(self) -> f32 {
+        match self.level {
+            0 => 1.,
+            1 => 0.7,
+            _ => 0.5,
+        }
+    }
+
+    fn script(self) -> Self {
+        Self {
+            level: (self.level + 1).min(2),
+            display: false,
+        }
+    }
+
+    fn fraction(self) -> Self {
+        if self.display {
+            Self {
+                level: self.level,
+                display: false,
+            }
+        } else {
+            self.script()
+        }
+    }
+}
+
+fn classes(nodes: &[Node]) -> Vec<Option<Class>> {
+    let mut classes = nodes.iter().map(Node::class).collect::<Vec<_>>();
+    let mut previous = None;
+    for index in 0..classes.len() {
+        let Some(class) = classes[index] else {
+            continue;
+        };
+        // A binary operator without operands on both sides, like a leading
+        // minus sign, is typeset as an ordinary symbol instead.
+        if class == Class::Bin {
+            let next = classes[index + 1..].iter().flatten().next().copied();
+            if matches!(
+                previous,
+                None | Some(Class::Bin | Class::Op | Class::Rel | Class::Open | Class::Punct)
+            ) || matches!(next, None | Some(Class::Rel | Class::Close | Class::Punct))
+            {
+                classes[index] = Some(Class::Ord);
+            }
+        }
+        previous = classes[index];
+    }
+    classes
+}
+
+fn spacing(left: Class, right: Class, style: MathStyle) -> f32 {
+    let mu = match (left, right) {
+        (Class::Open, _) | (_, Class::Close | Class::Punct) | (Class::Rel, Class::Rel) => 0.,
+        (Class::Rel, _) | (_, Class::Rel) => 5.,
+        (Class::Bin, _) | (_, Class::Bin) => 4.,
+        (Class::Punct, _)
+        | (Class::Op, Class::Ord | Class::Op)
+        | (Class::Ord | Class::Close, Class::Op) => 3.,
+        _ => 0.,
+    };
+    // Scripts only keep the thin spaces, just like TeX.
+    if style.level > 0 && mu > 3. {
+        0.
+    } else {
+        mu / 18.
+    }
+}
+
+struct Typesetter {
+    color: Color,
+    size: f32,
+}
+
+impl Typesetter {
+    fn em(&self, style: MathStyle) -> f32 {
+        self.size * style.scale()
+    }
+
+    fn rule(&self, style: MathStyle) -> f32 {
+        (self.em(style) * 0.05).max(1.)
+    }
+
+    fn axis(&self, style: MathStyle) -> f32 {
+        self.em(style) * 0.25
+    }
+
+    fn glyphs(
+        &self,
+        context: &mut LayoutContext<'_, '_, '_, '_>,
+        text: &str,
+        font: Font,
+        style: MathStyle,
+        scale: f32,
+    ) -> MathBox {
+        let size = self.em(style) * scale;
+        context.apply_current_font_settings();
+        context.gfx.set_font_family(FamilyOwned::Serif);
+        context.gfx.set_font_size(Px::from(size));
+        context.gfx.set_line_height(Px::from(size * 1.2));
+        match font {
+            Font::Italic => context.gfx.set_font_style(Style::Italic),
+            Font::Bold => context.gfx.set_font_weight(Weight::BOLD),
+            Font::Upright => {}
+        }
+        let measured = context.gfx.measure_text(Text::new(text, self.color));
+        MathBox {
+            width: measured.size.width.into_float(),
+            ascent: measured.ascent.into_float(),
+            descent: measured.descent.into_float().abs(),
+            items: vec![Item::Glyphs(measured, 0., 0.)],
+        }
+    }
+
+    // Font metrics describe the whole line rather than the ink of the glyphs,
+    // so vertical placement is measured relative to a plain glyph's metrics.
+    fn metrics(&self, context: &mut LayoutContext<'_, '_, '_, '_>, style: MathStyle) -> (f32, f32) {
+        let plain = self.glyphs(context, "x", Font::Italic, style, 1.);
+        (plain.ascent, plain.descent)
+    }
+
+    fn centered(
+        &self,
+        context: &mut LayoutContext<'_, '_, '_, '_>,
+        text: &str,
+        style: MathStyle,
+        scale: f32,
+    ) -> MathBox {
+        let mut centered = MathBox::default();
+        if !text.is_empty() {
+            let glyphs = self.glyphs(context, text, Font::Upright, style, scale);
+            let shift = (glyphs.ascent - glyphs.descent) / 2. - self.axis(style);
+            centered.place(glyphs, 0., shift);
+        }
+        centered
+    }
+
+    fn list(
+        &self,
+        context: &mut LayoutContext<'_, '_, '_, '_>,
+        nodes: &[Node],
+        style: MathStyle,
+    ) -> MathBox {
+        let em = self.em(style);
+        let mut list = MathBox::default();
+        let mut previous = None;
+        for (node, class) in nodes.iter().zip(classes(nodes)) {
+            if let Node::Space(amount) = node {
+                list.width += amount * em;
+                continue;
+            }
+            if let (Some(previous), Some(class)) = (previous, class) {
+                list.width += spacing(previous, class, style) * em;
+            }
+            let laid_out = self.node(context, node, style);
+            let x = list.width;
+            list.place(laid_out, x, 0.);
+            previous = class;
+        }
+        list
+    }
+
+    fn node(
+        &self,
+        context: &mut LayoutContext<'_, '_, '_, '_>,
+        node: &Node,
+        style: MathStyle,
+    ) -> MathBox {
+        match node {
+            Node::Atom(atom) if atom.large && style.display => {
+                self.centered(context, &atom.text, style, 1.5)
+            }
+            Node::Atom(atom) => self.glyphs(context, &atom.text, atom.font, style, 1.),
+            Node::Group(nodes) => self.list(context, nodes, style),
+            Node::Scripts { base, sup, sub } => {
+                self.scripts(context, base, sup.as_deref(), sub.as_deref(), style)
+            }
+            Node::Fraction(numerator, denominator) => {
+                self.fraction(context, numerator, denominator, style)
+            }
+            Node::Sqrt { index, body } => self.sqrt(context, index.as_deref(), body, style),
+            Node::Delimited { left, right, body } => {
+                self.delimited(context, left, right, body, style)
+            }
+            Node::Overline(body) => {
+                let body = self.node(context, body, style);
+                let top = self.ink_top(context, &body, style);
+                let rule = self.rule(style);
+                let width = body.width;
+                let mut overlined = MathBox::default();
+                overlined.place(body, 0., 0.);
+                overlined.items.push(Item::Rule {
+                    x: 0.,
+                    y: -top - rule * 2.,
+                    width,
+                    height: rule,
+                });
+                overlined.ascent = overlined.ascent.max(top + rule * 3.);
+                overlined
+            }
+            Node::Accent(accent, body) => self.accent(context, *accent, body, style),
+            Node::Space(amount) => MathBox {
+                width: amount * self.em(style),
+                ..MathBox::default()
+            },
+        }
+    }
+
+    fn ink_top(
+        &self,
+        context: &mut LayoutContext<'_, '_, '_, '_>,
+        body: &MathBox,
+        style: MathStyle,
+    ) -> f32 {
+        let (ascent, _) = self.metrics(context, style);
+        (body.ascent - ascent).max(0.) + self.em(style) * 0.72
+    }
+
+    fn accent(
+        &self,
+        context: &mut LayoutContext<'_, '_, '_, '_>,
+        accent: Accent,
+        body: &Node,
+        style: MathStyle,
+    ) -> MathBox {
+        let body = self.node(context, body, style);
+        let em = self.em(style);
+        let rule = self.rule(style);
+        let bottom = -self.ink_top(context, &body, style) - rule * 2.;
+        let top = bottom - em * 0.15;
+        let center = body.width / 2.;
+        let half = (body.width / 2.).clamp(em * 0.2, em * 0.5);
+
+        let mut accented = MathBox::default();
+        accented.place(body, 0., 0.);
+        match accent {
+            Accent::Hat => accented.items.push(Item::Stroke(
+                vec![
+                    (center - half, bottom),
+                    (center, top),
+                    (center + half, bottom),
+                ],
+                rule,
+            )),
+            Accent::Tilde => accented.items.push(Item::Stroke(
+                vec![
+                    (center - half, bottom),
+                    (center - half / 3., top),
+                    (center + half / 3., bottom),
+                    (center + half, top),
+                ],
+                rule,
+            )),
+            Accent::Vec => {
+                let middle = (top + bottom) / 2.;
+                let head = em * 0.1;
+                accented.items.push(Item::Stroke(
+                    vec![(center - half, middle), (center + half, middle)],
+                    rule,
+                ));
+                accented.items.push(Item::Stroke(
+                    vec![
+                        (center + half - head, middle - head),
+                        (center + half, middle),
+                        (center + half - head, middle + head),
+                    ],
+                    rule,
+                ));
+            }
+            Accent::Dot => {
+                let size = rule * 2.5;
+                accented.items.push(Item::Rule {
+                    x: center - size / 2.,
+                    y: bottom - size,
+                    width: size,
+                    height: size,
+                });
+            }
+        }
+        accented.ascent = accented.ascent.max(-top + rule);
+        accented
+    }
+
+    fn scripts(
+        &self,
+        context: &mut LayoutContext<'_, '_, '_, '_>,
+        base_node: &Node,
+        sup: Option<&Node>,
+        sub: Option<&Node>,
+        style: MathStyle,
+    ) -> MathBox {
+        let base = self.node(context, base_node, style);
+        let script = style.script();
+        let sup = sup.map(|sup| self.node(context, sup, script));
+        let sub = sub.map(|sub| self.node(context, sub, script));
+        let em = self.em(style);
+        let mut scripted = MathBox::default();
+
+        let limits = matches!(base_node, Node::Atom(atom) if atom.limits) && style.display;
+        if limits {
+            let width = sup
+                .iter()
+                .chain(&sub)
+                .fold(base.width, |width, script| width.max(script.width));
+            let (ascent, descent) = (base.ascent, base.descent);
+            scripted.place(base.centered_in(width), 0., 0.);
+            if let Some(sup) = sup {
+                let y = -(ascent + sup.descent);
+                scripted.place(sup.centered_in(width), 0., y);
+            }
+            if let Some(sub) = sub {
+                let y = descent + sub.ascent;
+                scripted.place(sub.centered_in(width), 0., y);
+            }
+            return scripted;
+        }
+
+        let (ascent, descent) = self.metrics(context, style);
+        let up = (base.ascent - ascent).max(0.) + em * if style.display { 0.45 } else { 0.4 };
+        let mut down = (base.descent - descent).max(0.) + em * 0.2;
+        if sup.is_some() && sub.is_some() && up + down < em * 0.9 {
+            down = em * 0.9 - up;
+        }
+        let x = base.width;
+        scripted.place(base, 0., 0.);
+        if let Some(sup) = sup {
+            scripted.place(sup, x, -up);
+        }
+        if let Some(sub) = sub {
+            scripted.place(sub, x, down);
+        }
+        scripted.width += em * 0.05;
+        scripted
+    }
+
+    fn fraction(
+        &self,
+        context: &mut LayoutContext<'_, '_, '_, '_>,
+        numerator: &Node,
+        denominator: &Node,
+        style: MathStyle,
+    ) -> MathBox {
+        let inner = style.fraction();
+        let numerator = self.node(context, numerator, inner);
+        let denominator = self.node(context, denominator, inner);
+        let (ascent, descent) = self.metrics(context, inner);
+        let em = self.em(style);
+        let inner_em = self.em(inner);
+        let rule = self.rule(style);
+        let axis = self.axis(style);
+        let gap = em * if style.display { 0.15 } else { 0.1 };
+        let padding = em * 0.12;
+
+        let numerator_y =
+            -(axis + rule / 2. + gap + (numerator.descent - descent).max(0.) + inner_em * 0.05);
+        let denominator_y =
+            -axis + rule / 2. + gap + (denominator.ascent - ascent).max(0.) + inner_em * 0.72;
+        let width = numerator.width.max(denominator.width) + padding * 2.;
+
+        let mut fraction = MathBox::default();
+        fraction.place(numerator.centered_in(width), 0., numerator_y);
+        fraction.place(denominator.centered_in(width), 0., denominator_y);
+        fraction.items.push(Item::Rule {
+            x: padding / 2.,
+            y: -axis - rule / 2.,
+            width: width - padding,
+            height: rule,
+        });
+        fraction
+    }
+
+    fn sqrt(
+        &self,
+        context: &mut LayoutContext<'_, '_, '_, '_>,
+        index: Option<&Node>,
+        body: &Node,
+        style: MathStyle,
+    ) -> MathBox {
+        let body = self.node(context, body, style);
+        let (ascent, descent) = self.metrics(context, style);
+        let em = self.em(style);
+        let rule = self.rule(style);
+        let gap = em * if style.display { 0.15 } else { 0.1 };
+
+        let top = -((body.ascent - ascent).max(0.) + em * 0.72 + gap);
+        let bottom = (body.descent - descent).max(0.) + em * 0.2;
+        let height = bottom - top;
+        let mid = bottom - height * 0.4;
+        let sign_width = em * 0.6;
+
+        let mut root = MathBox::default();
+        let mut x = 0.;
+        if let Some(index) = index {
+            let index = self.node(
+                context,
+                index,
+                MathStyle {
+                    level: 2,
+                    display: false,
+                },
+            );
+            x = (index.width - sign_width * 0.4).max(0.);
+            let index_x = x + sign_width * 0.4 - index.width;
+            let index_y = mid - em * 0.1 - index.descent;
+            root.place(index, index_x, index_y);
+        }
+
+        let body_width = body.width;
+        let end = x + sign_width + body_width + em * 0.1;
+        root.items.push(Item::Stroke(
+            vec![
+                (x, mid + em * 0.05),
+                (x + sign_width * 0.15, mid),
+                (x + sign_width * 0.45, bottom),
+                (x + sign_width, top),
+                (end, top),
+            ],
+            rule,
+        ));
+        root.place(body, x + sign_width + em * 0.05, 0.);
+        root.width = root.width.max(end);
+        root.ascent = root.ascent.max(-top + rule);
+        root.descent = root.descent.max(bottom + rule);
+        root
+    }
+
+    fn delimited(
+        &self,
+        context: &mut LayoutContext<'_, '_, '_, '_>,
+        left: &str,
+        right: &str,
+        body: &[Node],
+        style: MathStyle,
+    ) -> MathBox {
+        let body = self.list(context, body, style);
+        let (ascent, descent) = self.metrics(context, style);
+        let axis = self.axis(style);
+        // Delimiters grow to cover the body symmetrically around the axis.
+        let extent = (body.ascent - axis).max(body.descent + axis);
+        let normal = (ascent - axis).max(descent + axis);
+        let scale = (extent / normal).max(1.);
+
+        let left = self.centered(context, left, style, scale);
+        let right = self.centered(context, right, style, scale);
+        let mut delimited = MathBox::default();
+        let body_x = left.width;
+        let right_x = body_x + body.width;
+        delimited.place(left, 0., 0.);
+        delimited.place(body, body_x, 0.);
+        delimited.place(right, right_x, 0.);
+        delimited
+    }
+
+    fn layout(
+        &self,
+        context: &mut LayoutContext<'_, '_, '_, '_>,
+        nodes: &[Node],
+        display: bool,
+    ) -> MathBox {
+        self.list(context, nodes, MathStyle { level: 0, display })
+    }
+}
+
+#[derive(Debug)]
+pub struct MathView {
+    nodes: Arc<Vec<Node>>,
+    display: bool,
+    cached: Option<(f32, Color, f32)>,
+    math: MathBox,
+}
+
+impl MathView {
+    pub fn new(nodes: Arc<Vec<Node>>, display: bool) -> Self {
+        Self {
+            nodes,
+            display,
+            cached: None,
+            math: MathBox::default(),
+        }
+    }
+}
+
+impl Widget for MathView {
+    fn redraw(&mut self, context: &mut GraphicsContext<'_, '_, '_, '_>) {
+        let Some((_, color, _)) = self.cached else {
+            return;
+        };
+        let baseline = self.math.ascent;
+        let point = |x: f32, y: f32| Point::new(Px::from(x), Px::from(baseline + y));
+        for item in &self.math.items {
+            match item {
+                Item::Glyphs(measured, x, y) => context.gfx.draw_measured_text(
+                    measured.translate_by(point(*x, *y)),
+                    TextOrigin::FirstBaseline,
+                ),
+                Item::Rule {
+                    x,
+                    y,
+                    width,
+                    height,
+                } => context.gfx.draw_shape(&Shape::filled_rect(
+                    Rect::new(
+                        point(*x, *y),
+                        Size::new(Px::from(*width), Px::from(*height).max(Px::new(1))),
+                    ),
+                    color,
+                )),
+                Item::Stroke(points, thickness) => {
+                    let points = points
+                        .iter()
+                        .map(|&(x, y)| (x, baseline + y))
+                        .collect::<Vec<_>>();
+                    if let Some(path) = polyline(&points) {
+                        context.gfx.draw_shape(
+                            &path.stroke(
+                                StrokeOptions::px_wide(Px::from(*thickness)).colored(color),
+                            ),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    fn layout(
+        &mut self,
+        available_space: Size<ConstraintLimit>,
+        context: &mut LayoutContext<'_, '_, '_, '_>,
+    ) -> Size<UPx> {
+        let text_size = context
+            .get(&TextSize)
+            .into_px(context.gfx.scale())
+            .into_float();
+        let color = context.get(&TextColor);
+        let available = available_space.width.max().into_float();
+        let key = (text_size, color, available);
+        if self.cached != Some(key) {
+            let mut math = Typesetter {
+                color,
+                size: text_size,
+            }
+            .layout(context, &self.nodes, self.display);
+            // Formulas can't wrap, so one that is too wide is scaled down.
+            if math.width > available && math.width > 0. {
+                math = Typesetter {
+                    color,
+                    size: text_size * available / math.width,
+                }
+                .layout(context, &self.nodes, self.display);
+            }
+            self.math = math;
+            self.cached = Some(key);
+        }
+
+        Size::new(
+            UPx::from(self.math.width.ceil()),
+            UPx::from((self.math.ascent + self.math.descent).ceil()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, Node};
+
+    fn atom(node: &Node) -> &str {
+        match node {
+            Node::Atom(atom) => &atom.text,
+            Node::Group(nodes) if nodes.len() == 1 => atom(&nodes[0]),
+            other => panic!("expected an atom, found {other:?}"),
+        }
+    }
+
+    fn error(source: &str) -> String {
+        parse(source).expect_err("parsing should fail")
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(error("{a"), "missing }");
+        assert_eq!(error("a}"), "unexpected }");
+        assert_eq!(error("\\sqrt[3 x"), "missing ]");
+        assert_eq!(error("\\foo"), "unknown command \\foo");
+        assert_eq!(error("x^2^3"), "double superscript");
+        assert_eq!(error("x_1_2"), "double subscript");
+        assert_eq!(error("\\frac{a}"), "missing argument");
+        assert_eq!(error("\\left( x"), "\\left without \\right");
+        assert_eq!(error("x \\right)"), "\\right without \\left");
+        assert_eq!(error("\\left\\foo x \\right)"), "invalid delimiter \\foo");
+    }
+
+    #[test]
+    fn fractions() {
+        let nodes = parse("\\frac{a}{b + 1}").unwrap();
+        let [Node::Fraction(numerator, denominator)] = nodes.as_slice() else {
+            panic!("expected a fraction, found {nodes:?}");
+        };
+        assert_eq!(atom(numerator), "a");
+        let Node::Group(denominator) = &**denominator else {
+            panic!("expected a group, found {denominator:?}");
+        };
+        let texts = denominator.iter().map(atom).collect::<Vec<_>>();
+        assert_eq!(texts, ["b", "+", "1"]);
+    }
+
+    #[test]
+    fn roots() {
+        let nodes = parse("\\sqrt[3]{x} \\sqrt y").unwrap();
+        let [Node::Sqrt {
+            index: Some(index),
+            body,
+        }, Node::Sqrt {
+            index: None,
+            body: second,
+        }] = nodes.as_slice()
+        else {
+            panic!("expected two roots, found {nodes:?}");
+        };
+        assert_eq!(atom(index), "3");
+        assert_eq!(atom(body), "x");
+        assert_eq!(atom(second), "y");
+    }
+
+    #[test]
+    fn delimiters() {
+        let nodes = parse("\\left( x \\right\\rangle \\left. y \\right|").unwrap();
+        let [Node::Delimited { left, right, body }, Node::Delimited {
+            left: empty,
+            right: bar,
+            ..
+        }] = nodes.as_slice()
+        else {
+            panic!("expected two delimited groups, found {nodes:?}");
+        };
+        assert_eq!((left.as_str(), right.as_str()), ("(", "⟩"));
+        assert_eq!(body.iter().map(atom).collect::<Vec<_>>(), ["x"]);
+        assert_eq!((empty.as_str(), bar.as_str()), ("", "|"));
+    }
+}
@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+use std::mem;
+use std::time::Duration;
+
+use cushy::animation::easings::EaseInOutQuadradic;
+use cushy::animation::{AnimationHandle, AnimationTarget, Spawn, ZeroToOne};
+use cushy::context::GraphicsContext;
+use cushy::figures::units::Px;
+use cushy::figures::{FloatConversion, Point, Rect, Zero};
+use cushy::kludgine::text::{MeasuredText, TextOrigin};
+use cushy::kludgine::DrawableExt;
+use cushy::value::{Destination, Dynamic, Source};
+use similar::{capture_diff_slices, Algorithm, DiffTag};
+
+use crate::code::Span;
+
+#[derive(Debug, Default)]
+pub(crate) struct CodeMorphs {
+    previous: HashMap<String, Vec<Token>>,
+    current: HashMap<String, Vec<Token>>,
+    // Where each code block's lines were last drawn, in window coordinates,
+    // so that figures can point at them.
+    lines: HashMap<String, Vec<Rect<Px>>>,
+}
+
+impl CodeMorphs {
+    pub(crate) fn advance(&mut self) {
+        self.previous = mem::take(&mut self.current);
+        self.lines.clear();
+    }
+
+    // Lines are numbered from 1, starting with the first line shown.
+    pub(crate) fn line(&self, id: &str, line: usize) -> Option<Rect<Px>> {
+        self.lines.get(id)?.get(line.checked_sub(1)?).copied()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    text: String,
+    origin: Point<Px>,
+    measured: MeasuredText<Px>,
+}
+
+#[derive(Debug)]
+struct Transition {
+    measured: MeasuredText<Px>,
+    from: Point<Px>,
+    to: Point<Px>,
+    from_opacity: f32,
+    to_opacity: f32,
+}
+
+#[derive(Debug)]
+pub(crate) struct Morph {
+    id: String,
+    morphs: Dynamic<CodeMorphs>,
+    from: Option<Vec<Token>>,
+    transitions: Vec<Transition>,
+    progress: Dynamic<ZeroToOne>,
+    _animation: AnimationHandle,
+}
+
+impl Morph {
+    pub fn new(id: impl Into<String>, morphs: Dynamic<CodeMorphs>) -> Self {
+        let id = id.into();
+        let from = morphs.map_ref(|morphs| morphs.previous.get(&id).cloned());
+        Self {
+            id,
+            morphs,
+            from,
+            transitions: Vec::new(),
+            progress: Dynamic::new(ZeroToOne::ONE),
+            _animation: AnimationHandle::default(),
+        }
+    }
+
+    pub fn publish(&mut self, lines: &[Vec<Span>], line_height: Px) {
+        let mut tokens = Vec::new();
+        let mut y = Px::ZERO;
+        for line in lines {
+            let mut x = Px::ZERO;
+            for span in line {
+                if !span.text.trim().is_empty() {
+                    tokens.push(Token {
+                        text: span.text.trim().to_string(),
+                        origin: Point::new(x, y),
+                        measured: span.measured.clone(),
+                    });
+                }
+                x += span.measured.size.width;
+            }
+            y += line_height;
+        }
+
+        // Only the first measurement after the slide appears animates. Later
+        // measurements, such as after the window is resized, just publish
+        // the new positions for the next slide to morph from.
+        if let Some(from) = self.from.take() {
+            self.transitions = transitions(&from, &tokens);
+            self.progress.set(ZeroToOne::ZERO);
+            self._animation = self
+                .progress
+                .transition_to(ZeroToOne::ONE)
+                .over(Duration::from_millis(600))
+                .with_easing(EaseInOutQuadradic)
+                .spawn();
+        }
+
+        let id = self.id.clone();
+        self.morphs
+            .map_mut(|mut morphs| morphs.current.insert(id, tokens));
+    }
+
+    pub fn place_lines(&self, lines: Vec<Rect<Px>>) {
+        // Only notify when something moved, since figures redraw whenever
+        // the lines change.
+        let changed = self
+            .morphs
+            .map_ref(|morphs| morphs.lines.get(&self.id) != Some(&lines));
+        if changed {
+            let id = self.id.clone();
+            self.morphs
+                .map_mut(|mut morphs| morphs.lines.insert(id, lines));
+        }
+    }
+
+    pub fn draw(&self, offset: Point<Px>, context: &mut GraphicsContext<'_, '_, '_, '_>) -> bool {
+        context.redraw_when_changed(&self.progress);
+        let progress = self.progress.get().into_f32();
+        if progress >= 1. {
+            return false;
+        }
+
+        for transition in &self.transitions {
+            let origin = Point::new(
+                lerp(transition.from.x, transition.to.x, progress),
+                lerp(transition.from.y, transition.to.y, progress),
+            ) + offset;
+            let opacity = transition.from_opacity
+                + (transition.to_opacity - transition.from_opacity) * progress;
+            context.gfx.draw_measured_text(
+                transition.measured.translate_by(origin).opacity(opacity),
+                TextOrigin::TopLeft,
+            );
+        }
+        true
+    }
+}
+
+fn lerp(from: Px, to: Px, progress: f32) -> Px {
+    Px::from(from.into_float() + (to - from).into_float() * progress)
+}
+
+fn transitions(from: &[Token], to: &[Token]) -> Vec<Transition> {
+    let from_text = from.iter().map(|token| &token.text).collect::<Vec<_>>();
+    let to_text = to.iter().map(|token| &token.text).collect::<Vec<_>>();
+    let mut matched_from = vec![false; from.len()];
+    let mut matched_to = vec![None; to.len()];
+
+    for op in capture_diff_slices(Algorithm::Myers, &from_text, &to_text) {
+        let (tag, from_range, to_range) = op.as_tag_tuple();
+        if tag == DiffTag::Equal {
+            for (from_index, to_index) in from_range.zip(to_range) {
+                matched_from[from_index] = true;
+                matched_to[to_index] = Some(from_index);
+            }
+        }
+    }
+
+    // Tokens that moved out of order, such as a reordered line, aren't part
+    // of the common subsequence. Pair them up with any leftover token with
+    // the same text so they still move rather than fade.
+    for (to_index, token) in to.iter().enumerate() {
+        if matched_to[to_index].is_some() {
+            continue;
+        }
+        if let Some(from_index) =
+            (0..from.len()).find(|&index| !matched_from[index] && from[index].text == token.text)
+        {
+            matched_from[from_index] = true;
+            matched_to[to_index] = Some(from_index);
+        }
+    }
+
+    let mut transitions = Vec::with_capacity(from.len() + to.len());
+    for (token, matched) in to.iter().zip(matched_to) {
+        transitions.push(match matched {
+            Some(from_index) => Transition {
+                measured: token.measured.clone(),
+                from: from[from_index].origin,
+                to: token.origin,
+                from_opacity: 1.,
+                to_opacity: 1.,
+            },
+            None => Transition {
+                measured: token.measured.clone(),
+                from: token.origin,
+                to: token.origin,
+                from_opacity: 0.,
+                to_opacity: 1.,
+            },
+        });
+    }
+    for (token, _) in from
+        .iter()
+        .zip(matched_from)
+        .filter(|(_, matched)| !matched)
+    {
+        transitions.push(Transition {
+            measured: token.measured.clone(),
+            from: token.origin,
+            to: token.origin,
+            from_opacity: 1.,
+            to_opacity: 0.,
+        });
+    }
+    transitions
+}
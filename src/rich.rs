@@ -0,0 +1,780 @@
+use std::io;
+use std::process::Command;
+use std::thread;
+
+use cushy::context::{EventContext, GraphicsContext, LayoutContext};
+use cushy::figures::units::{Px, UPx};
+use cushy::figures::{
+    FloatConversion, IntoSigned, IntoUnsigned, Point, Rect, ScreenScale, Size, Zero,
+};
+use cushy::kludgine::app::winit::event::MouseButton;
+use cushy::kludgine::shapes::Shape;
+use cushy::kludgine::text::{MeasuredText, Text, TextOrigin};
+use cushy::kludgine::DrawableExt;
+use cushy::styles::components::TextSize;
+use cushy::styles::{Color, FamilyOwned, Style, Weight};
+use cushy::value::{Destination, Dynamic, Source};
+use cushy::widget::{EventHandling, Widget, HANDLED, IGNORED};
+use cushy::window::DeviceId;
+use cushy::ConstraintLimit;
+
+use crate::{ElementColor, HAlign};
+
+#[derive(Debug, Clone, Default)]
+pub struct RichText {
+    runs: Vec<Run>,
+}
+
+impl RichText {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Supports `**bold**`, `*italic*`, `~~strike~~`, `` `code` `` and
+    // `[label](url)`. A backslash escapes the character that follows it.
+    pub fn markup(mut self, markup: &str) -> Self {
+        parse(markup, RunStyle::default(), &mut self.runs);
+        self
+    }
+
+    pub fn text(self, text: impl Into<String>) -> Self {
+        self.with_run(text, RunStyle::default())
+    }
+
+    pub fn bold(self, text: impl Into<String>) -> Self {
+        self.with_run(
+            text,
+            RunStyle {
+                bold: true,
+                ..RunStyle::default()
+            },
+        )
+    }
+
+    pub fn italic(self, text: impl Into<String>) -> Self {
+        self.with_run(
+            text,
+            RunStyle {
+                italic: true,
+                ..RunStyle::default()
+            },
+        )
+    }
+
+    pub fn code(self, text: impl Into<String>) -> Self {
+        self.with_run(
+            text,
+            RunStyle {
+                code: true,
+                ..RunStyle::default()
+            },
+        )
+    }
+
+    pub fn strike(self, text: impl Into<String>) -> Self {
+        self.with_run(
+            text,
+            RunStyle {
+                strike: true,
+                ..RunStyle::default()
+            },
+        )
+    }
+
+    pub fn colored(self, text: impl Into<String>, color: impl Into<ElementColor>) -> Self {
+        self.with_run(
+            text,
+            RunStyle {
+                color: Some(color.into()),
+                ..RunStyle::default()
+            },
+        )
+    }
+
+    pub fn link(self, text: impl Into<String>, url: impl Into<String>) -> Self {
+        self.with_run(
+            text,
+            RunStyle {
+                link: Some(url.into()),
+                ..RunStyle::default()
+            },
+        )
+    }
+
+    fn with_run(mut self, text: impl Into<String>, style: RunStyle) -> Self {
+        self.runs.push(Run {
+            text: text.into(),
+            style,
+        });
+        self
+    }
+
+    pub(crate) fn runs(&self) -> &[Run] {
+        &self.runs
+    }
+}
+
+impl From<String> for RichText {
+    fn from(text: String) -> Self {
+        Self::new().text(text)
+    }
+}
+
+impl<'a> From<&'a String> for RichText {
+    fn from(text: &'a String) -> Self {
+        Self::from(text.clone())
+    }
+}
+
+impl<'a> From<&'a str> for RichText {
+    fn from(text: &'a str) -> Self {
+        Self::from(text.to_string())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Run {
+    pub text: String,
+    pub style: RunStyle,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RunStyle {
+    pub bold: bool,
+    pub italic: bool,
+    pub code: bool,
+    pub strike: bool,
+    pub color: Option<ElementColor>,
+    pub link: Option<String>,
+}
+
+fn parse(markup: &str, style: RunStyle, runs: &mut Vec<Run>) {
+    let mut text = String::new();
+    let mut rest = markup;
+    while let Some(ch) = rest.chars().next() {
+        if let Some(escaped) = rest
+            .strip_prefix('\\')
+            .and_then(|after| after.chars().next())
+        {
+            text.push(escaped);
+            rest = &rest[1 + escaped.len_utf8()..];
+        } else if let Some((inner, after)) = rest
+            .strip_prefix("**")
+            .and_then(|after| closing(after, "**"))
+        {
+            flush(&mut text, &style, runs);
+            parse(
+                inner,
+                RunStyle {
+                    bold: true,
+                    ..style.clone()
+                },
+                runs,
+            );
+            rest = after;
+        } else if let Some((inner, after)) = rest
+            .strip_prefix("~~")
+            .and_then(|after| closing(after, "~~"))
+        {
+            flush(&mut text, &style, runs);
+            parse(
+                inner,
+                RunStyle {
+                    strike: true,
+                    ..style.clone()
+                },
+                runs,
+            );
+            rest = after;
+        } else if let Some((inner, after)) = rest
+            .strip_prefix('*')
+            .filter(|after| !after.starts_with('*'))
+            .and_then(|after| closing(after, "*"))
+        {
+            flush(&mut text, &style, runs);
+            parse(
+                inner,
+                RunStyle {
+                    italic: true,
+                    ..style.clone()
+                },
+                runs,
+            );
+            rest = after;
+        } else if let Some((code, after)) = rest
+            .strip_prefix('`')
+            .and_then(|after| after.split_once('`'))
+        {
+            flush(&mut text, &style, runs);
+            runs.push(Run {
+                text: code.to_string(),
+                style: RunStyle {
+                    code: true,
+                    ..style.clone()
+                },
+            });
+            rest = after;
+        } else if let Some((label, url, after)) = rest.strip_prefix('[').and_then(link) {
+            flush(&mut text, &style, runs);
+            parse(
+                label,
+                RunStyle {
+                    link: Some(url.to_string()),
+                    ..style.clone()
+                },
+                runs,
+            );
+            rest = after;
+        } else {
+            text.push(ch);
+            rest = &rest[ch.len_utf8()..];
+        }
+    }
+    flush(&mut text, &style, runs);
+}
+
+// Finds the delimiter that closes a span whose opening delimiter was just
+// consumed, returning the span's contents and the markup after it. Like
+// Markdown, a span can't start or end with whitespace, so `5 * 3 * 2` stays
+// literal. Delimiters that aren't closed are kept as text.
+fn closing<'a>(markup: &'a str, delimiter: &str) -> Option<(&'a str, &'a str)> {
+    if markup.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let mut index = 0;
+    while let Some(ch) = markup[index..].chars().next() {
+        let rest = &markup[index..];
+        let doubled = delimiter == "*" && rest.starts_with("**");
+        if index > 0
+            && !doubled
+            && rest.starts_with(delimiter)
+            && !markup[..index].ends_with(char::is_whitespace)
+        {
+            return Some((&markup[..index], &rest[delimiter.len()..]));
+        }
+        index += if doubled {
+            2
+        } else if ch == '\\' {
+            1 + rest[1..].chars().next().map_or(0, char::len_utf8)
+        } else if ch == '`' {
+            rest[1..].find('`').map_or(1, |end| end + 2)
+        } else {
+            ch.len_utf8()
+        };
+    }
+    None
+}
+
+fn flush(text: &mut String, style: &RunStyle, runs: &mut Vec<Run>) {
+    if !text.is_empty() {
+        runs.push(Run {
+            text: std::mem::take(text),
+            style: style.clone(),
+        });
+    }
+}
+
+fn link(markup: &str) -> Option<(&str, &str, &str)> {
+    let (label, after) = markup.split_once("](")?;
+    let (url, after) = after.split_once(')')?;
+    Some((label, url, after))
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum PieceKind {
+    Word,
+    Space,
+    Break,
+}
+
+#[derive(Debug)]
+struct Piece {
+    run: usize,
+    kind: PieceKind,
+    measured: MeasuredText<Px>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Placed {
+    piece: usize,
+    x: Px,
+    width: Px,
+}
+
+#[derive(Debug, Default)]
+struct Line {
+    pieces: Vec<Placed>,
+    width: Px,
+    ascent: Px,
+    wrapped: bool,
+}
+
+impl Line {
+    fn place(&mut self, piece: usize, width: Px) {
+        self.pieces.push(Placed {
+            piece,
+            x: self.width,
+            width,
+        });
+        self.width += width;
+    }
+
+    fn shift(&mut self, offset: Px) {
+        for placed in &mut self.pieces {
+            placed.x += offset;
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct RichTextView {
+    runs: Vec<(Run, Color)>,
+    code_background: Color,
+    align: HAlign,
+    line_spacing: f32,
+    pieces: Vec<Piece>,
+    lines: Vec<Line>,
+    line_height: Px,
+    cached_text_size: Px,
+    cached_width: Option<Px>,
+    size: Size<UPx>,
+    link_error: Dynamic<Option<String>>,
+    measured_error: Option<MeasuredText<Px>>,
+    error_color: Color,
+}
+
+impl RichTextView {
+    pub fn new(runs: Vec<(Run, Color)>, code_background: Color) -> Self {
+        Self {
+            runs,
+            code_background,
+            align: HAlign::Left,
+            line_spacing: 1.,
+            pieces: Vec::new(),
+            lines: Vec::new(),
+            line_height: Px::ZERO,
+            cached_text_size: Px::ZERO,
+            cached_width: None,
+            size: Size::ZERO,
+            link_error: Dynamic::default(),
+            measured_error: None,
+            error_color: Color::RED,
+        }
+    }
+
+    pub fn error_color(mut self, color: Color) -> Self {
+        self.error_color = color;
+        self
+    }
+
+    pub fn align(mut self, align: HAlign) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn line_spacing(mut self, line_spacing: f32) -> Self {
+        self.line_spacing = line_spacing;
+        self
+    }
+
+    fn spaced_line_height(&self) -> Px {
+        Px::from(self.line_height.into_float() * self.line_spacing)
+    }
+
+    fn measure(&mut self, context: &mut LayoutContext<'_, '_, '_, '_>) {
+        let text_size = context.get(&TextSize).into_px(context.gfx.scale());
+        if text_size == self.cached_text_size && !self.pieces.is_empty() {
+            return;
+        }
+
+        self.cached_text_size = text_size;
+        self.cached_width = None;
+        self.pieces.clear();
+        context.apply_current_font_settings();
+        self.line_height = context.gfx.line_height().into_px(context.gfx.scale());
+        for (index, (run, color)) in self.runs.iter().enumerate() {
+            context.apply_current_font_settings();
+            if run.style.code {
+                context.gfx.set_font_family(FamilyOwned::Monospace);
+            }
+            if run.style.bold {
+                context.gfx.set_font_weight(Weight::BOLD);
+            }
+            if run.style.italic {
+                context.gfx.set_font_style(Style::Italic);
+            }
+
+            for (kind, text) in pieces(&run.text) {
+                self.pieces.push(Piece {
+                    run: index,
+                    kind,
+                    measured: context.gfx.measure_text(Text::new(text, *color)),
+                });
+            }
+        }
+    }
+
+    fn wrap(&mut self, max_width: Px) {
+        if self.cached_width == Some(max_width) && !self.lines.is_empty() {
+            return;
+        }
+        self.cached_width = Some(max_width);
+
+        let mut lines = vec![Line::default()];
+        let mut index = 0;
+        while index < self.pieces.len() {
+            let piece = &self.pieces[index];
+            match piece.kind {
+                PieceKind::Break => {
+                    lines.push(Line::default());
+                    index += 1;
+                }
+                PieceKind::Space => {
+                    let line = lines.last_mut().expect("always at least one line");
+                    if !line.pieces.is_empty() {
+                        line.place(index, piece.measured.size.width);
+                    }
+                    index += 1;
+                }
+                PieceKind::Word => {
+                    // Words split across runs, such as a partially bolded
+                    // word, must still wrap as a single unit.
+                    let end = self.pieces[index..]
+                        .iter()
+                        .position(|piece| piece.kind != PieceKind::Word)
+                        .map_or(self.pieces.len(), |length| index + length);
+                    let width = self.pieces[index..end]
+                        .iter()
+                        .fold(Px::ZERO, |width, piece| width + piece.measured.size.width);
+                    let line = lines.last_mut().expect("always at least one line");
+                    if !line.pieces.is_empty() && line.width + width > max_width {
+                        line.wrapped = true;
+                        lines.push(Line::default());
+                    }
+
+                    let line = lines.last_mut().expect("always at least one line");
+                    for index in index..end {
+                        let piece = &self.pieces[index];
+                        line.place(index, piece.measured.size.width);
+                        line.ascent = line.ascent.max(piece.measured.ascent);
+                    }
+                    index = end;
+                }
+            }
+        }
+
+        for line in &mut lines {
+            while let Some(last) = line.pieces.last() {
+                if self.pieces[last.piece].kind != PieceKind::Space {
+                    break;
+                }
+                line.width = last.x;
+                line.pieces.pop();
+            }
+        }
+
+        let widest = lines
+            .iter()
+            .map(|line| line.width)
+            .max()
+            .unwrap_or(Px::ZERO);
+        // Justified text fills the available width, but only once the text is
+        // long enough to wrap. Otherwise there is no meaningful width to fill.
+        let justify = self.align == HAlign::Fill && lines.iter().any(|line| line.wrapped);
+        let width = if justify {
+            max_width.max(widest)
+        } else {
+            widest
+        };
+        for line in &mut lines {
+            match self.align {
+                HAlign::Left => {}
+                HAlign::Center => line.shift(Px::from((width - line.width).into_float() / 2.)),
+                HAlign::Right => line.shift(width - line.width),
+                HAlign::Fill => {
+                    if justify && line.wrapped {
+                        self.justify(line, width);
+                    }
+                }
+            }
+        }
+
+        let height = Px::from(lines.len() as f32 * self.spaced_line_height().into_float());
+        self.size = Size::new(width, height).into_unsigned();
+        self.lines = lines;
+    }
+
+    fn justify(&self, line: &mut Line, width: Px) {
+        let spaces = line
+            .pieces
+            .iter()
+            .filter(|placed| self.pieces[placed.piece].kind == PieceKind::Space)
+            .count();
+        if spaces == 0 {
+            return;
+        }
+
+        let extra = (width - line.width).into_float() / spaces as f32;
+        let mut offset = 0.;
+        for placed in &mut line.pieces {
+            placed.x += Px::from(offset);
+            if self.pieces[placed.piece].kind == PieceKind::Space {
+                placed.width += Px::from(extra);
+                offset += extra;
+            }
+        }
+        line.width = width;
+    }
+
+    fn link_at(&self, location: Point<Px>) -> Option<&str> {
+        let line_height = self.spaced_line_height();
+        if location.y < Px::ZERO || line_height <= Px::ZERO {
+            return None;
+        }
+        let line = (location.y.into_float() / line_height.into_float()) as usize;
+        self.lines
+            .get(line)?
+            .pieces
+            .iter()
+            .find(|placed| location.x >= placed.x && location.x < placed.x + placed.width)
+            .and_then(|placed| {
+                self.runs[self.pieces[placed.piece].run]
+                    .0
+                    .style
+                    .link
+                    .as_deref()
+            })
+    }
+}
+
+fn pieces(text: &str) -> impl Iterator<Item = (PieceKind, &str)> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        let first = rest.chars().next()?;
+        let kind = piece_kind(first);
+        let length = if kind == PieceKind::Break {
+            first.len_utf8()
+        } else {
+            rest.char_indices()
+                .find(|(_, ch)| piece_kind(*ch) != kind)
+                .map_or(rest.len(), |(index, _)| index)
+        };
+        let (piece, after) = rest.split_at(length);
+        rest = after;
+        Some((kind, piece))
+    })
+}
+
+fn piece_kind(ch: char) -> PieceKind {
+    if ch == '\n' {
+        PieceKind::Break
+    } else if ch.is_whitespace() {
+        PieceKind::Space
+    } else {
+        PieceKind::Word
+    }
+}
+
+fn open_link(url: &str) -> io::Result<()> {
+    let mut child = if cfg!(target_os = "macos") {
+        Command::new("open").arg(url).spawn()?
+    } else if cfg!(windows) {
+        // Going through `cmd /C start` would let the shell interpret
+        // characters like `&` in the url.
+        Command::new("rundll32")
+            .args(["url.dll,FileProtocolHandler", url])
+            .spawn()?
+    } else {
+        Command::new("xdg-open").arg(url).spawn()?
+    };
+    thread::spawn(move || child.wait());
+    Ok(())
+}
+
+impl Widget for RichTextView {
+    fn redraw(&mut self, context: &mut GraphicsContext<'_, '_, '_, '_>) {
+        let thickness = Px::from(self.cached_text_size.into_float() / 16.).max(Px::new(1));
+        let line_height = self.spaced_line_height();
+        let leading = Px::from((line_height - self.line_height).into_float() / 2.);
+        let mut y = Px::ZERO;
+        for line in &self.lines {
+            let top = y + leading;
+            let baseline = top + line.ascent;
+            for &Placed { piece, x, width } in &line.pieces {
+                let piece = &self.pieces[piece];
+                let (run, color) = &self.runs[piece.run];
+                if run.style.code {
+                    context.gfx.draw_shape(&Shape::filled_rect(
+                        Rect::new(Point::new(x, top), Size::new(width, self.line_height)),
+                        self.code_background,
+                    ));
+                }
+                if piece.kind == PieceKind::Word {
+                    context.gfx.draw_measured_text(
+                        piece.measured.translate_by(Point::new(x, baseline)),
+                        TextOrigin::FirstBaseline,
+                    );
+                }
+                if run.style.strike {
+                    let strike_y = baseline - Px::from(line.ascent.into_float() * 0.3);
+                    context.gfx.draw_shape(&Shape::filled_rect(
+                        Rect::new(Point::new(x, strike_y), Size::new(width, thickness)),
+                        *color,
+                    ));
+                }
+                if run.style.link.is_some() {
+                    context.gfx.draw_shape(&Shape::filled_rect(
+                        Rect::new(
+                            Point::new(x, baseline + thickness),
+                            Size::new(width, thickness),
+                        ),
+                        *color,
+                    ));
+                }
+            }
+            y += line_height;
+        }
+
+        if let Some(error) = &self.measured_error {
+            let top = self.size.height.into_signed();
+            context.gfx.draw_measured_text(
+                error.translate_by(Point::new(Px::ZERO, top)),
+                TextOrigin::TopLeft,
+            );
+        }
+    }
+
+    fn layout(
+        &mut self,
+        available_space: Size<ConstraintLimit>,
+        context: &mut LayoutContext<'_, '_, '_, '_>,
+    ) -> Size<UPx> {
+        self.measure(context);
+        self.wrap(available_space.width.max().into_signed());
+
+        // A link that fails to open reports the error beneath the text.
+        context.invalidate_when_changed(&self.link_error);
+        self.measured_error = self.link_error.map_ref(|error| {
+            error.as_ref().map(|error| {
+                context.apply_current_font_settings();
+                context.gfx.measure_text(Text::new(error, self.error_color))
+            })
+        });
+        match &self.measured_error {
+            Some(error) => Size::new(
+                self.size.width.max(error.size.width.into_unsigned()),
+                self.size.height + error.size.height.into_unsigned(),
+            ),
+            None => self.size,
+        }
+    }
+
+    fn hit_test(&mut self, location: Point<Px>, _context: &mut EventContext<'_>) -> bool {
+        self.link_at(location).is_some()
+    }
+
+    fn mouse_down(
+        &mut self,
+        location: Point<Px>,
+        _device_id: DeviceId,
+        button: MouseButton,
+        _context: &mut EventContext<'_>,
+    ) -> EventHandling {
+        match self.link_at(location) {
+            Some(url) if button == MouseButton::Left => {
+                self.link_error.set(
+                    open_link(url)
+                        .err()
+                        .map(|err| format!("error opening {url}: {err}")),
+                );
+                HANDLED
+            }
+            _ => IGNORED,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn styled(markup: &str) -> Vec<(String, bool, bool, bool)> {
+        RichText::new()
+            .markup(markup)
+            .runs()
+            .iter()
+            .map(|run| {
+                (
+                    run.text.clone(),
+                    run.style.bold,
+                    run.style.italic,
+                    run.style.strike,
+                )
+            })
+            .collect()
+    }
+
+    fn plain(text: &str) -> Vec<(String, bool, bool, bool)> {
+        vec![(text.to_string(), false, false, false)]
+    }
+
+    #[test]
+    fn closed_spans() {
+        assert_eq!(
+            styled("a **b** *c* ~~d~~"),
+            vec![
+                (String::from("a "), false, false, false),
+                (String::from("b"), true, false, false),
+                (String::from(" "), false, false, false),
+                (String::from("c"), false, true, false),
+                (String::from(" "), false, false, false),
+                (String::from("d"), false, false, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_spans() {
+        assert_eq!(
+            styled("*a **b** c*"),
+            vec![
+                (String::from("a "), false, true, false),
+                (String::from("b"), true, true, false),
+                (String::from(" c"), false, true, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn unmatched_italic_is_literal() {
+        assert_eq!(styled("5 * 3 = 15"), plain("5 * 3 = 15"));
+        assert_eq!(styled("5 * 3 * 2"), plain("5 * 3 * 2"));
+        assert_eq!(styled("a *b"), plain("a *b"));
+    }
+
+    #[test]
+    fn unmatched_bold_is_literal() {
+        assert_eq!(styled("a ** b"), plain("a ** b"));
+        assert_eq!(styled("**a"), plain("**a"));
+    }
+
+    #[test]
+    fn unmatched_strike_is_literal() {
+        assert_eq!(styled("~~a"), plain("~~a"));
+    }
+
+    #[test]
+    fn delimiters_in_code_do_not_close() {
+        assert_eq!(
+            styled("*a `*` b*")
+                .into_iter()
+                .map(|(text, _, italic, _)| (text, italic))
+                .collect::<Vec<_>>(),
+            vec![
+                (String::from("a "), true),
+                (String::from("*"), true),
+                (String::from(" b"), true),
+            ]
+        );
+    }
+}
@@ -0,0 +1,359 @@
+use std::fmt::{self, Display};
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use std::{env, fs};
+
+use cushy::value::{Destination, Dynamic, Source};
+
+#[derive(Debug, Clone)]
+pub struct RunCommand {
+    program: String,
+    args: Vec<String>,
+    file_name: String,
+    timeout: Duration,
+}
+
+impl RunCommand {
+    pub fn new(program: impl Into<String>) -> Self {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+            file_name: String::from("snippet"),
+            timeout: Duration::from_secs(10),
+        }
+    }
+
+    pub fn shell(script: impl Into<String>) -> Self {
+        if cfg!(windows) {
+            Self::new("cmd").arg("/C").arg(script)
+        } else {
+            Self::new("sh").arg("-c").arg(script)
+        }
+    }
+
+    pub fn arg(mut self, arg: impl Into<String>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    pub fn args<Arg>(mut self, args: impl IntoIterator<Item = Arg>) -> Self
+    where
+        Arg: Into<String>,
+    {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn file_name(mut self, file_name: impl Into<String>) -> Self {
+        self.file_name = file_name.into();
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    fn cache_key(&self, source: &str) -> u64 {
+        // FNV-1a is used instead of the standard library's hasher because the
+        // key must remain stable between the rehearsal and the presentation.
+        let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+        for part in [&self.program, &self.file_name]
+            .into_iter()
+            .chain(&self.args)
+            .map(String::as_str)
+            .chain([source])
+        {
+            for byte in part.bytes().chain([0]) {
+                hash ^= u64::from(byte);
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
+        }
+        hash
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) enum RunState {
+    Idle,
+    Running,
+    Exited(Option<i32>),
+    Uncached(Option<i32>, String),
+    Replayed(Option<i32>),
+    TimedOut,
+    Killed,
+    Failed(String),
+}
+
+impl Display for RunState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RunState::Idle => Ok(()),
+            RunState::Running => f.write_str("Running..."),
+            RunState::Exited(Some(code)) => write!(f, "Exited with status {code}"),
+            RunState::Exited(None) => f.write_str("Terminated by signal"),
+            RunState::Uncached(status, err) => {
+                write!(
+                    f,
+                    "{} (error caching output: {err})",
+                    RunState::Exited(*status)
+                )
+            }
+            RunState::Replayed(Some(code)) => write!(f, "Replayed (status {code})"),
+            RunState::Replayed(None) => f.write_str("Replayed (terminated by signal)"),
+            RunState::TimedOut => f.write_str("Timed out"),
+            RunState::Killed => f.write_str("Killed"),
+            RunState::Failed(err) => write!(f, "Error: {err}"),
+        }
+    }
+}
+
+// Kills the running command when dropped. The run widgets hold one, so a
+// demo doesn't keep running after its slide is left.
+#[derive(Debug)]
+pub(crate) struct KillOnDrop(pub Runner);
+
+impl Drop for KillOnDrop {
+    fn drop(&mut self) {
+        self.0.kill();
+    }
+}
+
+static NEXT_RUN: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Clone)]
+pub(crate) struct Runner {
+    command: RunCommand,
+    cache: PathBuf,
+    replay: bool,
+    pub output: Dynamic<String>,
+    pub state: Dynamic<RunState>,
+    kill: Arc<AtomicBool>,
+}
+
+impl Runner {
+    pub fn new(command: RunCommand, cache: PathBuf, replay: bool) -> Self {
+        Self {
+            command,
+            cache,
+            replay,
+            output: Dynamic::default(),
+            state: Dynamic::new(RunState::Idle),
+            kill: Arc::default(),
+        }
+    }
+
+    pub fn run(&self, source: String) {
+        if self.state.get() == RunState::Running {
+            return;
+        }
+
+        self.output.set(String::new());
+        self.kill.store(false, Ordering::Relaxed);
+
+        if self.replay {
+            if let Some((status, output)) = self.read_cache(&source) {
+                self.output.set(output);
+                self.state.set(RunState::Replayed(status));
+                return;
+            }
+        }
+
+        self.state.set(RunState::Running);
+        let runner = self.clone();
+        thread::spawn(move || {
+            let mut state = runner
+                .execute(&source)
+                .unwrap_or_else(|err| RunState::Failed(err.to_string()));
+            if let RunState::Exited(status) = state {
+                if let Err(err) = runner.write_cache(&source, status) {
+                    state = RunState::Uncached(status, err.to_string());
+                }
+            }
+            runner.state.set(state);
+        });
+    }
+
+    pub fn kill(&self) {
+        self.kill.store(true, Ordering::Relaxed);
+    }
+
+    pub fn fail(&self, error: impl Display) {
+        self.state.set(RunState::Failed(error.to_string()));
+    }
+
+    fn execute(&self, source: &str) -> io::Result<RunState> {
+        let directory = env::temp_dir().join(format!(
+            "cushy-show-{}-{}",
+            process::id(),
+            NEXT_RUN.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&directory)?;
+        let result = self.execute_in(&directory, source);
+        let _ = fs::remove_dir_all(&directory);
+        result
+    }
+
+    fn execute_in(&self, directory: &Path, source: &str) -> io::Result<RunState> {
+        fs::write(directory.join(&self.command.file_name), source)?;
+        let mut child = Command::new(&self.command.program)
+            .args(&self.command.args)
+            .current_dir(directory)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let readers = [
+            child.stdout.take().map(|stdout| self.stream(stdout)),
+            child.stderr.take().map(|stderr| self.stream(stderr)),
+        ];
+
+        let started = Instant::now();
+        let state = loop {
+            if let Some(status) = child.try_wait()? {
+                break RunState::Exited(status.code());
+            } else if self.kill.load(Ordering::Relaxed) {
+                child.kill()?;
+                child.wait()?;
+                break RunState::Killed;
+            } else if started.elapsed() > self.command.timeout {
+                child.kill()?;
+                child.wait()?;
+                break RunState::TimedOut;
+            }
+            thread::sleep(Duration::from_millis(20));
+        };
+
+        // A killed shell can leave grandchildren holding the pipes open, so
+        // only wait for the remaining output when the process exited itself.
+        if matches!(state, RunState::Exited(_)) {
+            for reader in readers.into_iter().flatten() {
+                let _ = reader.join();
+            }
+        }
+        Ok(state)
+    }
+
+    fn stream(&self, pipe: impl Read + Send + 'static) -> JoinHandle<()> {
+        let output = self.output.clone();
+        thread::spawn(move || {
+            // Output is read as bytes so that invalid UTF-8 doesn't stop the
+            // reader and leave the process blocked on a full pipe.
+            let mut pipe = BufReader::new(pipe);
+            let mut line = Vec::new();
+            while matches!(pipe.read_until(b'\n', &mut line), Ok(read) if read > 0) {
+                output.map_mut(|mut output| output.push_str(&String::from_utf8_lossy(&line)));
+                line.clear();
+            }
+        })
+    }
+
+    fn cache_path(&self, source: &str) -> PathBuf {
+        self.cache
+            .join(format!("{:016x}.txt", self.command.cache_key(source)))
+    }
+
+    fn read_cache(&self, source: &str) -> Option<(Option<i32>, String)> {
+        let cached = fs::read_to_string(self.cache_path(source)).ok()?;
+        let (status, output) = cached.split_once('\n')?;
+        let status = status.strip_prefix("status: ")?;
+        let status = if status == "signal" {
+            None
+        } else {
+            Some(status.parse().ok()?)
+        };
+        Some((status, output.to_string()))
+    }
+
+    fn write_cache(&self, source: &str, status: Option<i32>) -> io::Result<()> {
+        fs::create_dir_all(&self.cache)?;
+        let status = status.map_or_else(|| String::from("signal"), |code| code.to_string());
+        fs::write(
+            self.cache_path(source),
+            format!("status: {status}\n{}", self.output.get()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use std::{env, fs, process};
+
+    use cushy::value::{Destination, Source};
+
+    use super::{RunCommand, RunState, Runner};
+
+    fn runner(command: RunCommand, name: &str) -> Runner {
+        let cache = env::temp_dir().join(format!("cushy-show-test-{}-{name}", process::id()));
+        Runner::new(command, cache, true)
+    }
+
+    #[test]
+    fn cache_keys() {
+        let command = RunCommand::new("rustc").arg("main.rs");
+        assert_eq!(command.cache_key("a"), command.cache_key("a"));
+        assert_ne!(command.cache_key("a"), command.cache_key("b"));
+        assert_ne!(
+            command.cache_key("a"),
+            command.clone().arg("-O").cache_key("a")
+        );
+        assert_ne!(
+            command.cache_key("a"),
+            command.file_name("other.rs").cache_key("a")
+        );
+    }
+
+    #[test]
+    fn cache_round_trip() {
+        let runner = runner(RunCommand::new("true"), "cache");
+        assert_eq!(runner.read_cache("source"), None);
+
+        runner.output.set(String::from("first\nsecond\n"));
+        runner.write_cache("source", Some(3)).unwrap();
+        assert_eq!(
+            runner.read_cache("source"),
+            Some((Some(3), String::from("first\nsecond\n")))
+        );
+        runner.output.set(String::new());
+        runner.write_cache("signaled", None).unwrap();
+        assert_eq!(runner.read_cache("signaled"), Some((None, String::new())));
+        assert_eq!(runner.read_cache("other"), None);
+
+        fs::remove_dir_all(&runner.cache).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn runs_and_captures_output() {
+        let runner = runner(RunCommand::shell("cat snippet; exit 2"), "output");
+        assert_eq!(
+            runner.execute("hello\n").unwrap(),
+            RunState::Exited(Some(2))
+        );
+        assert_eq!(runner.output.get(), "hello\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn invalid_utf8_output() {
+        let runner = runner(RunCommand::shell("printf 'a\\377b\\nafter\\n'"), "utf8");
+        assert_eq!(runner.execute("").unwrap(), RunState::Exited(Some(0)));
+        assert_eq!(runner.output.get(), "a\u{fffd}b\nafter\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn timeout() {
+        let runner = runner(
+            RunCommand::shell("sleep 5").timeout(Duration::from_millis(100)),
+            "timeout",
+        );
+        assert_eq!(runner.execute("").unwrap(), RunState::TimedOut);
+    }
+}
@@ -0,0 +1,116 @@
+use cushy::figures::units::Px;
+use cushy::figures::Point;
+use cushy::kludgine::shapes::{Path, PathBuilder};
+
+pub(crate) fn point((x, y): (f32, f32)) -> Point<Px> {
+    Point::new(Px::from(x), Px::from(y))
+}
+
+pub(crate) fn polyline(points: &[(f32, f32)]) -> Option<Path<Px, false>> {
+    let (first, rest) = points.split_first()?;
+    Some(
+        rest.iter()
+            .fold(PathBuilder::new(point(*first)), |path, next| {
+                path.line_to(point(*next))
+            })
+            .build(),
+    )
+}
+
+pub(crate) fn polygon(points: &[(f32, f32)]) -> Option<Path<Px, false>> {
+    let (first, rest) = points.split_first()?;
+    Some(
+        rest.iter()
+            .fold(PathBuilder::new(point(*first)), |path, next| {
+                path.line_to(point(*next))
+            })
+            .close(),
+    )
+}
+
+pub(crate) fn rounded_rect(
+    (left, top): (f32, f32),
+    (right, bottom): (f32, f32),
+    radius: f32,
+) -> Path<Px, false> {
+    let radius = radius
+        .min((right - left) / 2.)
+        .min((bottom - top) / 2.)
+        .max(0.);
+    PathBuilder::new(point((left + radius, top)))
+        .line_to(point((right - radius, top)))
+        .quadratic_curve_to(point((right, top)), point((right, top + radius)))
+        .line_to(point((right, bottom - radius)))
+        .quadratic_curve_to(point((right, bottom)), point((right - radius, bottom)))
+        .line_to(point((left + radius, bottom)))
+        .quadratic_curve_to(point((left, bottom)), point((left, bottom - radius)))
+        .line_to(point((left, top + radius)))
+        .quadratic_curve_to(point((left, top)), point((left + radius, top)))
+        .close()
+}
+
+pub(crate) fn ellipse((x, y): (f32, f32), (radius_x, radius_y): (f32, f32)) -> Path<Px, false> {
+    // Four cubic curves approximate an ellipse closely enough to be
+    // indistinguishable at slide sizes.
+    const KAPPA: f32 = 0.552_284_8;
+    let (kx, ky) = (radius_x * KAPPA, radius_y * KAPPA);
+    let (left, top, right, bottom) = (x - radius_x, y - radius_y, x + radius_x, y + radius_y);
+    PathBuilder::new(point((x, top)))
+        .cubic_curve_to(
+            point((x + kx, top)),
+            point((right, y - ky)),
+            point((right, y)),
+        )
+        .cubic_curve_to(
+            point((right, y + ky)),
+            point((x + kx, bottom)),
+            point((x, bottom)),
+        )
+        .cubic_curve_to(
+            point((x - kx, bottom)),
+            point((left, y + ky)),
+            point((left, y)),
+        )
+        .cubic_curve_to(point((left, y - ky)), point((x - kx, top)), point((x, top)))
+        .close()
+}
+
+pub(crate) fn arrow_head(tip: (f32, f32), from: (f32, f32), size: f32) -> Path<Px, false> {
+    let (dx, dy) = (tip.0 - from.0, tip.1 - from.1);
+    let length = (dx * dx + dy * dy).sqrt().max(f32::EPSILON);
+    let (ux, uy) = (dx / length, dy / length);
+    let base = (tip.0 - ux * size, tip.1 - uy * size);
+    PathBuilder::new(point(tip))
+        .line_to(point((base.0 - uy * size / 2., base.1 + ux * size / 2.)))
+        .line_to(point((base.0 + uy * size / 2., base.1 - ux * size / 2.)))
+        .close()
+}
+
+pub(crate) fn dashes(points: &[(f32, f32)], dash: f32) -> Vec<[(f32, f32); 2]> {
+    let mut segments = Vec::new();
+    let mut drawing = true;
+    let mut remaining = dash;
+    for pair in points.windows(2) {
+        let (mut start, end) = (pair[0], pair[1]);
+        let mut length = ((end.0 - start.0).powi(2) + (end.1 - start.1).powi(2)).sqrt();
+        while length > 0. {
+            let step = remaining.min(length);
+            let t = step / length;
+            let next = (
+                start.0 + (end.0 - start.0) * t,
+                start.1 + (end.1 - start.1) * t,
+            );
+            if drawing {
+                segments.push([start, next]);
+            }
+            start = next;
+            length -= step;
+            remaining -= step;
+            if remaining <= 0. {
+                drawing = !drawing;
+                remaining = dash;
+            }
+        }
+    }
+    segments
+}
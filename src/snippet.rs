@@ -0,0 +1,443 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::ops::{Bound, RangeBounds};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock, Weak};
+use std::{fs, io};
+
+use cushy::value::{Destination, Dynamic, Source};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+#[derive(Debug, Clone)]
+pub struct Snippet {
+    origin: Origin,
+    anchor: Option<String>,
+    first_line: usize,
+    last_line: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+enum Origin {
+    Source(String),
+    File(PathBuf),
+}
+
+impl Snippet {
+    pub fn from_source(source: impl Into<String>) -> Self {
+        Self::new(Origin::Source(source.into()))
+    }
+
+    pub fn from_file(path: impl Into<PathBuf>) -> Self {
+        Self::new(Origin::File(path.into()))
+    }
+
+    fn new(origin: Origin) -> Self {
+        Self {
+            origin,
+            anchor: None,
+            first_line: 1,
+            last_line: None,
+        }
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        match &self.origin {
+            Origin::Source(_) => None,
+            Origin::File(path) => Some(path),
+        }
+    }
+
+    pub fn set_anchor(&mut self, name: impl Into<String>) {
+        self.anchor = Some(name.into());
+    }
+
+    pub fn set_lines(&mut self, lines: impl RangeBounds<usize>) {
+        self.first_line = match lines.start_bound() {
+            Bound::Included(line) => *line,
+            Bound::Excluded(line) => line + 1,
+            Bound::Unbounded => 1,
+        };
+        self.last_line = match lines.end_bound() {
+            Bound::Included(line) => Some(*line),
+            Bound::Excluded(line) => Some(line.saturating_sub(1)),
+            Bound::Unbounded => None,
+        };
+    }
+
+    pub fn watch(&self) -> Result<Option<SnippetWatcher>, SnippetError> {
+        let Some(path) = self.path() else {
+            return Ok(None);
+        };
+        let watch_error = |error| SnippetError::Watch {
+            path: path.to_path_buf(),
+            error,
+        };
+
+        let path = fs::canonicalize(path).map_err(|error| SnippetError::Io {
+            path: path.to_path_buf(),
+            error,
+        })?;
+
+        // Slides are rebuilt on every visit, so code blocks showing the same
+        // file share one watcher for as long as any of them is alive.
+        static WATCHES: OnceLock<Mutex<HashMap<PathBuf, Weak<FileWatch>>>> = OnceLock::new();
+        let mut watches = WATCHES
+            .get_or_init(Mutex::default)
+            .lock()
+            .expect("poisoned");
+        let watch = match watches.get(&path).and_then(Weak::upgrade) {
+            Some(watch) => watch,
+            None => {
+                let Some(watch) = FileWatch::new(&path).map_err(watch_error)? else {
+                    return Ok(None);
+                };
+                let watch = Arc::new(watch);
+                watches.retain(|_, watch| watch.strong_count() > 0);
+                watches.insert(path, Arc::downgrade(&watch));
+                watch
+            }
+        };
+
+        Ok(Some(SnippetWatcher {
+            snippet: self.clone(),
+            loaded: watch.changed.get(),
+            watch,
+        }))
+    }
+
+    pub fn load(&self) -> Result<String, SnippetError> {
+        match &self.origin {
+            Origin::Source(source) => self.extract(source),
+            Origin::File(path) => {
+                let source = fs::read_to_string(path).map_err(|error| SnippetError::Io {
+                    path: path.clone(),
+                    error,
+                })?;
+                self.extract(&source)
+            }
+        }
+    }
+
+    fn extract(&self, source: &str) -> Result<String, SnippetError> {
+        let mut lines = source.lines().collect::<Vec<_>>();
+        if let Some(anchor) = &self.anchor {
+            lines = anchored(&lines, anchor)?.to_vec();
+        }
+
+        let last_line = self.last_line.unwrap_or(lines.len());
+        if self.first_line == 0 || self.first_line > last_line + 1 || last_line > lines.len() {
+            return Err(SnippetError::LinesOutOfRange {
+                first: self.first_line,
+                last: last_line,
+                line_count: lines.len(),
+            });
+        }
+        lines.truncate(last_line);
+        lines.drain(..self.first_line - 1);
+
+        lines.retain(|line| Marker::parse(line).is_none());
+        Ok(dedent(&lines))
+    }
+}
+
+struct FileWatch {
+    changed: Dynamic<usize>,
+    _watcher: RecommendedWatcher,
+}
+
+impl FileWatch {
+    fn new(path: &Path) -> Result<Option<Self>, notify::Error> {
+        let (Some(directory), Some(file_name)) = (path.parent(), path.file_name()) else {
+            return Ok(None);
+        };
+
+        // Editors commonly save by writing a new file and renaming it over the
+        // original, so the parent directory is watched rather than the file.
+        let changed = Dynamic::new(0_usize);
+        let mut watcher = notify::recommended_watcher({
+            let changed = changed.clone();
+            let file_name = file_name.to_os_string();
+            move |event: notify::Result<notify::Event>| {
+                let Ok(event) = event else {
+                    return;
+                };
+                if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+                    && event
+                        .paths
+                        .iter()
+                        .any(|path| path.file_name() == Some(&file_name))
+                {
+                    changed.map_mut(|mut generation| *generation += 1);
+                }
+            }
+        })?;
+        watcher.watch(directory, RecursiveMode::NonRecursive)?;
+        Ok(Some(Self {
+            changed,
+            _watcher: watcher,
+        }))
+    }
+}
+
+pub struct SnippetWatcher {
+    snippet: Snippet,
+    watch: Arc<FileWatch>,
+    loaded: usize,
+}
+
+impl SnippetWatcher {
+    pub fn changed(&self) -> &Dynamic<usize> {
+        &self.watch.changed
+    }
+
+    pub fn reload(&mut self) -> Option<Result<String, SnippetError>> {
+        let generation = self.watch.changed.get();
+        if generation == self.loaded {
+            return None;
+        }
+
+        self.loaded = generation;
+        Some(self.snippet.load())
+    }
+}
+
+impl fmt::Debug for SnippetWatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SnippetWatcher")
+            .field("snippet", &self.snippet)
+            .field("loaded", &self.loaded)
+            .finish_non_exhaustive()
+    }
+}
+
+enum Marker<'a> {
+    Start(&'a str),
+    End(&'a str),
+}
+
+impl<'a> Marker<'a> {
+    fn parse(line: &'a str) -> Option<Self> {
+        let (is_start, prefix, rest) = match line.split_once("ANCHOR_START") {
+            Some((prefix, rest)) => (true, prefix, rest),
+            None => {
+                let (prefix, rest) = line.split_once("ANCHOR_END")?;
+                (false, prefix, rest)
+            }
+        };
+
+        // Only treat the keyword as a marker when nothing but comment
+        // punctuation precedes it, so string literals mentioning a marker
+        // aren't mistaken for one.
+        if prefix
+            .chars()
+            .any(|ch| ch.is_alphanumeric() || matches!(ch, '"' | '\''))
+        {
+            return None;
+        }
+
+        let name = match rest.trim_start().strip_prefix(':') {
+            Some(name) => name.split_whitespace().next().unwrap_or(""),
+            None => "",
+        };
+        Some(if is_start {
+            Marker::Start(name)
+        } else {
+            Marker::End(name)
+        })
+    }
+}
+
+fn anchored<'a, 'b>(lines: &'b [&'a str], anchor: &str) -> Result<&'b [&'a str], SnippetError> {
+    let is_start =
+        |line: &&str| matches!(Marker::parse(line), Some(Marker::Start(name)) if name == anchor);
+    let is_end =
+        |line: &&str| matches!(Marker::parse(line), Some(Marker::End(name)) if name == anchor);
+
+    let start = lines
+        .iter()
+        .position(is_start)
+        .ok_or_else(|| SnippetError::MissingAnchor(anchor.to_string()))?
+        + 1;
+    let length = lines[start..]
+        .iter()
+        .position(is_end)
+        .ok_or_else(|| SnippetError::UnclosedAnchor(anchor.to_string()))?;
+    Ok(&lines[start..start + length])
+}
+
+fn dedent(lines: &[&str]) -> String {
+    let first = lines
+        .iter()
+        .position(|line| !line.trim().is_empty())
+        .unwrap_or(lines.len());
+    let last = lines
+        .iter()
+        .rposition(|line| !line.trim().is_empty())
+        .map_or(first, |last| last + 1);
+    let lines = &lines[first..last];
+
+    let mut common_whitespace: Option<&str> = None;
+    for line in lines.iter().filter(|line| !line.trim().is_empty()) {
+        let whitespace = &line[..line.len() - line.trim_start().len()];
+        common_whitespace = Some(match common_whitespace {
+            None => whitespace,
+            Some(common) => {
+                let matching = common
+                    .char_indices()
+                    .zip(whitespace.chars())
+                    .find_map(|((index, a), b)| (a != b).then_some(index))
+                    .unwrap_or(common.len().min(whitespace.len()));
+                &common[..matching]
+            }
+        });
+    }
+    let common_whitespace = common_whitespace.unwrap_or("");
+
+    let mut dedented = String::new();
+    for line in lines {
+        dedented.push_str(
+            line.strip_prefix(common_whitespace)
+                .unwrap_or_else(|| line.trim_start()),
+        );
+        dedented.push('\n');
+    }
+    dedented
+}
+
+#[derive(Debug)]
+pub enum SnippetError {
+    Io {
+        path: PathBuf,
+        error: io::Error,
+    },
+    Watch {
+        path: PathBuf,
+        error: notify::Error,
+    },
+    MissingAnchor(String),
+    UnclosedAnchor(String),
+    LinesOutOfRange {
+        first: usize,
+        last: usize,
+        line_count: usize,
+    },
+}
+
+impl Display for SnippetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnippetError::Io { path, error } => {
+                write!(f, "error reading {}: {error}", path.display())
+            }
+            SnippetError::Watch { path, error } => {
+                write!(f, "error watching {}: {error}", path.display())
+            }
+            SnippetError::MissingAnchor(name) => write!(f, "anchor {name:?} not found"),
+            SnippetError::UnclosedAnchor(name) => {
+                write!(f, "anchor {name:?} is missing its ANCHOR_END marker")
+            }
+            SnippetError::LinesOutOfRange {
+                first,
+                last,
+                line_count,
+            } => write!(
+                f,
+                "lines {first} through {last} are out of range for a snippet with {line_count} lines"
+            ),
+        }
+    }
+}
+
+impl Error for SnippetError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SnippetError::Io { error, .. } => Some(error),
+            SnippetError::Watch { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Snippet, SnippetError};
+
+    const SOURCE: &str = "fn main() {
+    // ANCHOR_START: body
+    let a = 1;
+        let b = 2;
+    // ANCHOR_END: body
+    println!(\"ANCHOR_START: not a marker\");
+}
+";
+
+    fn load(configure: impl FnOnce(&mut Snippet)) -> Result<String, SnippetError> {
+        let mut snippet = Snippet::from_source(SOURCE);
+        configure(&mut snippet);
+        snippet.load()
+    }
+
+    #[test]
+    fn markers_are_stripped() {
+        assert_eq!(
+            load(|_| {}).unwrap(),
+            "fn main() {
+    let a = 1;
+        let b = 2;
+    println!(\"ANCHOR_START: not a marker\");
+}
+"
+        );
+    }
+
+    #[test]
+    fn anchors_are_dedented() {
+        assert_eq!(
+            load(|snippet| snippet.set_anchor("body")).unwrap(),
+            "let a = 1;\n    let b = 2;\n"
+        );
+        assert!(matches!(
+            load(|snippet| snippet.set_anchor("missing")),
+            Err(SnippetError::MissingAnchor(name)) if name == "missing"
+        ));
+        assert!(matches!(
+            Snippet::from_source("// ANCHOR_START: open\nx\n").load(),
+            Err(SnippetError::UnclosedAnchor(name)) if name == "open"
+        ));
+    }
+
+    #[test]
+    fn line_ranges() {
+        assert_eq!(
+            load(|snippet| snippet.set_lines(3..=4)).unwrap(),
+            "let a = 1;\n    let b = 2;\n"
+        );
+        assert_eq!(
+            load(|snippet| snippet.set_lines(..2)).unwrap(),
+            "fn main() {\n"
+        );
+        assert!(matches!(
+            load(|snippet| snippet.set_lines(5..=20)),
+            Err(SnippetError::LinesOutOfRange {
+                first: 5,
+                last: 20,
+                line_count: 7
+            })
+        ));
+        assert!(matches!(
+            load(|snippet| snippet.set_lines(0..2)),
+            Err(SnippetError::LinesOutOfRange { first: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn dedent_trims_blank_lines() {
+        assert_eq!(
+            Snippet::from_source("\n\n    a\n\n      b\n\n")
+                .load()
+                .unwrap(),
+            "a\n\n  b\n"
+        );
+    }
+}
@@ -0,0 +1,107 @@
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use cushy::context::{GraphicsContext, LayoutContext};
+use cushy::figures::units::{Px, UPx};
+use cushy::figures::{FloatConversion, IntoSigned, Point, Rect, Size, Zero};
+use cushy::kludgine::image::{DynamicImage, RgbaImage};
+use cushy::kludgine::{wgpu, LazyTexture};
+use cushy::widget::Widget;
+use cushy::ConstraintLimit;
+use resvg::tiny_skia::{Pixmap, Transform};
+use resvg::usvg::{Options, Tree};
+
+// Rasterizing is done on the CPU, so an unconstrained layout, such as inside
+// of a scroll view, must not be allowed to request an enormous pixmap.
+const MAX_DIMENSION: f32 = 8192.;
+
+pub(crate) fn parse(source: &str) -> Result<Arc<Tree>, String> {
+    let mut options = Options::default();
+    options.fontdb_mut().load_system_fonts();
+    let data = if source.trim_start().starts_with('<') {
+        source.as_bytes().to_vec()
+    } else {
+        let path = Path::new(source);
+        options.resources_dir = path.parent().map(Path::to_path_buf);
+        fs::read(path).map_err(|err| format!("{source}: {err}"))?
+    };
+    Tree::from_data(&data, &options)
+        .map(Arc::new)
+        .map_err(|err| err.to_string())
+}
+
+#[derive(Debug)]
+pub struct SvgView {
+    tree: Arc<Tree>,
+    rendered: Option<(Size<UPx>, LazyTexture)>,
+}
+
+impl SvgView {
+    pub fn new(tree: Arc<Tree>) -> Self {
+        Self {
+            tree,
+            rendered: None,
+        }
+    }
+
+    fn rasterize(&mut self, size: Size<UPx>) {
+        if self.rendered.as_ref().map(|(rendered, _)| *rendered) == Some(size) {
+            return;
+        }
+
+        let Some(mut pixmap) = Pixmap::new(size.width.get(), size.height.get()) else {
+            self.rendered = None;
+            return;
+        };
+        let natural = self.tree.size();
+        resvg::render(
+            &self.tree,
+            Transform::from_scale(
+                size.width.into_float() / natural.width(),
+                size.height.into_float() / natural.height(),
+            ),
+            &mut pixmap.as_mut(),
+        );
+
+        let mut image = RgbaImage::new(size.width.get(), size.height.get());
+        for (pixel, rendered) in image.pixels_mut().zip(pixmap.pixels()) {
+            let color = rendered.demultiply();
+            pixel.0 = [color.red(), color.green(), color.blue(), color.alpha()];
+        }
+        self.rendered = Some((
+            size,
+            LazyTexture::from_image(DynamicImage::ImageRgba8(image), wgpu::FilterMode::Linear),
+        ));
+    }
+}
+
+impl Widget for SvgView {
+    fn redraw(&mut self, context: &mut GraphicsContext<'_, '_, '_, '_>) {
+        if let Some((size, texture)) = &self.rendered {
+            context
+                .gfx
+                .draw_texture(texture, Rect::new(Point::<Px>::ZERO, size.into_signed()));
+        }
+    }
+
+    fn layout(
+        &mut self,
+        available_space: Size<ConstraintLimit>,
+        _context: &mut LayoutContext<'_, '_, '_, '_>,
+    ) -> Size<UPx> {
+        // The available space is measured in physical pixels, so rasterizing
+        // at the laid out size keeps the image crisp at any display scale.
+        let natural = self.tree.size();
+        let available = available_space.map(ConstraintLimit::max);
+        let scale = (available.width.into_float() / natural.width())
+            .min(available.height.into_float() / natural.height())
+            .min(MAX_DIMENSION / natural.width().max(natural.height()));
+        let size = Size::new(
+            UPx::from((natural.width() * scale).round()),
+            UPx::from((natural.height() * scale).round()),
+        );
+        self.rasterize(size);
+        size
+    }
+}
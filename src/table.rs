@@ -0,0 +1,211 @@
+use cushy::context::{EventContext, GraphicsContext, LayoutContext};
+use cushy::figures::units::{Px, UPx};
+use cushy::figures::{
+    FloatConversion, IntoSigned, IntoUnsigned, Point, Rect, ScreenScale, Size, Zero,
+};
+use cushy::kludgine::shapes::Shape;
+use cushy::styles::components::IntrinsicPadding;
+use cushy::styles::Color;
+use cushy::widget::{MakeWidget, Widget, WidgetRef};
+use cushy::ConstraintLimit;
+
+#[derive(Debug, Clone, Copy)]
+pub struct TableColors {
+    pub header: Color,
+    pub stripe: Color,
+    pub border: Color,
+}
+
+#[derive(Debug)]
+pub struct TableView {
+    rows: Vec<Vec<WidgetRef>>,
+    header: bool,
+    striped: bool,
+    bordered: bool,
+    colors: TableColors,
+    column_edges: Vec<Px>,
+    row_edges: Vec<Px>,
+}
+
+impl TableView {
+    pub fn new(
+        rows: Vec<Vec<impl MakeWidget>>,
+        header: bool,
+        striped: bool,
+        bordered: bool,
+        colors: TableColors,
+    ) -> Self {
+        Self {
+            rows: rows
+                .into_iter()
+                .map(|row| row.into_iter().map(WidgetRef::new).collect())
+                .collect(),
+            header,
+            striped,
+            bordered,
+            colors,
+            column_edges: Vec::new(),
+            row_edges: Vec::new(),
+        }
+    }
+
+    fn columns(&self) -> usize {
+        self.rows.iter().map(Vec::len).max().unwrap_or(0)
+    }
+}
+
+fn edges(sizes: &[Px]) -> Vec<Px> {
+    let mut edges = Vec::with_capacity(sizes.len() + 1);
+    let mut edge = Px::ZERO;
+    edges.push(edge);
+    for size in sizes {
+        edge += *size;
+        edges.push(edge);
+    }
+    edges
+}
+
+impl Widget for TableView {
+    fn redraw(&mut self, context: &mut GraphicsContext<'_, '_, '_, '_>) {
+        let (Some(&left), Some(&right)) = (self.column_edges.first(), self.column_edges.last())
+        else {
+            return;
+        };
+        let width = right - left;
+
+        for (index, bounds) in self.row_edges.windows(2).enumerate() {
+            let background = if self.header && index == 0 {
+                Some(self.colors.header)
+            } else if self.striped && (index + usize::from(!self.header)) % 2 == 0 {
+                Some(self.colors.stripe)
+            } else {
+                None
+            };
+            if let Some(background) = background {
+                context.gfx.draw_shape(&Shape::filled_rect(
+                    Rect::new(
+                        Point::new(left, bounds[0]),
+                        Size::new(width, bounds[1] - bounds[0]),
+                    ),
+                    background,
+                ));
+            }
+        }
+
+        let thickness = Px::new(1);
+        let top = self.row_edges.first().copied().unwrap_or(Px::ZERO);
+        let bottom = self.row_edges.last().copied().unwrap_or(Px::ZERO);
+        if self.bordered {
+            for &y in &self.row_edges {
+                context.gfx.draw_shape(&Shape::filled_rect(
+                    Rect::new(
+                        Point::new(left, y.min(bottom - thickness)),
+                        Size::new(width, thickness),
+                    ),
+                    self.colors.border,
+                ));
+            }
+            for &x in &self.column_edges {
+                context.gfx.draw_shape(&Shape::filled_rect(
+                    Rect::new(
+                        Point::new(x.min(right - thickness), top),
+                        Size::new(thickness, bottom - top),
+                    ),
+                    self.colors.border,
+                ));
+            }
+        } else if let (true, Some(&y)) = (self.header, self.row_edges.get(1)) {
+            context.gfx.draw_shape(&Shape::filled_rect(
+                Rect::new(Point::new(left, y), Size::new(width, thickness)),
+                self.colors.border,
+            ));
+        }
+
+        for cell in self.rows.iter_mut().flatten() {
+            let cell = cell.mounted(&mut context.as_event_context());
+            context.for_other(&cell).redraw();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        available_space: Size<ConstraintLimit>,
+        context: &mut LayoutContext<'_, '_, '_, '_>,
+    ) -> Size<UPx> {
+        let padding = context.get(&IntrinsicPadding).into_px(context.gfx.scale());
+        let gutter = padding + padding;
+        let available = available_space.map(ConstraintLimit::max).into_signed();
+        let columns = self.columns();
+
+        let mut widths = vec![Px::ZERO; columns];
+        for row in &mut self.rows {
+            for (column, cell) in row.iter_mut().enumerate() {
+                let cell = cell.mounted(&mut context.as_event_context());
+                let size = context
+                    .for_other(&cell)
+                    .layout(available_space.map(|limit| ConstraintLimit::SizeToFit(limit.max())))
+                    .into_signed();
+                widths[column] = widths[column].max(size.width + gutter);
+            }
+        }
+
+        // Columns shrink proportionally when the table is too wide, which
+        // gives wrapping cells, like paragraphs, a chance to fit. A column
+        // never shrinks below its padding so cells never get negative sizes.
+        let total = widths.iter().fold(Px::ZERO, |total, width| total + *width);
+        if total > available.width && total > Px::ZERO {
+            let ratio = available.width.into_float() / total.into_float();
+            for width in &mut widths {
+                *width = Px::from(width.into_float() * ratio).floor().max(gutter);
+            }
+        }
+
+        let mut heights = vec![Px::ZERO; self.rows.len()];
+        for (row, height) in self.rows.iter_mut().zip(&mut heights) {
+            for (column, cell) in row.iter_mut().enumerate() {
+                let cell = cell.mounted(&mut context.as_event_context());
+                let size = context
+                    .for_other(&cell)
+                    .layout(Size::new(
+                        ConstraintLimit::Fill(
+                            (widths[column] - gutter).max(Px::ZERO).into_unsigned(),
+                        ),
+                        ConstraintLimit::SizeToFit(available_space.height.max()),
+                    ))
+                    .into_signed();
+                *height = (*height).max(size.height + gutter);
+            }
+        }
+
+        self.column_edges = edges(&widths);
+        self.row_edges = edges(&heights);
+        for ((row, &y), &height) in self.rows.iter_mut().zip(&self.row_edges).zip(&heights) {
+            for ((cell, &x), &width) in row.iter_mut().zip(&self.column_edges).zip(&widths) {
+                let cell = cell.mounted(&mut context.as_event_context());
+                let inner = Size::new(
+                    (width - gutter).max(Px::ZERO),
+                    (height - gutter).max(Px::ZERO),
+                );
+                context
+                    .for_other(&cell)
+                    .layout(inner.into_unsigned().map(ConstraintLimit::Fill));
+                context.set_child_layout(
+                    &cell,
+                    Rect::new(Point::new(x + padding, y + padding), inner),
+                );
+            }
+        }
+
+        Size::new(
+            self.column_edges.last().copied().unwrap_or(Px::ZERO),
+            self.row_edges.last().copied().unwrap_or(Px::ZERO),
+        )
+        .into_unsigned()
+    }
+
+    fn unmounted(&mut self, context: &mut EventContext<'_>) {
+        for cell in self.rows.iter_mut().flatten() {
+            cell.unmount_in(context);
+        }
+    }
+}
@@ -0,0 +1,685 @@
+use std::fmt;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::thread;
+
+use cushy::context::{EventContext, GraphicsContext, LayoutContext};
+use cushy::figures::units::{Px, UPx};
+use cushy::figures::{FloatConversion, IntoUnsigned, Point, Rect, ScreenScale, Size, Zero};
+use cushy::kludgine::app::winit::event::MouseButton;
+use cushy::kludgine::app::winit::keyboard::{Key, NamedKey};
+use cushy::kludgine::shapes::Shape;
+use cushy::kludgine::text::{Text, TextOrigin};
+use cushy::kludgine::DrawableExt;
+use cushy::styles::components::{TextColor, TextSize};
+use cushy::styles::{Color, FamilyOwned, Weight};
+use cushy::value::{Destination, Dynamic, Source};
+use cushy::widget::{EventHandling, Widget, HANDLED, IGNORED};
+use cushy::window::{DeviceId, KeyEvent};
+use cushy::ConstraintLimit;
+use portable_pty::{native_pty_system, Child, CommandBuilder, MasterPty, PtySize};
+use vte::{Params, Parser, Perform};
+
+use crate::code::monospace_cell;
+
+// Unbounded layouts would otherwise size the screen to the largest grid a PTY
+// supports. No slide can show more than this.
+const MAX_COLUMNS: f32 = 240.;
+const MAX_ROWS: f32 = 100.;
+
+#[derive(Debug, Clone)]
+pub struct TerminalCommand {
+    program: Option<String>,
+    args: Vec<String>,
+    cwd: Option<PathBuf>,
+}
+
+impl TerminalCommand {
+    pub fn new(program: Option<String>) -> Self {
+        Self {
+            program,
+            args: Vec::new(),
+            cwd: None,
+        }
+    }
+
+    pub fn arg(&mut self, arg: String) {
+        self.args.push(arg);
+    }
+
+    pub fn set_cwd(&mut self, cwd: PathBuf) {
+        self.cwd = Some(cwd);
+    }
+
+    fn builder(&self) -> CommandBuilder {
+        let mut builder = match &self.program {
+            Some(program) => {
+                let mut builder = CommandBuilder::new(program);
+                builder.args(&self.args);
+                builder
+            }
+            None => CommandBuilder::new_default_prog(),
+        };
+        if let Some(cwd) = &self.cwd {
+            builder.cwd(cwd);
+        }
+        builder.env("TERM", "xterm-256color");
+        builder
+    }
+}
+
+struct Session {
+    master: Box<dyn MasterPty + Send>,
+    writer: Box<dyn Write + Send>,
+    child: Box<dyn Child + Send + Sync>,
+}
+
+impl Session {
+    fn spawn(command: &TerminalCommand, screen: &Dynamic<Screen>) -> Result<Self, String> {
+        let size = screen.map_ref(|screen| PtySize {
+            cols: screen.columns as u16,
+            rows: screen.rows() as u16,
+            ..PtySize::default()
+        });
+        let pair = native_pty_system()
+            .openpty(size)
+            .map_err(|err| err.to_string())?;
+        let child = pair
+            .slave
+            .spawn_command(command.builder())
+            .map_err(|err| err.to_string())?;
+        // The slave end must be closed in this process for the reader to see
+        // the end of the stream once the child exits.
+        drop(pair.slave);
+
+        let mut reader = pair
+            .master
+            .try_clone_reader()
+            .map_err(|err| err.to_string())?;
+        let writer = pair.master.take_writer().map_err(|err| err.to_string())?;
+
+        thread::spawn({
+            let screen = screen.clone();
+            move || {
+                let mut parser = Parser::new();
+                let mut buffer = [0; 4096];
+                while let Ok(read @ 1..) = reader.read(&mut buffer) {
+                    screen.map_mut(|mut screen| {
+                        for byte in &buffer[..read] {
+                            parser.advance(&mut *screen, *byte);
+                        }
+                    });
+                }
+                screen.map_mut(|mut screen| screen.exited = true);
+            }
+        });
+
+        Ok(Self {
+            master: pair.master,
+            writer,
+            child,
+        })
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        // Reaping the child keeps it from lingering as a zombie process.
+        let _ = self.child.wait();
+    }
+}
+
+pub struct TerminalView {
+    command: TerminalCommand,
+    screen: Dynamic<Screen>,
+    // Slides are rebuilt every time they are shown, so the shell is only
+    // started once the terminal is focused rather than on every visit.
+    session: Option<Session>,
+    error: Option<String>,
+    error_color: Color,
+    error_background: Color,
+    cell: Size<Px>,
+    cached_text_size: Px,
+}
+
+impl TerminalView {
+    pub fn new(command: TerminalCommand) -> Self {
+        let size = PtySize::default();
+        Self {
+            command,
+            screen: Dynamic::new(Screen::new(usize::from(size.cols), usize::from(size.rows))),
+            session: None,
+            error: None,
+            error_color: Color::RED,
+            error_background: Color::BLACK,
+            cell: Size::ZERO,
+            cached_text_size: Px::ZERO,
+        }
+    }
+
+    pub fn error_colors(mut self, color: Color, background: Color) -> Self {
+        self.error_color = color;
+        self.error_background = background;
+        self
+    }
+
+    fn start(&mut self) {
+        if self.session.is_some() {
+            return;
+        }
+        match Session::spawn(&self.command, &self.screen) {
+            Ok(session) => {
+                self.session = Some(session);
+                self.error = None;
+            }
+            Err(err) => self.error = Some(format!("error starting terminal: {err}")),
+        }
+    }
+
+    fn send(&mut self, bytes: &[u8], context: &mut EventContext<'_>) {
+        let Some(session) = &mut self.session else {
+            return;
+        };
+        if let Err(err) = session
+            .writer
+            .write_all(bytes)
+            .and_then(|()| session.writer.flush())
+        {
+            self.error = Some(format!("error writing to terminal: {err}"));
+            context.set_needs_redraw();
+        }
+    }
+
+    fn draw_error(&self, context: &mut GraphicsContext<'_, '_, '_, '_>) {
+        let Some(error) = &self.error else {
+            return;
+        };
+        let text = context.gfx.measure_text(Text::new(error, self.error_color));
+        let size = context.gfx.size().into_signed();
+        let origin = Point::new(Px::ZERO, size.height - text.size.height);
+        context.gfx.draw_shape(&Shape::filled_rect(
+            Rect::new(origin, Size::new(size.width, text.size.height)),
+            self.error_background,
+        ));
+        context
+            .gfx
+            .draw_measured_text(text.translate_by(origin), TextOrigin::TopLeft);
+    }
+}
+
+impl fmt::Debug for TerminalView {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TerminalView")
+            .field("command", &self.command)
+            .field("screen", &self.screen)
+            .field("started", &self.session.is_some())
+            .field("error", &self.error)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Widget for TerminalView {
+    fn redraw(&mut self, context: &mut GraphicsContext<'_, '_, '_, '_>) {
+        context.redraw_when_changed(&self.screen);
+        let default_color = context.get(&TextColor);
+        context.gfx.set_font_family(FamilyOwned::Monospace);
+
+        let cell = self.cell;
+        let running = self.session.is_some();
+        self.screen.map_ref(|screen| {
+            for (row, cells) in screen.cells.iter().enumerate() {
+                let y = Px::from(row as f32 * cell.height.into_float());
+                let mut column = 0;
+                while column < cells.len() {
+                    let style = cells[column].style;
+                    let run = cells[column..]
+                        .iter()
+                        .take_while(|cell| cell.style == style)
+                        .map(|cell| cell.ch)
+                        .collect::<String>();
+                    let run_columns = run.chars().count();
+                    let origin = Point::new(Px::from(column as f32 * cell.width.into_float()), y);
+
+                    if let Some(background) = style.background {
+                        context.gfx.draw_shape(&Shape::filled_rect(
+                            Rect::new(
+                                origin,
+                                Size::new(
+                                    Px::from(run_columns as f32 * cell.width.into_float()),
+                                    cell.height,
+                                ),
+                            ),
+                            background,
+                        ));
+                    }
+                    if !run.trim().is_empty() {
+                        context.gfx.set_font_weight(if style.bold {
+                            Weight::BOLD
+                        } else {
+                            Weight::NORMAL
+                        });
+                        let text = context.gfx.measure_text(Text::new(
+                            &run,
+                            style.foreground.unwrap_or(default_color),
+                        ));
+                        context
+                            .gfx
+                            .draw_measured_text(text.translate_by(origin), TextOrigin::TopLeft);
+                    }
+                    column += run_columns;
+                }
+            }
+
+            if running && !screen.exited {
+                context.gfx.draw_shape(&Shape::filled_rect(
+                    Rect::new(
+                        Point::new(
+                            Px::from(screen.cursor.x as f32 * cell.width.into_float()),
+                            Px::from(screen.cursor.y as f32 * cell.height.into_float()),
+                        ),
+                        cell,
+                    ),
+                    default_color.with_alpha(0x80),
+                ));
+            }
+        });
+
+        if !running && self.error.is_none() {
+            let text = context.gfx.measure_text(Text::new(
+                "click to start the terminal",
+                default_color.with_alpha(0x80),
+            ));
+            context.gfx.draw_measured_text(text, TextOrigin::TopLeft);
+        }
+        self.draw_error(context);
+    }
+
+    fn layout(
+        &mut self,
+        available_space: Size<ConstraintLimit>,
+        context: &mut LayoutContext<'_, '_, '_, '_>,
+    ) -> Size<UPx> {
+        let text_size = context.get(&TextSize).into_px(context.gfx.scale());
+        if text_size != self.cached_text_size {
+            self.cached_text_size = text_size;
+            self.cell = monospace_cell(context);
+        }
+
+        let size = available_space.map(ConstraintLimit::max);
+        let columns = (size.width.into_float() / self.cell.width.into_float()).floor();
+        let rows = (size.height.into_float() / self.cell.height.into_float()).floor();
+        let grid = PtySize {
+            cols: columns.clamp(1., MAX_COLUMNS) as u16,
+            rows: rows.clamp(1., MAX_ROWS) as u16,
+            ..PtySize::default()
+        };
+        let resized = self
+            .screen
+            .map_mut(|mut screen| screen.resize(usize::from(grid.cols), usize::from(grid.rows)));
+        if let (true, Some(session)) = (resized, &self.session) {
+            if let Err(err) = session.master.resize(grid) {
+                self.error = Some(format!("error resizing terminal: {err}"));
+            }
+        }
+
+        Size::new(
+            Px::from(f32::from(grid.cols) * self.cell.width.into_float()),
+            Px::from(f32::from(grid.rows) * self.cell.height.into_float()),
+        )
+        .into_unsigned()
+    }
+
+    fn hit_test(&mut self, _location: Point<Px>, _context: &mut EventContext<'_>) -> bool {
+        true
+    }
+
+    fn accept_focus(&mut self, _context: &mut EventContext<'_>) -> bool {
+        true
+    }
+
+    fn focus(&mut self, context: &mut EventContext<'_>) {
+        self.start();
+        context.set_needs_redraw();
+    }
+
+    fn mouse_down(
+        &mut self,
+        _location: Point<Px>,
+        _device_id: DeviceId,
+        _button: MouseButton,
+        context: &mut EventContext<'_>,
+    ) -> EventHandling {
+        context.focus();
+        HANDLED
+    }
+
+    fn keyboard_input(
+        &mut self,
+        _device_id: DeviceId,
+        input: KeyEvent,
+        _is_synthetic: bool,
+        context: &mut EventContext<'_>,
+    ) -> EventHandling {
+        // While focused, every key belongs to the terminal so that keys like
+        // Space or the arrows don't also navigate between slides. Escape is
+        // needed by terminal programs, so Shift+Escape hands the keys back to
+        // the slides instead.
+        if input.logical_key == Key::Named(NamedKey::Escape)
+            && context.modifiers().state().shift_key()
+        {
+            if input.state.is_pressed() {
+                context.blur();
+            }
+            return HANDLED;
+        }
+
+        let bytes = match &input.logical_key {
+            Key::Named(named) => match named {
+                NamedKey::Enter => Some(b"\r".to_vec()),
+                NamedKey::Backspace => Some(b"\x7f".to_vec()),
+                NamedKey::Tab => Some(b"\t".to_vec()),
+                NamedKey::Escape => Some(b"\x1b".to_vec()),
+                NamedKey::ArrowUp => Some(b"\x1b[A".to_vec()),
+                NamedKey::ArrowDown => Some(b"\x1b[B".to_vec()),
+                NamedKey::ArrowRight => Some(b"\x1b[C".to_vec()),
+                NamedKey::ArrowLeft => Some(b"\x1b[D".to_vec()),
+                NamedKey::Home => Some(b"\x1b[H".to_vec()),
+                NamedKey::End => Some(b"\x1b[F".to_vec()),
+                NamedKey::Delete => Some(b"\x1b[3~".to_vec()),
+                NamedKey::PageUp => Some(b"\x1b[5~".to_vec()),
+                NamedKey::PageDown => Some(b"\x1b[6~".to_vec()),
+                _ => None,
+            },
+            Key::Character(ch) if context.modifiers().state().control_key() => {
+                match ch.as_bytes() {
+                    [ch @ (b'@'..=b'_' | b'a'..=b'z')] => {
+                        Some(vec![ch.to_ascii_uppercase() - b'@'])
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+        .or_else(|| input.text.as_ref().map(|text| text.as_bytes().to_vec()));
+
+        match bytes {
+            Some(bytes) => {
+                if input.state.is_pressed() {
+                    self.send(&bytes, context);
+                }
+                HANDLED
+            }
+            None => IGNORED,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+struct CellStyle {
+    foreground: Option<Color>,
+    background: Option<Color>,
+    bold: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Cell {
+    ch: char,
+    style: CellStyle,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self {
+            ch: ' ',
+            style: CellStyle::default(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Screen {
+    cells: Vec<Vec<Cell>>,
+    columns: usize,
+    cursor: Point<usize>,
+    style: CellStyle,
+    exited: bool,
+}
+
+impl Screen {
+    fn new(columns: usize, rows: usize) -> Self {
+        Self {
+            cells: vec![vec![Cell::default(); columns]; rows],
+            columns,
+            cursor: Point::new(0, 0),
+            style: CellStyle::default(),
+            exited: false,
+        }
+    }
+
+    fn rows(&self) -> usize {
+        self.cells.len()
+    }
+
+    fn resize(&mut self, columns: usize, rows: usize) -> bool {
+        if columns == self.columns && rows == self.rows() {
+            return false;
+        }
+
+        // Keep the bottom of the screen, where the prompt usually is.
+        if rows < self.rows() {
+            let removed = self.rows() - rows;
+            self.cells.drain(..removed);
+            self.cursor.y = self.cursor.y.saturating_sub(removed);
+        } else {
+            self.cells.resize(rows, vec![Cell::default(); columns]);
+        }
+        for row in &mut self.cells {
+            row.resize(columns, Cell::default());
+        }
+        self.columns = columns;
+        self.cursor.x = self.cursor.x.min(columns.saturating_sub(1));
+        self.cursor.y = self.cursor.y.min(rows.saturating_sub(1));
+        true
+    }
+
+    fn line_feed(&mut self) {
+        if self.cursor.y + 1 < self.rows() {
+            self.cursor.y += 1;
+        } else {
+            self.cells.remove(0);
+            self.cells.push(vec![Cell::default(); self.columns]);
+        }
+    }
+
+    fn clear(
+        &mut self,
+        rows: impl Iterator<Item = usize>,
+        columns: impl Fn(usize) -> (usize, usize),
+    ) {
+        for row in rows {
+            let (start, end) = columns(row);
+            if let Some(cells) = self.cells.get_mut(row) {
+                let end = end.min(cells.len());
+                for cell in &mut cells[start.min(end)..end] {
+                    *cell = Cell::default();
+                }
+            }
+        }
+    }
+
+    fn select_graphic_rendition(&mut self, params: &Params) {
+        let params = params.iter().flatten().copied().collect::<Vec<u16>>();
+        if params.is_empty() {
+            self.style = CellStyle::default();
+            return;
+        }
+
+        let mut params = params.into_iter();
+        while let Some(param) = params.next() {
+            match param {
+                0 => self.style = CellStyle::default(),
+                1 => self.style.bold = true,
+                22 => self.style.bold = false,
+                30..=37 => self.style.foreground = Some(ansi_color(param - 30)),
+                39 => self.style.foreground = None,
+                40..=47 => self.style.background = Some(ansi_color(param - 40)),
+                49 => self.style.background = None,
+                90..=97 => self.style.foreground = Some(ansi_color(param - 90 + 8)),
+                100..=107 => self.style.background = Some(ansi_color(param - 100 + 8)),
+                38 | 48 => {
+                    let color = match params.next() {
+                        Some(5) => params.next().map(ansi_color),
+                        Some(2) => match (params.next(), params.next(), params.next()) {
+                            (Some(r), Some(g), Some(b)) => {
+                                Some(Color::new(r as u8, g as u8, b as u8, 255))
+                            }
+                            _ => None,
+                        },
+                        _ => None,
+                    };
+                    if param == 38 {
+                        self.style.foreground = color;
+                    } else {
+                        self.style.background = color;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Perform for Screen {
+    fn print(&mut self, ch: char) {
+        if self.cursor.x >= self.columns {
+            self.cursor.x = 0;
+            self.line_feed();
+        }
+        let style = self.style;
+        if let Some(cell) = self
+            .cells
+            .get_mut(self.cursor.y)
+            .and_then(|row| row.get_mut(self.cursor.x))
+        {
+            *cell = Cell { ch, style };
+        }
+        self.cursor.x += 1;
+    }
+
+    fn execute(&mut self, byte: u8) {
+        match byte {
+            b'\n' | 0x0b | 0x0c => self.line_feed(),
+            b'\r' => self.cursor.x = 0,
+            0x08 => self.cursor.x = self.cursor.x.saturating_sub(1),
+            b'\t' => {
+                self.cursor.x = ((self.cursor.x / 8 + 1) * 8).min(self.columns.saturating_sub(1))
+            }
+            _ => {}
+        }
+    }
+
+    fn csi_dispatch(&mut self, params: &Params, intermediates: &[u8], _ignore: bool, action: char) {
+        if !intermediates.is_empty() {
+            return;
+        }
+
+        let values = params
+            .iter()
+            .map(|param| usize::from(param[0]))
+            .collect::<Vec<_>>();
+        let param = |index: usize, default: usize| {
+            values
+                .get(index)
+                .copied()
+                .filter(|value| *value > 0)
+                .unwrap_or(default)
+        };
+        let mode = values.first().copied().unwrap_or(0);
+        let last_row = self.rows().saturating_sub(1);
+        let last_column = self.columns.saturating_sub(1);
+        match action {
+            'A' => self.cursor.y = self.cursor.y.saturating_sub(param(0, 1)),
+            'B' => self.cursor.y = (self.cursor.y + param(0, 1)).min(last_row),
+            'C' => self.cursor.x = (self.cursor.x + param(0, 1)).min(last_column),
+            'D' => self.cursor.x = self.cursor.x.saturating_sub(param(0, 1)),
+            'G' => self.cursor.x = (param(0, 1) - 1).min(last_column),
+            'H' | 'f' => {
+                self.cursor.y = (param(0, 1) - 1).min(last_row);
+                self.cursor.x = (param(1, 1) - 1).min(last_column);
+            }
+            'J' => {
+                let cursor = self.cursor;
+                let columns = self.columns;
+                match mode {
+                    0 => self.clear(cursor.y..=last_row, |row| {
+                        (if row == cursor.y { cursor.x } else { 0 }, columns)
+                    }),
+                    1 => self.clear(0..=cursor.y, |row| {
+                        (
+                            0,
+                            if row == cursor.y {
+                                cursor.x + 1
+                            } else {
+                                columns
+                            },
+                        )
+                    }),
+                    _ => self.clear(0..=last_row, |_| (0, columns)),
+                }
+            }
+            'K' => {
+                let cursor = self.cursor;
+                let columns = self.columns;
+                match mode {
+                    0 => self.clear(cursor.y..=cursor.y, |_| (cursor.x, columns)),
+                    1 => self.clear(cursor.y..=cursor.y, |_| (0, cursor.x + 1)),
+                    _ => self.clear(cursor.y..=cursor.y, |_| (0, columns)),
+                }
+            }
+            'm' => self.select_graphic_rendition(params),
+            _ => {}
+        }
+    }
+}
+
+fn ansi_color(index: u16) -> Color {
+    const STANDARD: [(u8, u8, u8); 16] = [
+        (0x00, 0x00, 0x00),
+        (0xcd, 0x31, 0x31),
+        (0x0d, 0xbc, 0x79),
+        (0xe5, 0xe5, 0x10),
+        (0x24, 0x72, 0xc8),
+        (0xbc, 0x3f, 0xbc),
+        (0x11, 0xa8, 0xcd),
+        (0xe5, 0xe5, 0xe5),
+        (0x66, 0x66, 0x66),
+        (0xf1, 0x4c, 0x4c),
+        (0x23, 0xd1, 0x8b),
+        (0xf5, 0xf5, 0x43),
+        (0x3b, 0x8e, 0xea),
+        (0xd6, 0x70, 0xd6),
+        (0x29, 0xb8, 0xdb),
+        (0xff, 0xff, 0xff),
+    ];
+
+    let (r, g, b) = match index {
+        0..=15 => STANDARD[usize::from(index)],
+        16..=231 => {
+            let index = index - 16;
+            let level = |value: u16| {
+                if value == 0 {
+                    0
+                } else {
+                    (55 + value * 40) as u8
+                }
+            };
+            (level(index / 36), level(index / 6 % 6), level(index % 6))
+        }
+        _ => {
+            let gray = (8 + index.saturating_sub(232).min(23) * 10) as u8;
+            (gray, gray, gray)
+        }
+    };
+    Color::new(r, g, b, 255)
+}
@@ -0,0 +1,507 @@
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use cushy::context::{EventContext, GraphicsContext, LayoutContext};
+use cushy::figures::units::{Px, UPx};
+use cushy::figures::{IntoSigned, Point, Rect, Size, Zero};
+use cushy::kludgine::app::winit::event::MouseButton;
+use cushy::kludgine::app::winit::keyboard::{Key, NamedKey};
+use cushy::kludgine::shapes::Shape;
+use cushy::kludgine::text::{Text, TextOrigin};
+use cushy::kludgine::{wgpu, Graphics, LazyTexture, Texture};
+use cushy::styles::Color;
+use cushy::value::{Destination, Dynamic};
+use cushy::widget::{EventHandling, Widget, HANDLED, IGNORED};
+use cushy::window::{DeviceId, KeyEvent};
+use cushy::ConstraintLimit;
+
+use crate::image::aspect_fit;
+
+const SEEK_STEP: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct VideoInfo {
+    size: Size<UPx>,
+    frame_rate: f64,
+    duration: Option<Duration>,
+}
+
+pub(crate) fn probe(path: &Path) -> Result<VideoInfo, String> {
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-select_streams", "v:0", "-show_entries"])
+        .arg("stream=width,height,r_frame_rate:format=duration")
+        .args(["-of", "default=noprint_wrappers=1"])
+        .arg(path)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|err| format!("error running ffprobe (install ffmpeg to play videos): {err}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let mut width = 0;
+    let mut height = 0;
+    let mut frame_rate = 30.;
+    let mut duration = None;
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        match line.split_once('=') {
+            Some(("width", value)) => width = value.parse().unwrap_or_default(),
+            Some(("height", value)) => height = value.parse().unwrap_or_default(),
+            Some(("r_frame_rate", value)) => {
+                if let Some((numerator, denominator)) = value.split_once('/') {
+                    let numerator = numerator.parse::<f64>().unwrap_or_default();
+                    let denominator = denominator.parse::<f64>().unwrap_or_default();
+                    if numerator > 0. && denominator > 0. {
+                        frame_rate = numerator / denominator;
+                    }
+                }
+            }
+            Some(("duration", value)) => {
+                duration = value.parse::<f64>().ok().map(Duration::from_secs_f64);
+            }
+            _ => {}
+        }
+    }
+
+    if width == 0 || height == 0 {
+        return Err(format!("{}: no video stream", path.display()));
+    }
+
+    Ok(VideoInfo {
+        size: Size::new(UPx::new(width), UPx::new(height)),
+        frame_rate,
+        duration,
+    })
+}
+
+// Probing only finds `ffprobe`, so whether `ffmpeg` can be run is checked
+// separately, once.
+pub(crate) fn check_ffmpeg() -> Result<(), String> {
+    static CHECKED: OnceLock<Result<(), String>> = OnceLock::new();
+    CHECKED
+        .get_or_init(|| {
+            Command::new("ffmpeg")
+                .arg("-version")
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .map(|_| ())
+                .map_err(|err| {
+                    format!("error running ffmpeg (install ffmpeg to play videos): {err}")
+                })
+        })
+        .clone()
+}
+
+// Decoded frames are handed to the view in buffers that are returned once
+// uploaded, so playback doesn't allocate a new buffer for every frame.
+#[derive(Debug, Clone, Default)]
+struct Frames {
+    latest: Arc<Mutex<Option<Vec<u8>>>>,
+    spare: Arc<Mutex<Vec<Vec<u8>>>>,
+}
+
+impl Frames {
+    // Each decoder publishes into its own slot, so a frame decoded before a
+    // seek can never be shown after it. The spare buffers are still shared.
+    fn for_decoder(&self) -> Self {
+        Self {
+            latest: Arc::default(),
+            spare: self.spare.clone(),
+        }
+    }
+
+    fn spare(&self, length: usize) -> Vec<u8> {
+        match self.spare.lock().expect("poisoned").pop() {
+            Some(buffer) if buffer.len() == length => buffer,
+            _ => vec![0; length],
+        }
+    }
+
+    fn recycle(&self, buffer: Vec<u8>) {
+        self.spare.lock().expect("poisoned").push(buffer);
+    }
+
+    fn publish(&self, buffer: Vec<u8>) {
+        let replaced = self.latest.lock().expect("poisoned").replace(buffer);
+        if let Some(replaced) = replaced {
+            self.recycle(replaced);
+        }
+    }
+
+    fn take(&self) -> Option<Vec<u8>> {
+        self.latest.lock().expect("poisoned").take()
+    }
+}
+
+#[derive(Debug)]
+struct Decoder {
+    child: Child,
+    stop: Arc<AtomicBool>,
+}
+
+impl Decoder {
+    fn spawn(
+        path: &Path,
+        info: VideoInfo,
+        start: Duration,
+        single_frame: bool,
+        frames: Frames,
+        frame_number: Dynamic<usize>,
+    ) -> Result<Self, String> {
+        let mut command = Command::new("ffmpeg");
+        command
+            .args(["-v", "error", "-ss"])
+            .arg(format!("{:.3}", start.as_secs_f64()))
+            .arg("-i")
+            .arg(path);
+        if single_frame {
+            command.args(["-frames:v", "1"]);
+        }
+        let mut child = command
+            .args(["-f", "rawvideo", "-pix_fmt", "rgba", "-"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|err| {
+                format!("error running ffmpeg (install ffmpeg to play videos): {err}")
+            })?;
+        let mut stdout = child.stdout.take().expect("stdout is piped");
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let thread_stop = stop.clone();
+        thread::spawn(move || {
+            let length = info.size.width.get() as usize * info.size.height.get() as usize * 4;
+            let frame_duration = Duration::from_secs_f64(1. / info.frame_rate);
+            let started = Instant::now();
+            let mut index = 0;
+            while !thread_stop.load(Ordering::Relaxed) {
+                let mut buffer = frames.spare(length);
+                if stdout.read_exact(&mut buffer).is_err() {
+                    break;
+                }
+                let due = started + frame_duration * index;
+                let now = Instant::now();
+                index += 1;
+                if now < due {
+                    thread::sleep(due - now);
+                } else if now > due + frame_duration {
+                    // Decoding fell behind, so skip this frame to catch up.
+                    frames.recycle(buffer);
+                    continue;
+                }
+
+                frames.publish(buffer);
+                frame_number.map_mut(|mut number| *number += 1);
+            }
+        });
+
+        Ok(Self { child, stop })
+    }
+
+    fn stop(mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        let _ = self.child.kill();
+        thread::spawn(move || self.child.wait());
+    }
+}
+
+#[derive(Debug)]
+pub struct VideoView {
+    path: PathBuf,
+    info: VideoInfo,
+    poster: Option<LazyTexture>,
+    frames: Frames,
+    texture: Option<Texture>,
+    frame_number: Dynamic<usize>,
+    decoder: Option<Decoder>,
+    position: Duration,
+    playing_since: Option<Instant>,
+    size: Size<UPx>,
+    error: Option<String>,
+    error_color: Color,
+    error_background: Color,
+}
+
+impl VideoView {
+    pub fn new(
+        path: PathBuf,
+        info: VideoInfo,
+        poster: Option<LazyTexture>,
+        autoplay: bool,
+    ) -> Self {
+        let mut view = Self {
+            path,
+            info,
+            poster,
+            frames: Frames::default(),
+            texture: None,
+            frame_number: Dynamic::default(),
+            decoder: None,
+            position: Duration::ZERO,
+            playing_since: None,
+            size: Size::ZERO,
+            error: None,
+            error_color: Color::RED,
+            error_background: Color::BLACK,
+        };
+        if autoplay {
+            view.play();
+        } else if view.poster.is_none() {
+            view.show_frame();
+        }
+        view
+    }
+
+    pub fn error_colors(mut self, color: Color, background: Color) -> Self {
+        self.error_color = color;
+        self.error_background = background;
+        self
+    }
+
+    fn position(&self) -> Duration {
+        let position = self.position
+            + self
+                .playing_since
+                .map_or(Duration::ZERO, |since| since.elapsed());
+        self.info
+            .duration
+            .map_or(position, |duration| position.min(duration))
+    }
+
+    fn spawn(&mut self, single_frame: bool) {
+        if let Some(decoder) = self.decoder.take() {
+            decoder.stop();
+        }
+        self.frames = self.frames.for_decoder();
+        match Decoder::spawn(
+            &self.path,
+            self.info,
+            self.position,
+            single_frame,
+            self.frames.clone(),
+            self.frame_number.clone(),
+        ) {
+            Ok(decoder) => {
+                self.decoder = Some(decoder);
+                self.error = None;
+            }
+            Err(err) => {
+                self.playing_since = None;
+                self.error = Some(err);
+            }
+        }
+        // Redrawing shows the new error, or clears the previous one.
+        self.frame_number.map_mut(|mut number| *number += 1);
+    }
+
+    fn upload(&mut self, graphics: &Graphics<'_>, frame: &[u8]) {
+        // Every frame has the video's size, so one texture is created and each
+        // frame is written into it.
+        let Some(texture) = &self.texture else {
+            self.texture = Some(Texture::new_with_data(
+                graphics,
+                self.info.size,
+                wgpu::TextureFormat::Rgba8UnormSrgb,
+                wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                wgpu::FilterMode::Linear,
+                frame,
+            ));
+            return;
+        };
+        graphics.queue().write_texture(
+            wgpu::ImageCopyTexture {
+                texture: texture.wgpu(),
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            frame,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(self.info.size.width.get() * 4),
+                rows_per_image: None,
+            },
+            wgpu::Extent3d {
+                width: self.info.size.width.get(),
+                height: self.info.size.height.get(),
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+
+    fn draw_error(&self, context: &mut GraphicsContext<'_, '_, '_, '_>) {
+        let Some(error) = &self.error else {
+            return;
+        };
+        let text = context.gfx.measure_text(Text::new(error, self.error_color));
+        let size = self.size.into_signed();
+        let origin = Point::new(Px::ZERO, size.height - text.size.height);
+        context.gfx.draw_shape(&Shape::filled_rect(
+            Rect::new(origin, Size::new(size.width, text.size.height)),
+            self.error_background,
+        ));
+        context
+            .gfx
+            .draw_measured_text(text.translate_by(origin), TextOrigin::TopLeft);
+    }
+
+    fn play(&mut self) {
+        if self
+            .info
+            .duration
+            .map_or(false, |duration| self.position >= duration)
+        {
+            self.position = Duration::ZERO;
+        }
+        self.spawn(false);
+        self.playing_since = Some(Instant::now());
+    }
+
+    fn pause(&mut self) {
+        self.position = self.position();
+        self.playing_since = None;
+        if let Some(decoder) = self.decoder.take() {
+            decoder.stop();
+        }
+    }
+
+    fn show_frame(&mut self) {
+        self.spawn(true);
+    }
+
+    fn toggle(&mut self) {
+        if self.playing_since.is_some() {
+            self.pause();
+        } else {
+            self.play();
+        }
+    }
+
+    fn seek(&mut self, position: Duration) {
+        let playing = self.playing_since.is_some();
+        self.pause();
+        self.position = self
+            .info
+            .duration
+            .map_or(position, |duration| position.min(duration));
+        if playing {
+            self.play();
+        } else {
+            self.show_frame();
+        }
+    }
+}
+
+impl Drop for VideoView {
+    fn drop(&mut self) {
+        if let Some(decoder) = self.decoder.take() {
+            decoder.stop();
+        }
+    }
+}
+
+impl Widget for VideoView {
+    fn redraw(&mut self, context: &mut GraphicsContext<'_, '_, '_, '_>) {
+        context.redraw_when_changed(&self.frame_number);
+        if let Some(frame) = self.frames.take() {
+            self.upload(context.gfx.inner_graphics(), &frame);
+            self.frames.recycle(frame);
+        }
+
+        let area = Rect::new(Point::<Px>::ZERO, self.size.into_signed());
+        if let Some(texture) = &self.texture {
+            context.gfx.draw_texture(texture, area);
+        } else if let Some(poster) = &self.poster {
+            context.gfx.draw_texture(poster, area);
+        }
+        self.draw_error(context);
+    }
+
+    fn layout(
+        &mut self,
+        available_space: Size<ConstraintLimit>,
+        _context: &mut LayoutContext<'_, '_, '_, '_>,
+    ) -> Size<UPx> {
+        self.size = aspect_fit(self.info.size, available_space);
+        self.size
+    }
+
+    fn hit_test(&mut self, _location: Point<Px>, _context: &mut EventContext<'_>) -> bool {
+        true
+    }
+
+    fn accept_focus(&mut self, _context: &mut EventContext<'_>) -> bool {
+        true
+    }
+
+    fn mouse_down(
+        &mut self,
+        _location: Point<Px>,
+        _device_id: DeviceId,
+        button: MouseButton,
+        context: &mut EventContext<'_>,
+    ) -> EventHandling {
+        if button != MouseButton::Left {
+            return IGNORED;
+        }
+        context.focus();
+        self.toggle();
+        HANDLED
+    }
+
+    fn keyboard_input(
+        &mut self,
+        _device_id: DeviceId,
+        input: KeyEvent,
+        _is_synthetic: bool,
+        context: &mut EventContext<'_>,
+    ) -> EventHandling {
+        if !context.modifiers().state().is_empty() {
+            return IGNORED;
+        }
+
+        // While focused, the navigation keys control playback instead of
+        // changing slides. Escape hands the keys back to the slides.
+        match input.logical_key {
+            Key::Named(NamedKey::Space) => {
+                if input.state.is_pressed() {
+                    self.toggle();
+                }
+            }
+            Key::Named(NamedKey::ArrowLeft) => {
+                if input.state.is_pressed() {
+                    self.seek(self.position().saturating_sub(SEEK_STEP));
+                }
+            }
+            Key::Named(NamedKey::ArrowRight) => {
+                if input.state.is_pressed() {
+                    self.seek(self.position() + SEEK_STEP);
+                }
+            }
+            Key::Named(NamedKey::Home) => {
+                if input.state.is_pressed() {
+                    self.seek(Duration::ZERO);
+                }
+            }
+            Key::Named(NamedKey::Escape) => {
+                if input.state.is_pressed() {
+                    context.blur();
+                }
+            }
+            _ => return IGNORED,
+        }
+        HANDLED
+    }
+}